@@ -0,0 +1,427 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::{
+    rules::{AlertCondition, AlertRule},
+    sinks::Sink,
+};
+use crate::{models::position::Position, network::limit_orders::LimitOrder};
+
+/// An alert raised by a rule for a specific subject (a position, pair or order)
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub rule: String,
+    pub subject: String,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+    pub sinks: Vec<Sink>,
+}
+
+/// Evaluates [`AlertRule`]s against fresh position and limit order data.
+///
+/// Alerts are edge triggered: a rule fires for a subject when its condition becomes
+/// true and re-arms once the condition is false again, so a position that stays out
+/// of range only alerts once. A subject that flaps in and out of the condition is
+/// additionally held back until the rule's cooldown has elapsed, and fires then if
+/// the condition still holds.
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    active: HashSet<(usize, String)>,
+    last_fired: HashMap<(usize, String), DateTime<Utc>>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> AlertEngine {
+        AlertEngine {
+            rules,
+            active: HashSet::new(),
+            last_fired: HashMap::new(),
+        }
+    }
+
+    /// Whether a rule needs the open limit orders
+    pub fn watches_limit_orders(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| matches!(rule.condition, AlertCondition::LimitOrder { .. }))
+    }
+
+    pub fn evaluate(
+        &mut self,
+        positions: &[Position],
+        limit_orders: &[LimitOrder],
+        eth_price_usd: f64,
+    ) -> Vec<Alert> {
+        self.evaluate_at(Utc::now(), positions, limit_orders, eth_price_usd)
+    }
+
+    fn evaluate_at(
+        &mut self,
+        now: DateTime<Utc>,
+        positions: &[Position],
+        limit_orders: &[LimitOrder],
+        eth_price_usd: f64,
+    ) -> Vec<Alert> {
+        let mut triggered = HashSet::new();
+        let mut alerts = Vec::new();
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            for (subject, message) in
+                matches(&rule.condition, positions, limit_orders, eth_price_usd)
+            {
+                let key = (index, subject.clone());
+                if self.active.contains(&key) {
+                    triggered.insert(key);
+                    continue;
+                }
+                // Held back matches stay armed, so they fire once the cooldown is over
+                let cooling_down = self.last_fired.get(&key).is_some_and(|fired| {
+                    now - *fired < Duration::seconds(rule.cooldown_secs as i64)
+                });
+                if cooling_down {
+                    continue;
+                }
+                self.last_fired.insert(key.clone(), now);
+                alerts.push(Alert {
+                    rule: rule.name.clone(),
+                    subject,
+                    message,
+                    timestamp: now,
+                    sinks: rule.sinks.clone(),
+                });
+                triggered.insert(key);
+            }
+        }
+
+        self.active = triggered;
+        alerts
+    }
+}
+
+/// Returns the `(subject, message)` of every item currently satisfying `condition`
+fn matches(
+    condition: &AlertCondition,
+    positions: &[Position],
+    limit_orders: &[LimitOrder],
+    eth_price_usd: f64,
+) -> Vec<(String, String)> {
+    match condition {
+        AlertCondition::OutOfRange { pair } => for_pair(positions, pair)
+            .filter(|pos| pos.is_in_range() == Some(false))
+            .map(|pos| {
+                (
+                    position_subject(pos),
+                    format!("{} #{} is out of range", pos.pair(), pos.id),
+                )
+            })
+            .collect(),
+        AlertCondition::PriceAbove { pair, price } | AlertCondition::PriceBelow { pair, price } => {
+            let above = matches!(condition, AlertCondition::PriceAbove { .. });
+            let Some((base, quote)) = pair.split_once('/') else {
+                return Vec::new();
+            };
+            positions
+                .iter()
+                .find_map(|pos| pos.price_of(base.trim(), quote.trim()))
+                .filter(|current| {
+                    if above {
+                        current > price
+                    } else {
+                        current < price
+                    }
+                })
+                .map(|current| {
+                    vec![(
+                        pair.to_uppercase(),
+                        format!(
+                            "{} is {} {} (now {:.4})",
+                            pair,
+                            if above { "above" } else { "below" },
+                            price,
+                            current
+                        ),
+                    )]
+                })
+                .unwrap_or_default()
+        }
+        AlertCondition::FeesAbove { usd, pair } => for_pair(positions, pair)
+            .filter_map(|pos| {
+                let fees = pos.uncollected_fees_usd(eth_price_usd)?;
+                (fees > *usd).then(|| {
+                    (
+                        position_subject(pos),
+                        format!(
+                            "{} #{} has ${:.2} in uncollected fees",
+                            pos.pair(),
+                            pos.id,
+                            fees
+                        ),
+                    )
+                })
+            })
+            .collect(),
+        AlertCondition::LimitOrder { token } => limit_orders
+            .iter()
+            .filter(|order| order.token.eq_ignore_ascii_case(token))
+            .map(|order| {
+                (
+                    format!("{}:{}:{}", order.token, order.deadline, order.start_amount),
+                    format!(
+                        "New limit order: {} {} -> {}",
+                        order.token, order.start_amount, order.end_amount
+                    ),
+                )
+            })
+            .collect(),
+    }
+}
+
+fn for_pair<'a>(
+    positions: &'a [Position],
+    pair: &'a Option<String>,
+) -> impl Iterator<Item = &'a Position> {
+    positions
+        .iter()
+        .filter(move |pos| pair.as_ref().is_none_or(|p| pos.matches_pair(p)))
+}
+
+fn position_subject(position: &Position) -> String {
+    if position.id.is_empty() {
+        position.pair()
+    } else {
+        position.id.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// 2^128, a fee growth of one raw token per unit of liquidity
+    const Q128: &str = "340282366920938463463374607431768211456";
+
+    /// A WETH/USDC position over ticks `[0, 100)` with `liquidity` owed `fee_growth`
+    /// of WETH per unit since it was last touched
+    fn position(id: &str, tick: i32, price: &str, liquidity: &str, fee_growth: &str) -> Position {
+        let token = |symbol: &str| json!({ "name": symbol, "symbol": symbol, "decimals": "18", "derivedETH": "1" });
+        let tick_of = |idx: &str| json!({ "tickIdx": idx, "feeGrowthOutside0X128": "0", "feeGrowthOutside1X128": "0" });
+        serde_json::from_value(json!({
+            "id": id,
+            "token0": token("WETH"),
+            "token1": token("USDC"),
+            "pool": {
+                "tick": tick.to_string(),
+                "token0Price": "0.0005",
+                "token1Price": price,
+                "feeGrowthGlobal0X128": fee_growth,
+                "feeGrowthGlobal1X128": "0",
+            },
+            "tickLower": tick_of("0"),
+            "tickUpper": tick_of("100"),
+            "withdrawnToken0": "0",
+            "withdrawnToken1": "0",
+            "depositedToken0": "1",
+            "depositedToken1": "2000",
+            "feeGrowthInside0LastX128": "0",
+            "feeGrowthInside1LastX128": "0",
+            "liquidity": liquidity,
+        }))
+        .unwrap()
+    }
+
+    fn at_tick(tick: i32) -> Position {
+        position("1", tick, "2000", "0", "0")
+    }
+
+    fn rule(condition: AlertCondition, cooldown_secs: u64) -> AlertRule {
+        AlertRule {
+            name: "rule".to_owned(),
+            condition,
+            cooldown_secs,
+            sinks: vec![],
+        }
+    }
+
+    fn order(token: &str) -> LimitOrder {
+        serde_json::from_value(json!({
+            "token": token,
+            "deadline": "1700000000",
+            "start_amount": "1",
+            "end_amount": "2",
+            "value_usd": "100",
+            "market_cap_usd": "0",
+            "volume_24h": "0",
+        }))
+        .unwrap()
+    }
+
+    fn subjects(alerts: &[Alert]) -> Vec<&str> {
+        alerts.iter().map(|alert| alert.subject.as_str()).collect()
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut engine = AlertEngine::new(vec![rule(AlertCondition::OutOfRange { pair: None }, 0)]);
+        let positions = [at_tick(50), position("2", 100, "2000", "0", "0")];
+        let alerts = engine.evaluate(&positions, &[], 2000.0);
+        assert_eq!(subjects(&alerts), ["2"]);
+        assert_eq!(alerts[0].message, "WETH/USDC #2 is out of range");
+    }
+
+    #[test]
+    fn out_of_range_of_pair() {
+        let condition = AlertCondition::OutOfRange {
+            pair: Some("WBTC".to_owned()),
+        };
+        let mut engine = AlertEngine::new(vec![rule(condition, 0)]);
+        assert!(engine.evaluate(&[at_tick(-10)], &[], 2000.0).is_empty());
+    }
+
+    #[test]
+    fn price_above_and_below() {
+        let above = |price| AlertCondition::PriceAbove {
+            pair: "WETH/USDC".to_owned(),
+            price,
+        };
+        let below = |price| AlertCondition::PriceBelow {
+            pair: "weth/usdc".to_owned(),
+            price,
+        };
+        let positions = [at_tick(50)];
+        for (condition, fires) in [
+            (above(1999.0), true),
+            (above(2000.0), false),
+            (below(2001.0), true),
+            (below(2000.0), false),
+        ] {
+            let mut engine = AlertEngine::new(vec![rule(condition, 0)]);
+            assert_eq!(!engine.evaluate(&positions, &[], 2000.0).is_empty(), fires);
+        }
+    }
+
+    #[test]
+    fn price_of_inverted_pair() {
+        let condition = AlertCondition::PriceAbove {
+            pair: "USDC/WETH".to_owned(),
+            price: 0.0004,
+        };
+        let mut engine = AlertEngine::new(vec![rule(condition, 0)]);
+        let alerts = engine.evaluate(&[at_tick(50)], &[], 2000.0);
+        assert_eq!(subjects(&alerts), ["USDC/WETH"]);
+        assert_eq!(alerts[0].message, "USDC/WETH is above 0.0004 (now 0.0005)");
+    }
+
+    #[test]
+    fn fees_above() {
+        // 10^18 liquidity owed a growth of 2^128 is one WETH, $2000 at a derived ETH of 1
+        let earning = position("1", 50, "2000", "1000000000000000000", Q128);
+        let mut engine = AlertEngine::new(vec![
+            rule(
+                AlertCondition::FeesAbove {
+                    usd: 1999.0,
+                    pair: None,
+                },
+                0,
+            ),
+            rule(
+                AlertCondition::FeesAbove {
+                    usd: 2001.0,
+                    pair: None,
+                },
+                0,
+            ),
+        ]);
+        let alerts = engine.evaluate(&[earning, at_tick(50)], &[], 2000.0);
+        assert_eq!(subjects(&alerts), ["1"]);
+        assert_eq!(
+            alerts[0].message,
+            "WETH/USDC #1 has $2000.00 in uncollected fees"
+        );
+    }
+
+    #[test]
+    fn limit_order_fill() {
+        let condition = AlertCondition::LimitOrder {
+            token: "pepe".to_owned(),
+        };
+        let mut engine = AlertEngine::new(vec![rule(condition, 0)]);
+        let alerts = engine.evaluate(&[], &[order("PEPE"), order("WETH")], 2000.0);
+        assert_eq!(subjects(&alerts), ["PEPE:1700000000:1"]);
+        assert_eq!(alerts[0].message, "New limit order: PEPE 1 -> 2");
+    }
+
+    #[test]
+    fn watches_limit_orders_only_with_a_rule_for_them() {
+        let out_of_range = rule(AlertCondition::OutOfRange { pair: None }, 0);
+        let limit_order = rule(
+            AlertCondition::LimitOrder {
+                token: "PEPE".to_owned(),
+            },
+            0,
+        );
+        assert!(!AlertEngine::new(vec![out_of_range.clone()]).watches_limit_orders());
+        assert!(AlertEngine::new(vec![out_of_range, limit_order]).watches_limit_orders());
+    }
+
+    #[test]
+    fn rearms_after_condition_clears() {
+        let mut engine = AlertEngine::new(vec![rule(AlertCondition::OutOfRange { pair: None }, 0)]);
+        assert_eq!(engine.evaluate(&[at_tick(-10)], &[], 2000.0).len(), 1);
+        assert!(engine.evaluate(&[at_tick(-20)], &[], 2000.0).is_empty());
+        assert!(engine.evaluate(&[at_tick(50)], &[], 2000.0).is_empty());
+        assert_eq!(engine.evaluate(&[at_tick(150)], &[], 2000.0).len(), 1);
+    }
+
+    #[test]
+    fn fires_once_per_subject_while_condition_holds() {
+        let mut engine = AlertEngine::new(vec![rule(AlertCondition::OutOfRange { pair: None }, 0)]);
+        let out = [at_tick(-10), position("2", 150, "2000", "0", "0")];
+        assert_eq!(subjects(&engine.evaluate(&out, &[], 2000.0)), ["1", "2"]);
+        assert!(engine.evaluate(&out, &[], 2000.0).is_empty());
+        // A subject newly matching still fires while the others stay quiet
+        let more = [
+            out[0].clone(),
+            out[1].clone(),
+            position("3", 200, "2000", "0", "0"),
+        ];
+        assert_eq!(subjects(&engine.evaluate(&more, &[], 2000.0)), ["3"]);
+    }
+
+    #[test]
+    fn cooldown_holds_back_flapping() {
+        let mut engine =
+            AlertEngine::new(vec![rule(AlertCondition::OutOfRange { pair: None }, 300)]);
+        let start = Utc::now();
+        let mut at = |secs, tick| {
+            let now = start + Duration::seconds(secs);
+            engine.evaluate_at(now, &[at_tick(tick)], &[], 2000.0).len()
+        };
+        assert_eq!(at(0, -10), 1);
+        assert_eq!(at(10, 50), 0);
+        assert_eq!(at(20, -10), 0);
+        // Once the cooldown has elapsed the next crossing fires again
+        assert_eq!(at(400, 50), 0);
+        assert_eq!(at(410, -10), 1);
+    }
+
+    #[test]
+    fn fires_when_cooldown_ends_while_condition_holds() {
+        let mut engine =
+            AlertEngine::new(vec![rule(AlertCondition::OutOfRange { pair: None }, 300)]);
+        let start = Utc::now();
+        let mut at = |secs, tick| {
+            let now = start + Duration::seconds(secs);
+            engine.evaluate_at(now, &[at_tick(tick)], &[], 2000.0).len()
+        };
+        assert_eq!(at(0, -10), 1);
+        assert_eq!(at(10, 50), 0);
+        // Back out of range during the cooldown, and still out after it
+        assert_eq!(at(20, -10), 0);
+        assert_eq!(at(299, -10), 0);
+        assert_eq!(at(300, -10), 1);
+        assert_eq!(at(310, -10), 0);
+    }
+}
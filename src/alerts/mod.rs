@@ -0,0 +1,3 @@
+pub mod engine;
+pub mod rules;
+pub mod sinks;
@@ -0,0 +1,52 @@
+use serde::Deserialize;
+
+use super::sinks::Sink;
+
+/// A user defined alert, configured in `config.toml`, e.g.
+///
+/// ```toml
+/// [[alerts]]
+/// name = "ETH above 4k"
+/// when = "price_above"
+/// pair = "WETH/USDC"
+/// price = 4000.0
+/// cooldown_secs = 900
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    #[serde(flatten)]
+    pub condition: AlertCondition,
+    /// Minimum time between two alerts of this rule for the same subject
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// Extra destinations the alert is delivered to
+    #[serde(default)]
+    pub sinks: Vec<Sink>,
+}
+
+fn default_cooldown_secs() -> u64 {
+    300
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "when", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// A position's pool price leaves its tick range
+    OutOfRange {
+        #[serde(default)]
+        pair: Option<String>,
+    },
+    /// The price of the pair's base token in its quote token rises above `price`
+    PriceAbove { pair: String, price: f64 },
+    /// The price of the pair's base token in its quote token falls below `price`
+    PriceBelow { pair: String, price: f64 },
+    /// A position's uncollected fees exceed `usd`
+    FeesAbove {
+        usd: f64,
+        #[serde(default)]
+        pair: Option<String>,
+    },
+    /// An open limit order for `token` appears
+    LimitOrder { token: String },
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tokio::time::{sleep, Duration};
+
+use super::engine::Alert;
+use crate::network::metrics::{track_response, Upstream};
+
+const DEFAULT_WEBHOOK_BODY: &str = r#"{"text": "{{rule}}: {{message}}"}"#;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where an alert is delivered in addition to the in-app notification, e.g.
+///
+/// ```toml
+/// [[alerts.sinks]]
+/// type = "webhook"
+/// url = "http://127.0.0.1:8008/hooks/lp"
+/// body = '{"msgtype": "m.text", "body": "{{rule}}: {{message}}"}'
+///
+/// [[alerts.sinks]]
+/// type = "command"
+/// command = "notify-send"
+/// args = ["gerrehbenta"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Sink {
+    /// POSTs a JSON body rendered from `body` to `url`, retrying with exponential backoff
+    Webhook {
+        url: String,
+        #[serde(default)]
+        body: Option<String>,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default = "default_retries")]
+        retries: u32,
+    },
+    /// Runs `command` with the alert fields exposed as `GERREHBENTA_ALERT_*` environment variables
+    Command {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+pub async fn deliver(sink: &Sink, alert: &Alert) -> Result<()> {
+    match sink {
+        Sink::Webhook {
+            url,
+            body,
+            headers,
+            retries,
+        } => {
+            let body = render_template(body.as_deref().unwrap_or(DEFAULT_WEBHOOK_BODY), alert);
+            post_webhook(url, &body, headers, *retries).await
+        }
+        Sink::Command { command, args } => run_command(command, args, alert).await,
+    }
+}
+
+async fn post_webhook(
+    url: &str,
+    body: &str,
+    headers: &HashMap<String, String>,
+    retries: u32,
+) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()?;
+
+    let mut attempt = 0;
+    loop {
+        let mut request = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .body(body.to_owned());
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let error = match track_response(Upstream::Webhook, request.send()).await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => anyhow!("webhook {} returned status {}", url, response.status()),
+            Err(e) => e.into(),
+        };
+
+        if attempt >= retries {
+            return Err(error);
+        }
+        attempt += 1;
+        log::warn!(
+            "Webhook delivery failed ({}), retry {}/{}",
+            error,
+            attempt,
+            retries
+        );
+        sleep(Duration::from_secs(1 << attempt.min(6))).await;
+    }
+}
+
+async fn run_command(command: &str, args: &[String], alert: &Alert) -> Result<()> {
+    let status = tokio::process::Command::new(command)
+        .args(args)
+        .env("GERREHBENTA_ALERT_RULE", &alert.rule)
+        .env("GERREHBENTA_ALERT_SUBJECT", &alert.subject)
+        .env("GERREHBENTA_ALERT_MESSAGE", &alert.message)
+        .env("GERREHBENTA_ALERT_TIMESTAMP", alert.timestamp.to_rfc3339())
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .await?;
+
+    if !status.success() {
+        return Err(anyhow!(
+            "alert command '{}' exited with {}",
+            command,
+            status
+        ));
+    }
+
+    Ok(())
+}
+
+/// Replaces `{{rule}}`, `{{subject}}`, `{{message}}` and `{{timestamp}}` with the
+/// alert's fields, escaped so they can be embedded inside JSON strings
+pub fn render_template(template: &str, alert: &Alert) -> String {
+    let escape = |value: &str| {
+        let quoted = serde_json::to_string(value).unwrap_or_default();
+        quoted[1..quoted.len() - 1].to_owned()
+    };
+
+    template
+        .replace("{{rule}}", &escape(&alert.rule))
+        .replace("{{subject}}", &escape(&alert.subject))
+        .replace("{{message}}", &escape(&alert.message))
+        .replace("{{timestamp}}", &alert.timestamp.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        task::JoinHandle,
+    };
+
+    use super::*;
+
+    fn alert() -> Alert {
+        Alert {
+            rule: "ETH \"high\"".to_owned(),
+            subject: "WETH/USDC".to_owned(),
+            message: "WETH/USDC is above 4000\nnow 4100".to_owned(),
+            timestamp: Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap(),
+            sinks: vec![],
+        }
+    }
+
+    /// Answers one request per status in `statuses` on a local port, returning
+    /// its url and the requests received, head and body
+    async fn listen(statuses: Vec<u16>) -> (String, JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut received = Vec::new();
+                let mut buf = [0; 1024];
+                let (head, body_start) = loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    received.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&received).into_owned();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        break (text[..end].to_owned(), end + 4);
+                    }
+                };
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                while received.len() < body_start + length {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    received.extend_from_slice(&buf[..n]);
+                }
+                let body = String::from_utf8_lossy(&received[body_start..]).into_owned();
+                requests.push((head, body));
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn renders_template_escaped_for_json() {
+        let body = render_template(DEFAULT_WEBHOOK_BODY, &alert());
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            value["text"],
+            "ETH \"high\": WETH/USDC is above 4000\nnow 4100"
+        );
+
+        let body = render_template("{{subject}} at {{timestamp}}", &alert());
+        assert_eq!(body, "WETH/USDC at 2024-01-02T03:04:05+00:00");
+    }
+
+    #[tokio::test]
+    async fn delivers_webhook() {
+        let (url, server) = listen(vec![200]).await;
+        let sink = Sink::Webhook {
+            url,
+            body: Some(r#"{"body": "{{message}}"}"#.to_owned()),
+            headers: HashMap::from([("X-Token".to_owned(), "secret".to_owned())]),
+            retries: 0,
+        };
+        deliver(&sink, &alert()).await.unwrap();
+
+        let requests = server.await.unwrap();
+        let (head, body) = &requests[0];
+        assert!(head.starts_with("POST /hook HTTP/1.1"));
+        assert!(head.to_lowercase().contains("x-token: secret"));
+        assert!(head
+            .to_lowercase()
+            .contains("content-type: application/json"));
+        assert_eq!(body, r#"{"body": "WETH/USDC is above 4000\nnow 4100"}"#);
+    }
+
+    #[tokio::test]
+    async fn retries_webhook_after_error_status() {
+        let (url, server) = listen(vec![503, 200]).await;
+        post_webhook(&url, "{}", &HashMap::new(), 1).await.unwrap();
+        assert_eq!(server.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_webhook_once_retries_run_out() {
+        let (url, server) = listen(vec![500]).await;
+        let error = post_webhook(&url, "{}", &HashMap::new(), 0)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("500"));
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn runs_command_with_alert_env() {
+        let check = |expected: &str| {
+            vec![
+                "-c".to_owned(),
+                format!("test \"$GERREHBENTA_ALERT_SUBJECT\" = '{expected}'"),
+            ]
+        };
+        run_command("sh", &check("WETH/USDC"), &alert())
+            .await
+            .unwrap();
+        assert!(run_command("sh", &check("WBTC/USDC"), &alert())
+            .await
+            .is_err());
+    }
+}
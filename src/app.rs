@@ -1,15 +1,31 @@
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveTime, Utc};
 use ethers::types::NameOrAddress;
+use ratatui::widgets::TableState;
 use std::sync::mpsc::Sender;
 
 use crate::{
-    models::{position::Position, states::AppSearchState},
-    network::{limit_orders::LimitOrder, network::NetworkEvent},
-    routes::{ActiveBlock, Route},
-    widgets::table::StatefulTable,
+    alerts::engine::{Alert, AlertEngine},
+    config::{IndicatorConfig, ThemeConfig},
+    keymap::{KeyContext, Keymap},
+    models::{
+        history::{HistoryStore, PoolKey, Resolution},
+        position::{Pool, Position},
+        states::{AppSearchState, InputMode},
+    },
+    network::{limit_orders::LimitOrder, metrics, network::NetworkEvent},
+    routes::{ActiveBlock, Route, RouteId, ViewState},
+    theme::{no_color, Theme, ThemePreset},
+    widgets::{
+        chart::ChartCursor, limit_orders::LimitOrdersWidget, notifications::Notification,
+        pool_info::PoolExplorer, range_estimator::RangeEstimator, table::StatefulTable,
+        top_pools::TopPools,
+    },
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ChartTimeRange {
+    #[default]
     OneDay,
     OneWeek,
     OneMonth,
@@ -40,80 +56,624 @@ impl ChartTimeRange {
             ChartTimeRange::FiveYears => "5Y",
         }
     }
+
+    /// How far back the chart goes
+    pub fn seconds(&self) -> i64 {
+        const DAY: i64 = 60 * 60 * 24;
+        match self {
+            ChartTimeRange::OneDay => DAY,
+            ChartTimeRange::OneWeek => DAY * 7,
+            ChartTimeRange::OneMonth => DAY * 30,
+            ChartTimeRange::ThreeMonths => DAY * 90,
+            ChartTimeRange::SixMonths => DAY * 180,
+            ChartTimeRange::OneYear => DAY * 365,
+            ChartTimeRange::FiveYears => DAY * 365 * 5,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ChartView {
+    #[default]
     Price,
+    /// Price candles, falling back to `Price` without OHLC data
+    Candles,
     Volume,
+    Fees,
+    Tvl,
+}
+
+impl ChartView {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChartView::Price => "Price",
+            ChartView::Candles => "Candles",
+            ChartView::Volume => "Volume",
+            ChartView::Fees => "Fees",
+            ChartView::Tvl => "TVL",
+        }
+    }
+
+    /// The view `v` switches to
+    pub fn next(&self) -> ChartView {
+        match self {
+            ChartView::Price => ChartView::Candles,
+            ChartView::Candles => ChartView::Volume,
+            ChartView::Volume => ChartView::Fees,
+            ChartView::Fees => ChartView::Tvl,
+            ChartView::Tvl => ChartView::Price,
+        }
+    }
+}
+
+/// How the price view puts both prices on the y axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PriceScale {
+    /// Both prices on one linear axis
+    #[default]
+    Shared,
+    /// token0's price on the left axis and token1's on its own on the right
+    Dual,
+    Log,
+    /// Each price as its change since the start of the chart
+    Percent,
+}
+
+impl PriceScale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceScale::Shared => "Shared",
+            PriceScale::Dual => "Dual axes",
+            PriceScale::Log => "Log",
+            PriceScale::Percent => "% change",
+        }
+    }
+
+    pub fn next(&self) -> PriceScale {
+        match self {
+            PriceScale::Shared => PriceScale::Dual,
+            PriceScale::Dual => PriceScale::Log,
+            PriceScale::Log => PriceScale::Percent,
+            PriceScale::Percent => PriceScale::Shared,
+        }
+    }
+}
+
+/// Technical indicators drawn over the price chart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Indicator {
+    #[default]
+    None,
+    /// Simple and exponential moving averages
+    MovingAverages,
+    Bollinger,
+}
+
+impl Indicator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Indicator::None => "No indicators",
+            Indicator::MovingAverages => "SMA/EMA",
+            Indicator::Bollinger => "Bollinger",
+        }
+    }
+
+    pub fn next(&self) -> Indicator {
+        match self {
+            Indicator::None => Indicator::MovingAverages,
+            Indicator::MovingAverages => Indicator::Bollinger,
+            Indicator::Bollinger => Indicator::None,
+        }
+    }
+}
+
+/// How charts are drawn, shared by every screen with one and kept on a screen's route
+/// while others are opened over it
+#[derive(Debug, Clone, Default)]
+pub struct ChartState {
+    pub time_range: ChartTimeRange,
+    pub view: ChartView,
+    pub scale: PriceScale,
+    /// Whether the pair is shown the other way around, token1 first
+    pub inverted: bool,
+    pub indicator: Indicator,
+    /// Whether the realized volatility is shown below the price chart
+    pub volatility: bool,
+    /// Periods of the indicators, from the `[indicators]` config section
+    pub periods: IndicatorConfig,
+    /// Crosshair moved with the keys or the mouse
+    pub cursor: ChartCursor,
+    /// Times at the left and right edges once zoomed, panned or typed in,
+    /// otherwise `time_range` up to now is shown
+    pub viewport: Option<(f64, f64)>,
+    /// The date range being typed, if any
+    pub range_input: Option<String>,
+    /// Why the last typed date range was rejected
+    pub range_error: Option<String>,
+}
+
+impl ChartState {
+    /// Narrowest span zoomed into, a few hourly snapshots
+    const MIN_SPAN: f64 = 60.0 * 60.0 * 6.0;
+    /// Widest span zoomed out to, the longest preset
+    const MAX_SPAN: f64 = 60.0 * 60.0 * 24.0 * 365.0 * 5.0;
+
+    /// Times at the left and right edges of the chart
+    pub fn window(&self, now: f64) -> (f64, f64) {
+        self.viewport
+            .unwrap_or((now - self.time_range.seconds() as f64, now))
+    }
+
+    /// The snapshots the chart is drawn from, hourly for up to a week
+    pub fn resolution(&self, now: f64) -> Resolution {
+        let (from, to) = self.window(now);
+        if to - from <= 60.0 * 60.0 * 24.0 * 7.0 {
+            Resolution::Hour
+        } else {
+            Resolution::Day
+        }
+    }
+
+    /// Shows `range` up to now
+    pub fn pick_range(&mut self, range: ChartTimeRange) {
+        self.time_range = range;
+        self.viewport = None;
+    }
+
+    /// Scales the span shown by `factor`, keeping the time under the cursor,
+    /// or the middle without one, where it is
+    pub fn zoom(&mut self, factor: f64, now: f64) {
+        let (from, to) = self.window(now);
+        let anchor = self
+            .cursor
+            .time()
+            .filter(|time| from <= *time && *time <= to)
+            .unwrap_or((from + to) / 2.0);
+        let span = ((to - from) * factor).clamp(Self::MIN_SPAN, Self::MAX_SPAN);
+        let from = anchor - span * (anchor - from) / (to - from);
+        self.viewport = Some((from, from + span));
+        self.clamp(now);
+    }
+
+    /// Moves the span shown by `fraction` of its width, back in time when negative
+    pub fn pan(&mut self, fraction: f64, now: f64) {
+        let (from, to) = self.window(now);
+        self.viewport = Some((from + (to - from) * fraction, to + (to - from) * fraction));
+        self.clamp(now);
+    }
+
+    pub fn start_range_input(&mut self) {
+        self.range_input = Some(String::new());
+        self.range_error = None;
+    }
+
+    pub fn cancel_range_input(&mut self) {
+        self.range_input = None;
+    }
+
+    /// Shows the typed date range, keeping the current one if it is invalid
+    pub fn submit_range_input(&mut self, now: f64) {
+        let Some(input) = self.range_input.take() else {
+            return;
+        };
+        match parse_date_range(&input, now) {
+            Ok(range) => {
+                self.viewport = Some(range);
+                self.range_error = None;
+            }
+            Err(e) => self.range_error = Some(e.to_string()),
+        }
+    }
+
+    /// Keeps the viewport from reaching past now
+    fn clamp(&mut self, now: f64) {
+        if let Some((from, to)) = self.viewport {
+            if to > now {
+                self.viewport = Some((from - (to - now), now));
+            }
+        }
+    }
+}
+
+/// Parses a date range typed as `from to`, `from..to` or just `from`, e.g.
+/// `2024-01-01 2024-03-31`. Dates are `YYYY-MM-DD` in UTC, the range ends
+/// at the end of `to` and at `now` without one.
+fn parse_date_range(input: &str, now: f64) -> Result<(f64, f64)> {
+    let parse = |date: &str| -> Result<f64> {
+        let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|_| anyhow!("'{}' is not a date like 2024-01-31", date.trim()))?;
+        Ok(date.and_time(NaiveTime::MIN).and_utc().timestamp() as f64)
+    };
+    let input = input.trim();
+    let (from, to) = match input.split_once("..") {
+        Some((from, to)) => (from, Some(to)),
+        None => match input.split_once(char::is_whitespace) {
+            Some((from, to)) => (from, Some(to.trim_start_matches("to"))),
+            None => (input, None),
+        },
+    };
+    let from = parse(from)?;
+    let to = match to.map(str::trim).filter(|to| !to.is_empty()) {
+        Some(to) => (parse(to)? + 60.0 * 60.0 * 24.0).min(now),
+        None => now,
+    };
+    if from >= to {
+        return Err(anyhow!(
+            "the range must end after it starts, and before now"
+        ));
+    }
+    Ok((from, to))
 }
 
 // App holds the state of the application
 pub struct App {
-    /// Current mode
-    pub mode: Mode,
-    /// Previous mode
-    pub previous_mode: Mode,
     /// Current input mode
     pub search_state: AppSearchState,
     /// Current wallet address
     pub wallet_address: Option<String>,
     /// whether to show help dialogue
     pub show_help: bool,
-    /// Current route
+    /// Lines scrolled down in the help dialogue
+    pub help_scroll: u16,
+    /// Screens navigated through, the current one is last and the first is never popped
     pub routes: Vec<Route>,
     /// The channel to send network events to
     pub network_txn: Option<Sender<NetworkEvent>>,
     /// Current limit orders
     pub limit_orders: Vec<LimitOrder>,
+    /// Table state for limit orders
+    pub limit_orders_table: LimitOrdersWidget,
     /// Current positions
     pub positions: Vec<Position>,
     /// Table state for positions
     pub stateful_table: StatefulTable,
-    pub chart_time_range: ChartTimeRange,
-    pub chart_view: ChartView,
-}
-
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
-pub enum Mode {
-    Welcome,
-    Main,
-    Help,
-    Search,
-    LimitOrders,
-    PoolInfo,
-    MyPositions,
+    /// Search and results of the pool explorer
+    pub pool_explorer: PoolExplorer,
+    /// Filters and cached results of the top pools leaderboard
+    pub top_pools: TopPools,
+    /// Form and results of the range fee estimator
+    pub range_estimator: RangeEstimator,
+    /// History of the pools charted, backfilled as longer time ranges are picked
+    pub history: HistoryStore,
+    /// Time range, view and cursor of the chart on screen
+    pub chart: ChartState,
+    /// Latest ETH price in USD, used to value positions
+    pub eth_price_usd: f64,
+    /// Alert rules evaluated on every data refresh
+    pub alert_engine: AlertEngine,
+    /// In-app notifications raised by alerts
+    pub notifications: Vec<Notification>,
+    /// Whether the terminal bell should ring on the next draw
+    pub ring_bell: bool,
+    /// Colors used by every widget
+    pub theme: Theme,
+    /// The preset `theme` is based on, used when cycling themes
+    pub theme_preset: ThemePreset,
+    /// The `[theme]` config section, whose colors apply to every preset
+    pub theme_config: ThemeConfig,
+    /// Key bindings, from the `[keys]` config section
+    pub keymap: Keymap,
 }
 
 impl App {
     pub fn default() -> App {
         App {
-            previous_mode: Mode::Welcome,
-            mode: Mode::Welcome,
             search_state: AppSearchState::default(),
             wallet_address: None,
             routes: vec![Route::default()],
             show_help: false,
+            help_scroll: 0,
             network_txn: None,
             limit_orders: Vec::new(),
+            limit_orders_table: LimitOrdersWidget::new(),
             positions: Vec::new(),
             stateful_table: StatefulTable::new(),
-            chart_time_range: ChartTimeRange::OneDay,
-            chart_view: ChartView::Price,
+            pool_explorer: PoolExplorer::new(),
+            top_pools: TopPools::new(vec!["mainnet".to_owned()], "mainnet", 300),
+            range_estimator: RangeEstimator::new(),
+            history: HistoryStore::default(),
+            chart: ChartState::default(),
+            eth_price_usd: 0.0,
+            alert_engine: AlertEngine::new(Vec::new()),
+            notifications: Vec::new(),
+            ring_bell: false,
+            theme: Theme::default(),
+            theme_preset: ThemePreset::default(),
+            theme_config: ThemeConfig::default(),
+            keymap: Keymap::default(),
         }
     }
 
+    /// The context key presses are handled in
+    pub fn key_context(&self) -> KeyContext {
+        if let RouteId::Welcome = self.route_id() {
+            return KeyContext::TextInput;
+        }
+        match self.get_current_route().get_active_block() {
+            ActiveBlock::SearchBar => match self.search_state.input_mode {
+                InputMode::Editing => KeyContext::TextInput,
+                InputMode::Normal => KeyContext::Navigation,
+            },
+            ActiveBlock::MyPositions if self.stateful_table.filter_input.is_some() => {
+                KeyContext::TextInput
+            }
+            ActiveBlock::MyPositions | ActiveBlock::PositionInfo | ActiveBlock::PoolInfo
+                if self.chart.range_input.is_some() =>
+            {
+                KeyContext::TextInput
+            }
+            ActiveBlock::MyPositions => KeyContext::Positions,
+            ActiveBlock::PositionInfo => KeyContext::PositionInfo,
+            ActiveBlock::PoolInfo if self.pool_explorer.editing => KeyContext::TextInput,
+            ActiveBlock::PoolInfo => KeyContext::PoolInfo,
+            ActiveBlock::TopPools => KeyContext::TopPools,
+            ActiveBlock::RangeEstimator if self.range_estimator.editing => KeyContext::TextInput,
+            ActiveBlock::RangeEstimator => KeyContext::RangeEstimator,
+            ActiveBlock::LimitOrders => KeyContext::LimitOrders,
+            ActiveBlock::Main => KeyContext::Navigation,
+        }
+    }
+
+    /// Switches to the next built-in theme preset, keeping the configured colors
+    pub fn cycle_theme(&mut self) {
+        (self.theme_preset, self.theme) = self.theme_config.with_preset(self.theme_preset.next());
+        let message = if no_color() {
+            "NO_COLOR is set, keeping the monochrome theme".to_owned()
+        } else {
+            format!("Switched to the {} theme", self.theme_preset.as_str())
+        };
+        self.notifications
+            .push(Notification::new("Theme".to_owned(), message));
+    }
+
+    /// Called whenever the network thread has refreshed positions or limit orders
     pub fn update(&mut self) {
-        // #TODO: Add update logic
+        if let Some(wallet) = &self.wallet_address {
+            metrics::record_positions(wallet, &self.positions, self.eth_price_usd);
+        }
+
+        let alerts =
+            self.alert_engine
+                .evaluate(&self.positions, &self.limit_orders, self.eth_price_usd);
+        self.raise_alerts(alerts);
+        self.notifications.retain(|n| !n.is_expired());
+    }
+
+    fn raise_alerts(&mut self, alerts: Vec<Alert>) {
+        if alerts.is_empty() {
+            return;
+        }
+
+        for alert in alerts {
+            log::info!("Alert '{}' raised: {}", alert.rule, alert.message);
+            self.notifications
+                .push(Notification::new(alert.rule.clone(), alert.message.clone()));
+            if !alert.sinks.is_empty() {
+                if let Some(network_txn) = &self.network_txn {
+                    let _ = network_txn.send(NetworkEvent::DeliverAlert { alert });
+                }
+            }
+        }
+        self.ring_bell = true;
+    }
+
+    /// Keeps the history `pool` was fetched with and fills it in with
+    /// everything backfilled before
+    pub fn merge_history(&mut self, key: &PoolKey, pool: &mut Pool) {
+        self.history.seed(key, pool, Utc::now().timestamp());
+        self.history.apply(key, pool);
+    }
+
+    /// Hands backfilled history to every pool shown
+    pub fn apply_history(&mut self) {
+        for position in &mut self.positions {
+            self.history
+                .apply(&(None, position.pool.id.clone()), &mut position.pool);
+        }
+        let chain = self.pool_explorer.chain.clone();
+        if let Some(page) = &mut self.pool_explorer.page {
+            self.history
+                .apply(&(chain.clone(), page.pool.id.clone()), &mut page.pool);
+        }
+        // Positions opened from the pool explorer only live on their route
+        for route in &mut self.routes {
+            if let RouteId::PositionInfo(Some(position)) = route.id_mut() {
+                if !self.positions.iter().any(|p| p.id == position.id) {
+                    self.history.apply(
+                        &(chain.clone(), position.pool.id.clone()),
+                        &mut position.pool,
+                    );
+                }
+            }
+        }
+    }
+
+    /// The pool whose chart is on screen
+    fn charted_pool(&self) -> Option<PoolKey> {
+        match self.route_id() {
+            RouteId::MyPositions(_) => {
+                let i = self.stateful_table.selected_position()?;
+                Some((None, self.positions.get(i)?.pool.id.clone()))
+            }
+            RouteId::PositionInfo(Some(position)) => {
+                if self.positions.iter().any(|p| p.id == position.id) {
+                    Some((None, position.pool.id.clone()))
+                } else {
+                    Some((self.pool_explorer.chain.clone(), position.pool.id.clone()))
+                }
+            }
+            RouteId::PoolInfo => {
+                let page = self.pool_explorer.page.as_ref()?;
+                Some((self.pool_explorer.chain.clone(), page.pool.id.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Fetches whatever history the chart on screen lacks for its time range
+    pub fn backfill_chart(&mut self) {
+        let (Some(network_txn), Some(pool)) = (&self.network_txn, self.charted_pool()) else {
+            return;
+        };
+        let now = Utc::now().timestamp() as f64;
+        let resolution = self.chart.resolution(now);
+        let (from, to) = self.chart.window(now);
+        let wanted = (from as i64, to.ceil() as i64);
+        for span in self.history.request(&pool, resolution, wanted) {
+            let _ = network_txn.send(NetworkEvent::BackfillHistory {
+                pool: pool.clone(),
+                resolution,
+                span,
+            });
+        }
     }
 
     pub fn update_limit_orders(&mut self, orders: Vec<LimitOrder>) {
+        self.limit_orders_table.clamp_selection(orders.len());
         self.limit_orders = orders;
     }
 
+    /// Shows the limit orders, starting to poll for them the first time
+    pub fn open_limit_orders(&mut self) {
+        self.set_route(Route::new(RouteId::LimitOrders, ActiveBlock::LimitOrders));
+        self.poll_limit_orders();
+    }
+
+    /// Starts polling for limit orders unless it already has
+    pub fn poll_limit_orders(&mut self) {
+        if self.limit_orders_table.next_refresh.is_none() && !self.limit_orders_table.loading {
+            if let Some(network_txn) = &self.network_txn {
+                self.limit_orders_table.loading = true;
+                let _ = network_txn.send(NetworkEvent::FetchLimitOrders);
+            }
+        }
+    }
+
+    /// Shows the details of the position selected in the table
+    pub fn open_position_info(&mut self) {
+        let selected = self
+            .stateful_table
+            .selected_position()
+            .and_then(|i| self.positions.get(i));
+        if let Some(position) = selected {
+            self.set_route(Route::new(
+                RouteId::PositionInfo(Some(Box::new(position.clone()))),
+                ActiveBlock::PositionInfo,
+            ));
+        }
+    }
+
+    /// Opens the pool explorer on the pool of the position being looked at,
+    /// or on the last pool explored
+    pub fn open_pool_explorer(&mut self) {
+        let pool = match self.route_id() {
+            RouteId::PositionInfo(Some(position)) => Some(position.pool.id.clone()),
+            RouteId::MyPositions(_) => self
+                .stateful_table
+                .selected_position()
+                .and_then(|i| self.positions.get(i))
+                .map(|position| position.pool.id.clone()),
+            _ => None,
+        };
+
+        self.set_route(Route::new(RouteId::PoolInfo, ActiveBlock::PoolInfo));
+        match pool {
+            Some(pool) => {
+                self.pool_explorer.chain = None;
+                self.pool_explorer.input = pool;
+                self.search_pool();
+            }
+            None if self.pool_explorer.page.is_none() => self.pool_explorer.start_search(),
+            None => {}
+        }
+    }
+
+    /// Looks up the pool typed into the pool explorer
+    pub fn search_pool(&mut self) {
+        if let Some(query) = self.pool_explorer.submit() {
+            if let Some(network_txn) = &self.network_txn {
+                let chain = self.pool_explorer.chain.clone();
+                let _ = network_txn.send(NetworkEvent::FetchPool { query, chain });
+            }
+        }
+    }
+
+    /// Shows the details of the top position selected in the pool explorer
+    pub fn open_pool_position(&mut self) {
+        if let Some(position) = self.pool_explorer.selected_position() {
+            self.set_route(Route::new(
+                RouteId::PositionInfo(Some(Box::new(position))),
+                ActiveBlock::PositionInfo,
+            ));
+        }
+    }
+
+    /// Estimates the fees of a range in the pool shown by the pool explorer
+    pub fn open_range_estimator(&mut self) {
+        if let Some(page) = &self.pool_explorer.page {
+            self.range_estimator
+                .open(page.clone(), self.pool_explorer.chain.clone());
+            self.set_route(Route::new(
+                RouteId::RangeEstimator,
+                ActiveBlock::RangeEstimator,
+            ));
+            self.estimate_range();
+            self.range_estimator.start_editing();
+        }
+    }
+
+    /// Reads the estimator form, fetching the pool's ticks over the range if needed
+    pub fn estimate_range(&mut self) {
+        if let Some((pool, ticks)) = self.range_estimator.submit() {
+            if let Some(network_txn) = &self.network_txn {
+                let chain = self.range_estimator.chain.clone();
+                let _ = network_txn.send(NetworkEvent::FetchTicks { pool, chain, ticks });
+            }
+        }
+    }
+
+    /// Shows the pools with the most value locked
+    pub fn open_top_pools(&mut self) {
+        self.set_route(Route::new(RouteId::TopPools, ActiveBlock::TopPools));
+        self.refresh_top_pools();
+    }
+
+    /// Fetches the shown leaderboard unless a fresh copy is cached
+    pub fn refresh_top_pools(&mut self) {
+        if let Some((chain, fee_tier)) = self.top_pools.request() {
+            if let Some(network_txn) = &self.network_txn {
+                let _ = network_txn.send(NetworkEvent::FetchTopPools { chain, fee_tier });
+            }
+        }
+    }
+
+    /// Explores the pool selected in the leaderboard
+    pub fn open_top_pool(&mut self) {
+        if let Some((chain, pool)) = self.top_pools.selected_pool() {
+            self.set_route(Route::new(RouteId::PoolInfo, ActiveBlock::PoolInfo));
+            self.pool_explorer.chain = Some(chain);
+            self.pool_explorer.input = pool;
+            self.search_pool();
+        }
+    }
+
+    /// Goes back to the positions of the searched wallet, if there are any
+    pub fn open_positions(&mut self) {
+        if let Some(i) = self
+            .routes
+            .iter()
+            .position(|r| matches!(r.id(), RouteId::MyPositions(_)))
+        {
+            self.routes.truncate(i + 1);
+            self.change_active_block(ActiveBlock::MyPositions);
+            self.restore_view();
+        }
+    }
+
     pub fn pop_current_route(&mut self) {
         if self.routes.len() > 1 {
             self.routes.pop();
+            self.restore_view();
         }
     }
 
@@ -123,15 +683,77 @@ impl App {
             .map_or(Route::default(), |route| route.to_owned())
     }
 
-    pub fn set_route(&mut self, route: Route) {
-        self.routes.push(route);
+    /// The screen currently shown
+    pub fn route_id(&self) -> &RouteId {
+        self.routes.last().map_or(&RouteId::Welcome, Route::id)
+    }
+
+    /// Shows `route`. A screen already on the stack is returned to rather than pushed
+    /// again, dropping the screens opened after it, so going back never passes through
+    /// the same screen twice. It shows what `route` does, with the scroll, selection
+    /// and chart it was left with.
+    pub fn set_route(&mut self, mut route: Route) {
+        self.save_view();
+        match self
+            .routes
+            .iter()
+            .position(|r| r.id().is_same_screen(route.id()))
+        {
+            Some(i) => {
+                self.routes.truncate(i + 1);
+                route.set_view(self.routes[i].view().clone());
+                self.routes[i] = route;
+                self.restore_view();
+            }
+            None => self.routes.push(route),
+        }
+    }
+
+    /// The table the screen `id` scrolls and selects in, if it has one
+    fn table_state_mut(&mut self, id: &RouteId) -> Option<&mut TableState> {
+        match id {
+            RouteId::MyPositions(_) => Some(&mut self.stateful_table.state),
+            RouteId::LimitOrders => Some(&mut self.limit_orders_table.state),
+            RouteId::TopPools => Some(&mut self.top_pools.state),
+            RouteId::PoolInfo => Some(&mut self.pool_explorer.state),
+            _ => None,
+        }
+    }
+
+    /// Keeps the view of the screen shown on its route, before another is opened
+    fn save_view(&mut self) {
+        let Some(id) = self.routes.last().map(Route::get_id) else {
+            return;
+        };
+        let view = ViewState {
+            table: self.table_state_mut(&id).map(|state| state.clone()),
+            chart: Some(self.chart.clone()),
+        };
+        if let Some(route) = self.routes.last_mut() {
+            route.set_view(view);
+        }
+    }
+
+    /// Puts back the view saved on the screen now shown
+    fn restore_view(&mut self) {
+        let Some(route) = self.routes.last().cloned() else {
+            return;
+        };
+        let view = route.view().clone();
+        if let (Some(saved), Some(state)) = (view.table, self.table_state_mut(route.id())) {
+            *state = saved;
+        }
+        if let Some(chart) = view.chart {
+            self.chart = chart;
+            self.backfill_chart();
+        }
     }
 
     pub fn change_active_block(&mut self, active_block: ActiveBlock) {
-        let current_route = self.get_current_route();
-        self.routes.pop();
-        self.routes
-            .push(Route::new(current_route.get_id(), active_block));
+        match self.routes.last_mut() {
+            Some(route) => route.set_active_block(active_block),
+            None => self.routes.push(Route::new(RouteId::Welcome, active_block)),
+        }
     }
 
     pub fn move_cursor_left(&mut self) {
@@ -223,3 +845,55 @@ impl App {
         self.reset_cursor();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(app: &App) -> Vec<String> {
+        app.routes.iter().map(|r| r.id().title()).collect()
+    }
+
+    #[test]
+    fn back_restores_selection_and_chart() {
+        let mut app = App::default();
+        app.set_route(Route::new(
+            RouteId::MyPositions(None),
+            ActiveBlock::MyPositions,
+        ));
+        app.stateful_table.state.select(Some(2));
+        app.chart.time_range = ChartTimeRange::OneWeek;
+
+        app.set_route(Route::new(
+            RouteId::PositionInfo(None),
+            ActiveBlock::PositionInfo,
+        ));
+        app.stateful_table.state.select(Some(0));
+        app.chart.time_range = ChartTimeRange::OneYear;
+
+        app.pop_current_route();
+        assert_eq!(app.stateful_table.state.selected(), Some(2));
+        assert_eq!(app.chart.time_range, ChartTimeRange::OneWeek);
+    }
+
+    #[test]
+    fn opening_a_screen_on_the_stack_returns_to_it() {
+        let mut app = App::default();
+        app.set_route(Route::new(RouteId::TopPools, ActiveBlock::TopPools));
+        app.top_pools.state.select(Some(4));
+        app.set_route(Route::new(RouteId::PoolInfo, ActiveBlock::PoolInfo));
+        app.set_route(Route::new(
+            RouteId::PositionInfo(None),
+            ActiveBlock::PositionInfo,
+        ));
+        app.top_pools.state.select(None);
+
+        app.set_route(Route::new(RouteId::TopPools, ActiveBlock::TopPools));
+        assert_eq!(ids(&app), ["Search", "Top Pools"]);
+        assert_eq!(app.top_pools.state.selected(), Some(4));
+
+        // Going back from it leaves the screens opened after it behind
+        app.pop_current_route();
+        assert_eq!(ids(&app), ["Search"]);
+    }
+}
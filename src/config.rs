@@ -0,0 +1,632 @@
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use ethers::types::NameOrAddress;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    alerts::{rules::AlertRule, sinks::Sink},
+    keymap::Keymap,
+    theme::{no_color, Theme, ThemePreset},
+    util::paths::config_dir,
+    widgets::columns::Column,
+};
+
+const DEFAULT_CHAIN: &str = "mainnet";
+const MAINNET_RPC: &str = "https://eth.llamarpc.com/";
+const MAINNET_SUBGRAPH: &str =
+    "https://gateway.thegraph.com/api/subgraphs/id/5zvR82QoaXYFyDEKLZ9t6v9adgnptxYpKpSbxtgVENFV";
+const MAINNET_LIMIT_ORDERS: &str = "https://api.uniswap.org/v1/limit-orders?orderStatus=open&chainId=1&limit=100&sortKey=createdAt&desc=true";
+
+/// Application configuration, layered as defaults < config file < environment < flags.
+///
+/// ```toml
+/// chain = "mainnet"
+/// wallets = ["vitalik.eth"]
+///
+/// [endpoints.mainnet]
+/// rpc = "https://eth.llamarpc.com/"
+///
+/// [credentials]
+/// subgraph_api_key = "..."
+///
+/// [theme]
+/// preset = "light"
+/// profit = "#00FF00"
+///
+/// [refresh]
+/// positions_secs = 60
+///
+/// [table]
+/// columns = ["pair", "value", "fees", "in_range"]
+///
+/// [indicators]
+/// sma = 20
+/// bollinger_width = 2.0
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The chain whose endpoints are used
+    pub chain: String,
+    /// Serve canned limit orders instead of calling the Uniswap API
+    pub mock_data: bool,
+    /// The wallet (ENS name or address) to load on startup. Positions are
+    /// shown for one wallet at a time, so at most one may be listed.
+    pub wallets: Vec<String>,
+    pub endpoints: BTreeMap<String, ChainEndpoints>,
+    pub credentials: Credentials,
+    pub theme: ThemeConfig,
+    pub refresh: RefreshIntervals,
+    pub table: TableConfig,
+    pub indicators: IndicatorConfig,
+    /// Action name to key chords, e.g. `quit = ["q", "ctrl-c"]`
+    pub keys: BTreeMap<String, Vec<String>>,
+    pub alerts: Vec<AlertRule>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainEndpoints {
+    /// Json-RPC URL used for ENS lookups and balances
+    pub rpc: Option<String>,
+    /// Uniswap v3 subgraph URL
+    pub subgraph: Option<String>,
+    /// Uniswap limit orders API URL
+    pub limit_orders: Option<String>,
+}
+
+/// The endpoints of the active chain with built-in defaults applied
+#[derive(Debug, Clone)]
+pub struct Endpoints {
+    pub rpc: String,
+    pub subgraph: String,
+    pub limit_orders: String,
+}
+
+/// A preset with individual colors overridden by hex strings
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "toml::Table")]
+pub struct ThemeConfig {
+    pub preset: ThemePreset,
+    pub colors: Theme,
+}
+
+// The colors share the table with `preset`, and flattening them into it would
+// let unknown keys through
+impl TryFrom<toml::Table> for ThemeConfig {
+    type Error = toml::de::Error;
+
+    fn try_from(mut table: toml::Table) -> Result<Self, Self::Error> {
+        let preset = match table.remove("preset") {
+            Some(preset) => preset.try_into()?,
+            None => ThemePreset::default(),
+        };
+        Ok(Self {
+            preset,
+            colors: toml::Value::Table(table).try_into()?,
+        })
+    }
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            preset: ThemePreset::default(),
+            colors: Theme::unset(),
+        }
+    }
+}
+
+impl ThemeConfig {
+    /// The preset and theme to start with. `NO_COLOR` wins over any configured colors.
+    pub fn resolve(&self) -> (ThemePreset, Theme) {
+        self.with_preset(self.preset)
+    }
+
+    /// `preset` with the configured colors over it, or the monochrome theme
+    /// when `NO_COLOR` is set
+    pub fn with_preset(&self, preset: ThemePreset) -> (ThemePreset, Theme) {
+        if no_color() {
+            let preset = ThemePreset::Monochrome;
+            return (preset, preset.theme());
+        }
+        (preset, preset.theme().merge(self.colors))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TableConfig {
+    /// Columns of the positions table, in order
+    pub columns: Vec<Column>,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        Self {
+            columns: Column::DEFAULT.to_vec(),
+        }
+    }
+}
+
+/// Periods of the chart's technical indicators, in snapshots of the chart's
+/// resolution except for the volatility's, in hours
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndicatorConfig {
+    pub sma: usize,
+    pub ema: usize,
+    pub bollinger: usize,
+    /// Standard deviations from the Bollinger bands' average to either band
+    pub bollinger_width: f64,
+    /// Hourly returns the realized volatility is measured over
+    pub volatility: usize,
+}
+
+impl Default for IndicatorConfig {
+    fn default() -> Self {
+        Self {
+            sma: 20,
+            ema: 50,
+            bollinger: 20,
+            bollinger_width: 2.0,
+            volatility: 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Credentials {
+    pub subgraph_api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RefreshIntervals {
+    pub positions_secs: u64,
+    pub limit_orders_secs: u64,
+    /// How long the top pools leaderboard is cached
+    pub top_pools_secs: u64,
+}
+
+impl Default for RefreshIntervals {
+    fn default() -> Self {
+        Self {
+            positions_secs: 60,
+            limit_orders_secs: 30,
+            top_pools_secs: 300,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            chain: DEFAULT_CHAIN.to_owned(),
+            mock_data: false,
+            wallets: Vec::new(),
+            endpoints: BTreeMap::new(),
+            credentials: Credentials::default(),
+            theme: ThemeConfig::default(),
+            refresh: RefreshIntervals::default(),
+            table: TableConfig::default(),
+            indicators: IndicatorConfig::default(),
+            keys: BTreeMap::new(),
+            alerts: Vec::new(),
+        }
+    }
+}
+
+/// Values given on the command line, which take precedence over everything else
+#[derive(Debug, Default)]
+pub struct Overrides {
+    pub chain: Option<String>,
+    pub rpc: Option<String>,
+    pub subgraph: Option<String>,
+    pub limit_orders: Option<String>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/gerrehbenta/config.toml`
+    pub fn default_path() -> PathBuf {
+        config_dir().join("config.toml")
+    }
+
+    /// Reads the config file at `path`, falling back to the defaults if it does not exist
+    pub fn from_file(path: &Path) -> Result<Config> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    /// Builds the effective configuration from every layer
+    pub fn load(path: &Path, overrides: Overrides) -> Result<Config> {
+        let mut config = Config::from_file(path)?;
+        config.apply_layers(|name| env::var(name).ok(), overrides);
+        if config.wallets.len() > 1 {
+            return Err(anyhow!(
+                "wallets lists {} wallets, but positions are shown for one at a time; \
+                 search for the others once started",
+                config.wallets.len()
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Applies the environment, read through `var`, then the flags. The chain
+    /// is picked from every layer first, so the endpoints given in either
+    /// belong to the chain in use.
+    fn apply_layers(&mut self, var: impl Fn(&str) -> Option<String>, overrides: Overrides) {
+        if let Some(chain) = overrides.chain.clone().or_else(|| var("GERREHBENTA_CHAIN")) {
+            self.chain = chain;
+        }
+        self.apply_env(var);
+        self.apply_overrides(overrides);
+    }
+
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) {
+        if let Some(key) = var("SUBGRAPH_API_KEY") {
+            self.credentials.subgraph_api_key = Some(key);
+        }
+        if let Some(mock) = var("USE_MOCK_DATA") {
+            self.mock_data = mock.to_lowercase() == "true";
+        }
+
+        let chain = self.chain.clone();
+        let endpoints = self.endpoints.entry(chain).or_default();
+        if let Some(rpc) = var("GERREHBENTA_RPC_URL") {
+            endpoints.rpc = Some(rpc);
+        }
+        if let Some(subgraph) = var("GERREHBENTA_SUBGRAPH_URL") {
+            endpoints.subgraph = Some(subgraph);
+        }
+        if let Some(limit_orders) = var("GERREHBENTA_LIMIT_ORDERS_URL") {
+            endpoints.limit_orders = Some(limit_orders);
+        }
+    }
+
+    fn apply_overrides(&mut self, overrides: Overrides) {
+        let chain = self.chain.clone();
+        let endpoints = self.endpoints.entry(chain).or_default();
+        if overrides.rpc.is_some() {
+            endpoints.rpc = overrides.rpc;
+        }
+        if overrides.subgraph.is_some() {
+            endpoints.subgraph = overrides.subgraph;
+        }
+        if overrides.limit_orders.is_some() {
+            endpoints.limit_orders = overrides.limit_orders;
+        }
+    }
+
+    /// Endpoints of the active chain. Mainnet falls back to public defaults,
+    /// other chains must be fully configured.
+    pub fn endpoints(&self) -> Result<Endpoints> {
+        let configured = self.endpoints.get(&self.chain).cloned().unwrap_or_default();
+        let is_mainnet = self.chain == DEFAULT_CHAIN;
+        let resolve = |value: Option<String>, default: &str, name: &str| {
+            value
+                .or_else(|| is_mainnet.then(|| default.to_owned()))
+                .ok_or_else(|| anyhow!("endpoints.{}.{} is not set", self.chain, name))
+        };
+
+        Ok(Endpoints {
+            rpc: resolve(configured.rpc, MAINNET_RPC, "rpc")?,
+            subgraph: resolve(configured.subgraph, MAINNET_SUBGRAPH, "subgraph")?,
+            limit_orders: resolve(
+                configured.limit_orders,
+                MAINNET_LIMIT_ORDERS,
+                "limit_orders",
+            )?,
+        })
+    }
+
+    /// Subgraph URLs of every chain that has one, mainnet always included
+    pub fn subgraphs(&self) -> BTreeMap<String, String> {
+        let mut subgraphs: BTreeMap<String, String> = self
+            .endpoints
+            .iter()
+            .filter_map(|(chain, endpoints)| Some((chain.clone(), endpoints.subgraph.clone()?)))
+            .collect();
+        subgraphs
+            .entry(DEFAULT_CHAIN.to_owned())
+            .or_insert_with(|| MAINNET_SUBGRAPH.to_owned());
+        subgraphs
+    }
+
+    /// Validates the effective configuration, returning every problem found
+    pub fn check(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        match self.endpoints() {
+            Ok(endpoints) => {
+                for (name, url) in [
+                    ("rpc", &endpoints.rpc),
+                    ("subgraph", &endpoints.subgraph),
+                    ("limit_orders", &endpoints.limit_orders),
+                ] {
+                    if let Err(e) = Url::parse(url) {
+                        issues.push(ConfigIssue::error(format!(
+                            "endpoints.{}.{}: invalid URL '{}' ({})",
+                            self.chain, name, url, e
+                        )));
+                    }
+                }
+            }
+            Err(e) => issues.push(ConfigIssue::error(e.to_string())),
+        }
+
+        if self.credentials.subgraph_api_key.is_none() {
+            issues.push(ConfigIssue::warning(
+                "credentials.subgraph_api_key is not set, positions cannot be loaded".to_owned(),
+            ));
+        }
+
+        if self.refresh.positions_secs == 0
+            || self.refresh.limit_orders_secs == 0
+            || self.refresh.top_pools_secs == 0
+        {
+            issues.push(ConfigIssue::error(
+                "refresh intervals must be greater than zero".to_owned(),
+            ));
+        }
+
+        for wallet in &self.wallets {
+            let is_valid = match wallet.parse::<NameOrAddress>() {
+                Ok(NameOrAddress::Address(_)) => true,
+                Ok(NameOrAddress::Name(name)) => {
+                    name.contains('.')
+                        && name
+                            .chars()
+                            .all(|c| c.is_alphanumeric() || matches!(c, '.' | '-' | '_'))
+                }
+                Err(_) => false,
+            };
+            if !is_valid {
+                issues.push(ConfigIssue::error(format!(
+                    "wallets: '{}' is neither an ENS name nor an address",
+                    wallet
+                )));
+            }
+        }
+
+        if self.table.columns.is_empty() {
+            issues.push(ConfigIssue::error(
+                "table.columns must list at least one column".to_owned(),
+            ));
+        }
+
+        if let Err(e) = Keymap::from_config(&self.keys) {
+            issues.push(ConfigIssue::error(e.to_string()));
+        }
+
+        for rule in &self.alerts {
+            for sink in &rule.sinks {
+                if let Sink::Webhook { url, .. } = sink {
+                    if let Err(e) = Url::parse(url) {
+                        issues.push(ConfigIssue::error(format!(
+                            "alert '{}': invalid webhook URL '{}' ({})",
+                            rule.name, url, e
+                        )));
+                    }
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigIssue {
+    Error(String),
+    Warning(String),
+}
+
+impl ConfigIssue {
+    fn error(message: String) -> Self {
+        ConfigIssue::Error(message)
+    }
+
+    fn warning(message: String) -> Self {
+        ConfigIssue::Warning(message)
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, ConfigIssue::Error(_))
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigIssue::Error(message) => write!(f, "error: {}", message),
+            ConfigIssue::Warning(message) => write!(f, "warning: {}", message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::*;
+
+    fn file(contents: &str) -> Config {
+        toml::from_str(contents).unwrap()
+    }
+
+    /// Applies `env`, given as `(name, value)` pairs, and the flags to `config`
+    fn layered(mut config: Config, env: &[(&str, &str)], overrides: Overrides) -> Config {
+        config.apply_layers(
+            |name| {
+                env.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            },
+            overrides,
+        );
+        config
+    }
+
+    const FILE: &str = r#"
+        chain = "mainnet"
+
+        [endpoints.mainnet]
+        rpc = "http://file/rpc"
+        subgraph = "http://file/subgraph"
+
+        [endpoints.arbitrum]
+        rpc = "http://file/arbitrum/rpc"
+        subgraph = "http://file/arbitrum/subgraph"
+        limit_orders = "http://file/arbitrum/orders"
+    "#;
+
+    #[test]
+    fn theme_preset_and_colors() {
+        let config = file(
+            r##"
+            [theme]
+            preset = "light"
+            profit = "#00FF00"
+            "##,
+        );
+        assert_eq!(config.theme.preset, ThemePreset::Light);
+        assert_eq!(config.theme.colors.profit(), Color::Rgb(0, 255, 0));
+        assert_eq!(file("[theme]").theme.preset, ThemePreset::Dark);
+    }
+
+    #[test]
+    fn rejects_unknown_theme_keys() {
+        let typo = toml::from_str::<Config>("[theme]\nprofitt = \"#00FF00\"\n");
+        let error = typo.unwrap_err().to_string();
+        assert!(error.contains("profitt"), "{error}");
+        assert!(toml::from_str::<Config>("[theme]\npreset = \"neon\"\n").is_err());
+        assert!(toml::from_str::<Config>("[theme]\nprofit = \"green\"\n").is_err());
+    }
+
+    #[test]
+    fn load_fails_on_unknown_theme_keys() {
+        let result = load("theme", "[theme]\nprofitt = \"#00FF00\"\n");
+        assert!(format!("{:#}", result.unwrap_err()).contains("profitt"));
+    }
+
+    #[test]
+    fn load_takes_one_wallet_at_most() {
+        let config = load("one-wallet", "wallets = [\"vitalik.eth\"]\n").unwrap();
+        assert_eq!(config.wallets, ["vitalik.eth"]);
+        let result = load("two-wallets", "wallets = [\"vitalik.eth\", \"nick.eth\"]\n");
+        assert!(result.unwrap_err().to_string().contains("2 wallets"));
+    }
+
+    /// Loads `contents` as the config file
+    fn load(name: &str, contents: &str) -> Result<Config> {
+        let path =
+            std::env::temp_dir().join(format!("gerrehbenta-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let result = Config::load(&path, Overrides::default());
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn defaults() {
+        let config = layered(Config::default(), &[], Overrides::default());
+        let endpoints = config.endpoints().unwrap();
+        assert_eq!(config.chain, DEFAULT_CHAIN);
+        assert_eq!(endpoints.rpc, MAINNET_RPC);
+        assert_eq!(endpoints.subgraph, MAINNET_SUBGRAPH);
+        assert_eq!(endpoints.limit_orders, MAINNET_LIMIT_ORDERS);
+    }
+
+    #[test]
+    fn missing_file_is_defaults() {
+        let config = Config::from_file(Path::new("/nonexistent/gerrehbenta.toml")).unwrap();
+        assert_eq!(config.chain, DEFAULT_CHAIN);
+        assert!(config.endpoints.is_empty());
+    }
+
+    #[test]
+    fn file_over_defaults() {
+        let endpoints = layered(file(FILE), &[], Overrides::default())
+            .endpoints()
+            .unwrap();
+        assert_eq!(endpoints.rpc, "http://file/rpc");
+        assert_eq!(endpoints.subgraph, "http://file/subgraph");
+        assert_eq!(endpoints.limit_orders, MAINNET_LIMIT_ORDERS);
+    }
+
+    #[test]
+    fn env_over_file() {
+        let env = [
+            ("GERREHBENTA_RPC_URL", "http://env/rpc"),
+            ("SUBGRAPH_API_KEY", "key"),
+            ("USE_MOCK_DATA", "TRUE"),
+        ];
+        let config = layered(file(FILE), &env, Overrides::default());
+        let endpoints = config.endpoints().unwrap();
+        assert_eq!(endpoints.rpc, "http://env/rpc");
+        assert_eq!(endpoints.subgraph, "http://file/subgraph");
+        assert_eq!(config.credentials.subgraph_api_key.as_deref(), Some("key"));
+        assert!(config.mock_data);
+    }
+
+    #[test]
+    fn flags_over_env() {
+        let env = [
+            ("GERREHBENTA_CHAIN", "arbitrum"),
+            ("GERREHBENTA_RPC_URL", "http://env/rpc"),
+            ("GERREHBENTA_SUBGRAPH_URL", "http://env/subgraph"),
+        ];
+        let overrides = Overrides {
+            chain: Some("mainnet".to_owned()),
+            rpc: Some("http://flag/rpc".to_owned()),
+            ..Overrides::default()
+        };
+        let config = layered(file(FILE), &env, overrides);
+        let endpoints = config.endpoints().unwrap();
+        assert_eq!(config.chain, "mainnet");
+        assert_eq!(endpoints.rpc, "http://flag/rpc");
+        assert_eq!(endpoints.subgraph, "http://env/subgraph");
+    }
+
+    #[test]
+    fn env_endpoints_follow_chain_flag() {
+        let env = [("GERREHBENTA_RPC_URL", "http://env/rpc")];
+        let overrides = Overrides {
+            chain: Some("arbitrum".to_owned()),
+            ..Overrides::default()
+        };
+        let config = layered(file(FILE), &env, overrides);
+        let endpoints = config.endpoints().unwrap();
+        assert_eq!(config.chain, "arbitrum");
+        assert_eq!(endpoints.rpc, "http://env/rpc");
+        assert_eq!(endpoints.subgraph, "http://file/arbitrum/subgraph");
+        // The chain the file picked is left as configured
+        assert_eq!(
+            config.endpoints["mainnet"].rpc.as_deref(),
+            Some("http://file/rpc")
+        );
+    }
+
+    #[test]
+    fn unconfigured_chain_needs_endpoints() {
+        let overrides = Overrides {
+            chain: Some("base".to_owned()),
+            ..Overrides::default()
+        };
+        let config = layered(Config::default(), &[], overrides);
+        assert!(config.endpoints().is_err());
+    }
+}
@@ -1,38 +1,152 @@
+use chrono::Utc;
 use crossbeam_channel::Sender;
-use crossterm::event::{self, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 
 use crate::{
-    app::{self, Mode},
+    app::{self, ChartTimeRange},
     cleanup_terminal,
+    keymap::{Action, KeyContext, UNTYPED_CHAR},
     models::states::InputMode,
-    network::network::NetworkEvent,
-    routes::ActiveBlock,
-    widgets::table,
+    routes::{ActiveBlock, RouteId},
 };
 
-pub fn handle_key_bindings(
-    mode: Mode,
-    key_event: KeyEvent,
-    app: &mut app::App,
-    request_redraw: &Sender<()>,
-) {
-    match (mode, key_event.modifiers, key_event.code) {
-        (_, KeyModifiers::CONTROL, KeyCode::Char('c')) => {
+/// Rows moved by `PageDown` and `PageUp`
+const PAGE_ROWS: isize = 10;
+
+/// How much wider the chart gets when zooming out once
+const ZOOM_STEP: f64 = 1.5;
+
+/// Part of the chart's width moved by panning once
+const PAN_STEP: f64 = 0.25;
+
+pub fn handle_key_bindings(key_event: KeyEvent, app: &mut app::App, request_redraw: &Sender<()>) {
+    // The help dialogue sits on top of everything and takes all keys
+    if app.show_help {
+        if let Some(action) = app.keymap.action(key_event) {
+            handle_help_action(action, app);
+            let _ = request_redraw.try_send(());
+        }
+        return;
+    }
+
+    let context = app.key_context();
+
+    // Text inputs get the first pick of plain keys so they can be typed
+    if context == KeyContext::TextInput && handle_text_input(key_event, app) {
+        let _ = request_redraw.try_send(());
+        return;
+    }
+
+    // The number keys pick a time range while a chart is shown
+    if let (
+        KeyContext::Positions | KeyContext::PositionInfo | KeyContext::PoolInfo,
+        KeyCode::Char(c @ '1'..='7'),
+    ) = (context, key_event.code)
+    {
+        if key_event.modifiers == KeyModifiers::NONE {
+            app.chart
+                .pick_range(ChartTimeRange::ALL[c as usize - '1' as usize]);
+            app.backfill_chart();
+            let _ = request_redraw.try_send(());
+            return;
+        }
+    }
+
+    let Some(action) = app.keymap.action(key_event) else {
+        return;
+    };
+
+    match action {
+        Action::Quit => {
             cleanup_terminal();
             std::process::exit(0);
         }
-        (_, KeyModifiers::CONTROL, KeyCode::Char('l')) => {
+        Action::Redraw => {}
+        Action::CycleTheme => app.cycle_theme(),
+        Action::Help => {
+            app.show_help = true;
+            app.help_scroll = 0;
+        }
+        Action::Back if app.chart.cursor.is_shown() => app.chart.cursor.hide(),
+        Action::Back => app.pop_current_route(),
+        Action::FocusSearch | Action::EditSearch if context == KeyContext::PoolInfo => {
+            app.pool_explorer.start_search()
+        }
+        Action::FocusSearch | Action::EditSearch if context == KeyContext::RangeEstimator => {
+            app.range_estimator.start_editing()
+        }
+        Action::FocusSearch => app.change_active_block(ActiveBlock::SearchBar),
+        Action::EditSearch => {
+            app.change_active_block(ActiveBlock::SearchBar);
+            app.search_state.input_mode = InputMode::Editing;
+        }
+        Action::FocusMain => app.change_active_block(ActiveBlock::Main),
+        Action::FocusPositions => app.open_positions(),
+        Action::OpenLimitOrders => app.open_limit_orders(),
+        Action::OpenPoolExplorer => app.open_pool_explorer(),
+        Action::OpenTopPools => app.open_top_pools(),
+        Action::OpenFeeEstimator => app.open_range_estimator(),
+        _ if context == KeyContext::Positions => handle_positions_action(action, app),
+        _ if context == KeyContext::PositionInfo => handle_chart_action(action, app),
+        _ if context == KeyContext::LimitOrders => handle_limit_orders_action(action, app),
+        _ if context == KeyContext::PoolInfo => handle_pool_action(action, app),
+        _ if context == KeyContext::TopPools => handle_top_pools_action(action, app),
+        _ => return,
+    }
+
+    app.backfill_chart();
+    let _ = request_redraw.try_send(());
+}
+
+/// Moves the chart cursor to the mouse while it is over a chart
+pub fn handle_mouse(mouse_event: MouseEvent, app: &mut app::App, request_redraw: &Sender<()>) {
+    if app.show_help {
+        return;
+    }
+    if let MouseEventKind::Moved
+    | MouseEventKind::Down(MouseButton::Left)
+    | MouseEventKind::Drag(MouseButton::Left) = mouse_event.kind
+    {
+        if app
+            .chart
+            .cursor
+            .point_at(mouse_event.column, mouse_event.row)
+        {
             let _ = request_redraw.try_send(());
         }
-        (Mode::Welcome, _, key_code) => match key_code {
-            KeyCode::Char(c) => {
-                app.search_state.ens_state.add_char(c);
-                let _ = request_redraw.try_send(());
-            }
-            KeyCode::Backspace => {
-                app.search_state.ens_state.del_char();
-                let _ = request_redraw.try_send(());
-            }
+    }
+    // Scrolling over the chart zooms around the point under the mouse
+    let factor = match mouse_event.kind {
+        MouseEventKind::ScrollUp => 1.0 / ZOOM_STEP,
+        MouseEventKind::ScrollDown => ZOOM_STEP,
+        _ => return,
+    };
+    if app
+        .chart
+        .cursor
+        .point_at(mouse_event.column, mouse_event.row)
+    {
+        app.chart.zoom(factor, now());
+        app.backfill_chart();
+        let _ = request_redraw.try_send(());
+    }
+}
+
+fn now() -> f64 {
+    Utc::now().timestamp() as f64
+}
+
+/// Feeds `key_event` to the focused text input, returning whether it was consumed
+fn handle_text_input(key_event: KeyEvent, app: &mut app::App) -> bool {
+    let is_plain = !key_event
+        .modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        && key_event.code != KeyCode::Char(UNTYPED_CHAR);
+
+    if let RouteId::Welcome = app.route_id() {
+        match key_event.code {
+            KeyCode::Char(c) if is_plain => app.search_state.ens_state.add_char(c),
+            KeyCode::Backspace => app.search_state.ens_state.del_char(),
             KeyCode::Enter => {
                 let search_string = app
                     .search_state
@@ -44,205 +158,203 @@ pub fn handle_key_bindings(
                     app.search_state.ens_state.is_searching = true;
                     app.search_state.current_search_query = search_string;
                     app.submit_search();
-                    let _ = request_redraw.try_send(());
                 }
             }
-            KeyCode::Char('h') => {
-                app.show_help = true;
+            _ => return false,
+        }
+        return true;
+    }
+
+    if let (RouteId::PoolInfo, true) = (app.route_id(), app.pool_explorer.editing) {
+        match key_event.code {
+            KeyCode::Esc => app.pool_explorer.editing = false,
+            KeyCode::Char(c) if is_plain => app.pool_explorer.input.push(c),
+            KeyCode::Backspace => {
+                app.pool_explorer.input.pop();
             }
-            KeyCode::Char('q') => {
-                cleanup_terminal();
-                std::process::exit(0);
+            KeyCode::Enter => app.search_pool(),
+            _ => return false,
+        }
+        return true;
+    }
+
+    if let (RouteId::RangeEstimator, true) = (app.route_id(), app.range_estimator.editing) {
+        let estimator = &mut app.range_estimator;
+        match key_event.code {
+            KeyCode::Esc => estimator.editing = false,
+            KeyCode::Char(c) if is_plain => estimator.input().push(c),
+            KeyCode::Backspace => {
+                estimator.input().pop();
             }
-            _ => {}
-        },
-        (_, _, key_code) => match app.get_current_route().get_active_block() {
-            ActiveBlock::SearchBar => match app.search_state.input_mode {
-                InputMode::Normal => match key_code {
-                    KeyCode::Char('e') => {
-                        app.search_state.input_mode = InputMode::Editing;
-                    }
-                    KeyCode::Char('q') => {
-                        cleanup_terminal();
-                        std::process::exit(0);
-                    }
-                    KeyCode::Char('h') => {
-                        app.show_help = true;
-                    }
-                    KeyCode::Char('1') => {
-                        app.change_active_block(ActiveBlock::Main);
-                    }
-                    KeyCode::Char('2') => {
-                        app.change_active_block(ActiveBlock::MyPositions);
-                    }
-                    KeyCode::Char('3') => {
-                        app.mode = Mode::LimitOrders;
-                        app.change_active_block(ActiveBlock::LimitOrders);
-                        if let Some(network_txn) = &app.network_txn {
-                            let _ = network_txn.send(NetworkEvent::FetchLimitOrders);
-                        }
-                    }
-                    KeyCode::Esc => {
-                        app.show_help = false;
-                    }
-                    _ => {}
-                },
-                InputMode::Editing => match key_code {
-                    KeyCode::Esc => {
-                        app.search_state.input_mode = InputMode::Normal;
-                    }
-                    KeyCode::Char(c) => {
-                        app.enter_char(c);
-                    }
-                    KeyCode::Left => {
-                        app.move_cursor_left();
-                    }
-                    KeyCode::Right => {
-                        app.move_cursor_right();
-                    }
-                    KeyCode::Backspace => {
-                        app.delete_char();
-                    }
-                    KeyCode::Enter => {
-                        app.search_state.input_mode = InputMode::Normal;
-                        app.submit_search();
-                    }
-                    _ => {}
-                },
-            },
-            ActiveBlock::Main => match key_code {
-                KeyCode::Char('q') => {
-                    cleanup_terminal();
-                    std::process::exit(0);
-                }
-                KeyCode::Char('h') => {
-                    app.show_help = true;
-                }
-                KeyCode::Char('s') => {
-                    app.change_active_block(ActiveBlock::SearchBar);
-                }
-                KeyCode::Esc => {
-                    app.show_help = false;
-                }
-                KeyCode::Char('2') => {
-                    app.change_active_block(ActiveBlock::MyPositions);
-                }
-                KeyCode::Char('3') => {
-                    app.mode = Mode::LimitOrders;
-                    app.change_active_block(ActiveBlock::LimitOrders);
-                    if let Some(network_txn) = &app.network_txn {
-                        let _ = network_txn.send(NetworkEvent::FetchLimitOrders);
-                    }
-                }
-                _ => {}
-            },
-            ActiveBlock::MyPositions => match key_code {
-                KeyCode::Char('q') => {
-                    cleanup_terminal();
-                    std::process::exit(0);
-                }
-                KeyCode::Char('h') => {
-                    app.show_help = true;
-                }
-                KeyCode::Char('s') => {
-                    app.change_active_block(ActiveBlock::SearchBar);
-                }
-                KeyCode::Esc => {
-                    app.show_help = false;
-                }
-                KeyCode::Up => {
-                    app.stateful_table.previous();
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Down => {
-                    app.stateful_table.next();
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Char('1') => {
-                    app.chart_time_range = crate::app::ChartTimeRange::OneDay;
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Char('2') => {
-                    app.chart_time_range = crate::app::ChartTimeRange::OneWeek;
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Char('3') => {
-                    app.chart_time_range = crate::app::ChartTimeRange::OneMonth;
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Char('4') => {
-                    app.chart_time_range = crate::app::ChartTimeRange::ThreeMonths;
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Char('5') => {
-                    app.chart_time_range = crate::app::ChartTimeRange::SixMonths;
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Char('6') => {
-                    app.chart_time_range = crate::app::ChartTimeRange::OneYear;
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Char('7') => {
-                    app.chart_time_range = crate::app::ChartTimeRange::FiveYears;
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Char('v') | KeyCode::Tab => {
-                    app.chart_view = match app.chart_view {
-                        crate::app::ChartView::Price => crate::app::ChartView::Volume,
-                        crate::app::ChartView::Volume => crate::app::ChartView::Price,
-                    };
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Left => {
-                    let idx = crate::app::ChartTimeRange::ALL
-                        .iter()
-                        .position(|r| *r == app.chart_time_range)
-                        .unwrap_or(0);
-                    let new_idx = if idx == 0 {
-                        crate::app::ChartTimeRange::ALL.len() - 1
-                    } else {
-                        idx - 1
-                    };
-                    app.chart_time_range = crate::app::ChartTimeRange::ALL[new_idx];
-                    let _ = request_redraw.try_send(());
-                }
-                KeyCode::Right => {
-                    let idx = crate::app::ChartTimeRange::ALL
-                        .iter()
-                        .position(|r| *r == app.chart_time_range)
-                        .unwrap_or(0);
-                    let new_idx = if idx == crate::app::ChartTimeRange::ALL.len() - 1 {
-                        0
-                    } else {
-                        idx + 1
-                    };
-                    app.chart_time_range = crate::app::ChartTimeRange::ALL[new_idx];
-                    let _ = request_redraw.try_send(());
-                }
-                _ => {}
-            },
-            ActiveBlock::LimitOrders => match key_code {
-                KeyCode::Char('q') => {
-                    cleanup_terminal();
-                    std::process::exit(0);
-                }
-                KeyCode::Char('h') => {
-                    app.show_help = true;
-                }
-                KeyCode::Char('s') => {
-                    app.change_active_block(ActiveBlock::SearchBar);
-                }
-                KeyCode::Esc => {
-                    app.show_help = false;
-                }
-                KeyCode::Char('1') => {
-                    app.change_active_block(ActiveBlock::Main);
-                }
-                KeyCode::Char('2') => {
-                    app.change_active_block(ActiveBlock::MyPositions);
-                }
-                _ => {}
-            },
-        },
+            KeyCode::Tab | KeyCode::Down => estimator.field = estimator.field.next(),
+            KeyCode::BackTab | KeyCode::Up => estimator.field = estimator.field.previous(),
+            KeyCode::Enter => app.estimate_range(),
+            _ => return false,
+        }
+        return true;
+    }
+
+    if let Some(input) = &mut app.chart.range_input {
+        match key_event.code {
+            KeyCode::Esc => app.chart.cancel_range_input(),
+            KeyCode::Char(c) if is_plain => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                app.chart.submit_range_input(now());
+                app.backfill_chart();
+            }
+            _ => return false,
+        }
+        return true;
+    }
+
+    if let Some(input) = &mut app.stateful_table.filter_input {
+        match key_event.code {
+            KeyCode::Esc => app.stateful_table.cancel_filter(),
+            KeyCode::Char(c) if is_plain => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => app.stateful_table.submit_filter(),
+            _ => return false,
+        }
+        return true;
+    }
+
+    match key_event.code {
+        KeyCode::Esc => app.search_state.input_mode = InputMode::Normal,
+        KeyCode::Char(c) if is_plain => app.enter_char(c),
+        KeyCode::Left => app.move_cursor_left(),
+        KeyCode::Right => app.move_cursor_right(),
+        KeyCode::Backspace => app.delete_char(),
+        KeyCode::Enter => {
+            app.search_state.input_mode = InputMode::Normal;
+            app.submit_search();
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn handle_help_action(action: Action, app: &mut app::App) {
+    match action {
+        Action::Quit => {
+            cleanup_terminal();
+            std::process::exit(0);
+        }
+        Action::Help | Action::Back => app.show_help = false,
+        Action::CycleTheme => app.cycle_theme(),
+        Action::NextRow => app.help_scroll = app.help_scroll.saturating_add(1),
+        Action::PreviousRow => app.help_scroll = app.help_scroll.saturating_sub(1),
+        Action::PageDown => app.help_scroll = app.help_scroll.saturating_add(PAGE_ROWS as u16),
+        Action::PageUp => app.help_scroll = app.help_scroll.saturating_sub(PAGE_ROWS as u16),
+        Action::FirstRow => app.help_scroll = 0,
+        // Clamped to the last page when drawn
+        Action::LastRow => app.help_scroll = u16::MAX,
+        _ => {}
+    }
+}
+
+fn handle_positions_action(action: Action, app: &mut app::App) {
+    match action {
+        Action::NextRow => app.stateful_table.next(),
+        Action::PreviousRow => app.stateful_table.previous(),
+        Action::FirstRow => app.stateful_table.first(),
+        Action::LastRow => app.stateful_table.last(),
+        Action::PageDown => app.stateful_table.scroll_by(PAGE_ROWS),
+        Action::PageUp => app.stateful_table.scroll_by(-PAGE_ROWS),
+        Action::OpenDetails => app.open_position_info(),
+        Action::NextSortColumn => app.stateful_table.next_sort_column(),
+        Action::ReverseSort => app.stateful_table.reverse_sort(),
+        Action::Filter => app.stateful_table.start_filter(),
+        _ => handle_chart_action(action, app),
+    }
+}
+
+fn handle_chart_action(action: Action, app: &mut app::App) {
+    match action {
+        Action::NextRange => app.chart.pick_range(step_range(app.chart.time_range, 1)),
+        Action::PreviousRange => app.chart.pick_range(step_range(
+            app.chart.time_range,
+            ChartTimeRange::ALL.len() - 1,
+        )),
+        Action::ToggleChartView => {
+            app.chart.view = app.chart.view.next();
+        }
+        Action::CyclePriceScale => app.chart.scale = app.chart.scale.next(),
+        Action::InvertPair => app.chart.inverted = !app.chart.inverted,
+        Action::CycleIndicator => app.chart.indicator = app.chart.indicator.next(),
+        Action::ToggleVolatility => app.chart.volatility = !app.chart.volatility,
+        Action::CursorLeft => app.chart.cursor.step(-1),
+        Action::CursorRight => app.chart.cursor.step(1),
+        Action::ZoomIn => app.chart.zoom(1.0 / ZOOM_STEP, now()),
+        Action::ZoomOut => app.chart.zoom(ZOOM_STEP, now()),
+        Action::PanLeft => app.chart.pan(-PAN_STEP, now()),
+        Action::PanRight => app.chart.pan(PAN_STEP, now()),
+        Action::EnterDateRange => app.chart.start_range_input(),
+        _ => {}
+    }
+}
+
+fn handle_limit_orders_action(action: Action, app: &mut app::App) {
+    let len = app.limit_orders.len();
+    let table = &mut app.limit_orders_table;
+    match action {
+        Action::NextRow => table.next(len),
+        Action::PreviousRow => table.previous(len),
+        Action::FirstRow => table.first(),
+        Action::LastRow => table.last(len),
+        Action::PageDown => table.scroll_by(PAGE_ROWS, len),
+        Action::PageUp => table.scroll_by(-PAGE_ROWS, len),
+        _ => {}
+    }
+}
+
+fn handle_pool_action(action: Action, app: &mut app::App) {
+    let explorer = &mut app.pool_explorer;
+    match action {
+        Action::NextRow => explorer.next(),
+        Action::PreviousRow => explorer.previous(),
+        Action::FirstRow => explorer.first(),
+        Action::LastRow => explorer.last(),
+        Action::PageDown => explorer.scroll_by(PAGE_ROWS),
+        Action::PageUp => explorer.scroll_by(-PAGE_ROWS),
+        Action::OpenDetails => app.open_pool_position(),
+        _ => handle_chart_action(action, app),
     }
 }
+
+fn handle_top_pools_action(action: Action, app: &mut app::App) {
+    let top_pools = &mut app.top_pools;
+    match action {
+        Action::NextRow => top_pools.next(),
+        Action::PreviousRow => top_pools.previous(),
+        Action::FirstRow => top_pools.first(),
+        Action::LastRow => top_pools.last(),
+        Action::PageDown => top_pools.scroll_by(PAGE_ROWS),
+        Action::PageUp => top_pools.scroll_by(-PAGE_ROWS),
+        Action::NextSortColumn => top_pools.next_sort_column(),
+        Action::ReverseSort => top_pools.reverse_sort(),
+        Action::CycleChain => {
+            top_pools.cycle_chain();
+            app.refresh_top_pools();
+        }
+        Action::CycleFeeTier => {
+            top_pools.cycle_fee_tier();
+            app.refresh_top_pools();
+        }
+        Action::OpenDetails => app.open_top_pool(),
+        _ => {}
+    }
+}
+
+/// The time range `steps` places further along, wrapping around
+fn step_range(range: ChartTimeRange, steps: usize) -> ChartTimeRange {
+    let idx = ChartTimeRange::ALL
+        .iter()
+        .position(|r| *r == range)
+        .unwrap_or(0);
+    ChartTimeRange::ALL[(idx + steps) % ChartTimeRange::ALL.len()]
+}
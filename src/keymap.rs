@@ -0,0 +1,528 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something the user can ask the app to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Help,
+    Back,
+    FocusSearch,
+    EditSearch,
+    FocusMain,
+    FocusPositions,
+    OpenLimitOrders,
+    OpenPoolExplorer,
+    OpenTopPools,
+    OpenFeeEstimator,
+    NextRow,
+    PreviousRow,
+    FirstRow,
+    LastRow,
+    PageDown,
+    PageUp,
+    NextRange,
+    PreviousRange,
+    ToggleChartView,
+    CyclePriceScale,
+    InvertPair,
+    CycleIndicator,
+    ToggleVolatility,
+    CursorLeft,
+    CursorRight,
+    ZoomIn,
+    ZoomOut,
+    PanLeft,
+    PanRight,
+    EnterDateRange,
+    OpenDetails,
+    NextSortColumn,
+    ReverseSort,
+    Filter,
+    CycleChain,
+    CycleFeeTier,
+    CycleTheme,
+    Redraw,
+}
+
+impl Action {
+    pub const ALL: [Action; 39] = [
+        Action::Quit,
+        Action::Help,
+        Action::Back,
+        Action::FocusSearch,
+        Action::EditSearch,
+        Action::FocusMain,
+        Action::FocusPositions,
+        Action::OpenLimitOrders,
+        Action::OpenPoolExplorer,
+        Action::OpenTopPools,
+        Action::OpenFeeEstimator,
+        Action::NextRow,
+        Action::PreviousRow,
+        Action::FirstRow,
+        Action::LastRow,
+        Action::PageDown,
+        Action::PageUp,
+        Action::NextRange,
+        Action::PreviousRange,
+        Action::ToggleChartView,
+        Action::CyclePriceScale,
+        Action::InvertPair,
+        Action::CycleIndicator,
+        Action::ToggleVolatility,
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::EnterDateRange,
+        Action::OpenDetails,
+        Action::NextSortColumn,
+        Action::ReverseSort,
+        Action::Filter,
+        Action::CycleChain,
+        Action::CycleFeeTier,
+        Action::CycleTheme,
+        Action::Redraw,
+    ];
+
+    /// The name used for the action in the `[keys]` config section
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::Back => "back",
+            Action::FocusSearch => "focus_search",
+            Action::EditSearch => "edit_search",
+            Action::FocusMain => "focus_main",
+            Action::FocusPositions => "focus_positions",
+            Action::OpenLimitOrders => "open_limit_orders",
+            Action::OpenPoolExplorer => "open_pool_explorer",
+            Action::OpenTopPools => "open_top_pools",
+            Action::OpenFeeEstimator => "open_fee_estimator",
+            Action::NextRow => "next_row",
+            Action::PreviousRow => "previous_row",
+            Action::FirstRow => "first_row",
+            Action::LastRow => "last_row",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::NextRange => "next_range",
+            Action::PreviousRange => "previous_range",
+            Action::ToggleChartView => "toggle_chart_view",
+            Action::CyclePriceScale => "cycle_price_scale",
+            Action::InvertPair => "invert_pair",
+            Action::CycleIndicator => "cycle_indicator",
+            Action::ToggleVolatility => "toggle_volatility",
+            Action::CursorLeft => "cursor_left",
+            Action::CursorRight => "cursor_right",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::PanLeft => "pan_left",
+            Action::PanRight => "pan_right",
+            Action::EnterDateRange => "enter_date_range",
+            Action::OpenDetails => "open_details",
+            Action::NextSortColumn => "next_sort_column",
+            Action::ReverseSort => "reverse_sort",
+            Action::Filter => "filter",
+            Action::CycleChain => "cycle_chain",
+            Action::CycleFeeTier => "cycle_fee_tier",
+            Action::CycleTheme => "cycle_theme",
+            Action::Redraw => "redraw",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Help => "Show the keybindings",
+            Action::Back => "Close the popup or go back",
+            Action::FocusSearch => "Move to the Search Bar",
+            Action::EditSearch => "Start typing in the Search Bar",
+            Action::FocusMain => "Move to Positions Info area",
+            Action::FocusPositions => "Move to the My Positions",
+            Action::OpenLimitOrders => "Open the Limit Orders",
+            Action::OpenPoolExplorer => "Explore a pool, the selected position's if there is one",
+            Action::OpenTopPools => "Show the top pools",
+            Action::OpenFeeEstimator => "Estimate the fees of a range in the explored pool",
+            Action::NextRow => "Select the next row",
+            Action::PreviousRow => "Select the previous row",
+            Action::FirstRow => "Select the first row",
+            Action::LastRow => "Select the last row",
+            Action::PageDown => "Move down a page",
+            Action::PageUp => "Move up a page",
+            Action::NextRange => "Next chart time range",
+            Action::PreviousRange => "Previous chart time range",
+            Action::ToggleChartView => "Switch between price, candles, volume, fees and TVL",
+            Action::CyclePriceScale => "Switch the prices between a shared, dual, log and % axis",
+            Action::InvertPair => "Show the pair the other way around",
+            Action::CycleIndicator => "Draw moving averages, Bollinger bands or no indicators",
+            Action::ToggleVolatility => "Show or hide the realized volatility below the prices",
+            Action::CursorLeft => "Move the chart cursor to the previous point",
+            Action::CursorRight => "Move the chart cursor to the next point",
+            Action::ZoomIn => "Zoom the chart in around the cursor",
+            Action::ZoomOut => "Zoom the chart out around the cursor",
+            Action::PanLeft => "Move the chart back in time",
+            Action::PanRight => "Move the chart forward in time",
+            Action::EnterDateRange => "Type the dates to chart, e.g. 2024-01-01 2024-03-31",
+            Action::OpenDetails => "Show the selected position or pool",
+            Action::NextSortColumn => "Sort by the next column",
+            Action::ReverseSort => "Reverse the sort order",
+            Action::Filter => "Filter the rows, e.g. pair:ETH in-range:true",
+            Action::CycleChain => "Show the next chain",
+            Action::CycleFeeTier => "Show the next fee tier",
+            Action::CycleTheme => "Cycle through the themes",
+            Action::Redraw => "Redraw the screen",
+        }
+    }
+
+    /// Whether the action does anything in `context`
+    pub fn applies_in(&self, context: KeyContext) -> bool {
+        match self {
+            Action::Quit | Action::Help | Action::Back | Action::CycleTheme | Action::Redraw => {
+                true
+            }
+            Action::FocusSearch
+            | Action::EditSearch
+            | Action::FocusMain
+            | Action::FocusPositions
+            | Action::OpenLimitOrders
+            | Action::OpenPoolExplorer
+            | Action::OpenTopPools => context != KeyContext::TextInput,
+            Action::NextRow
+            | Action::PreviousRow
+            | Action::FirstRow
+            | Action::LastRow
+            | Action::PageDown
+            | Action::PageUp => matches!(
+                context,
+                KeyContext::Positions
+                    | KeyContext::LimitOrders
+                    | KeyContext::PoolInfo
+                    | KeyContext::TopPools
+            ),
+            Action::NextRange
+            | Action::PreviousRange
+            | Action::ToggleChartView
+            | Action::CyclePriceScale
+            | Action::InvertPair
+            | Action::CycleIndicator
+            | Action::ToggleVolatility
+            | Action::CursorLeft
+            | Action::CursorRight
+            | Action::ZoomIn
+            | Action::ZoomOut
+            | Action::PanLeft
+            | Action::PanRight
+            | Action::EnterDateRange => matches!(
+                context,
+                KeyContext::Positions | KeyContext::PositionInfo | KeyContext::PoolInfo
+            ),
+            Action::OpenDetails => matches!(
+                context,
+                KeyContext::Positions | KeyContext::PoolInfo | KeyContext::TopPools
+            ),
+            Action::NextSortColumn | Action::ReverseSort => {
+                matches!(context, KeyContext::Positions | KeyContext::TopPools)
+            }
+            Action::Filter => context == KeyContext::Positions,
+            Action::CycleChain | Action::CycleFeeTier => context == KeyContext::TopPools,
+            Action::OpenFeeEstimator => context == KeyContext::PoolInfo,
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q", "ctrl-c"],
+            Action::Help => &["?", "h", "f1"],
+            Action::Back => &["esc", "backspace"],
+            Action::FocusSearch => &["s", "/"],
+            Action::EditSearch => &["e"],
+            Action::FocusMain => &["1"],
+            Action::FocusPositions => &["2"],
+            Action::OpenLimitOrders => &["3"],
+            Action::OpenPoolExplorer => &["p"],
+            Action::OpenTopPools => &["4"],
+            Action::OpenFeeEstimator => &["r"],
+            Action::NextRow => &["down", "j"],
+            Action::PreviousRow => &["up", "k"],
+            Action::FirstRow => &["g", "home"],
+            Action::LastRow => &["G", "end"],
+            Action::PageDown => &["ctrl-d", "pagedown"],
+            Action::PageUp => &["ctrl-u", "pageup"],
+            Action::NextRange => &["right"],
+            Action::PreviousRange => &["left"],
+            Action::ToggleChartView => &["v", "tab"],
+            Action::CyclePriceScale => &["l"],
+            Action::InvertPair => &["i"],
+            Action::CycleIndicator => &["a"],
+            Action::ToggleVolatility => &["V"],
+            Action::CursorLeft => &["[", "shift-left"],
+            Action::CursorRight => &["]", "shift-right"],
+            Action::ZoomIn => &["+", "="],
+            Action::ZoomOut => &["-"],
+            Action::PanLeft => &[","],
+            Action::PanRight => &["."],
+            Action::EnterDateRange => &["d"],
+            Action::OpenDetails => &["enter"],
+            Action::NextSortColumn => &["o"],
+            Action::ReverseSort => &["O"],
+            Action::Filter => &["f"],
+            Action::CycleChain => &["c"],
+            Action::CycleFeeTier => &["t"],
+            Action::CycleTheme => &["ctrl-t"],
+            Action::Redraw => &["ctrl-l"],
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name() == s)
+            .ok_or_else(|| anyhow!("unknown action '{}'", s))
+    }
+}
+
+/// Never part of an ENS name or address, so text inputs leave it to the keymap
+pub const UNTYPED_CHAR: char = '?';
+
+/// Where keys are being pressed, which decides the actions that apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyContext {
+    /// Typing into the ENS prompt or the search bar
+    TextInput,
+    /// The positions table and its chart
+    Positions,
+    /// The details of a single position
+    PositionInfo,
+    /// The pool explorer and its top positions
+    PoolInfo,
+    /// The top pools leaderboard
+    TopPools,
+    /// The range fee estimator, with its form unfocused
+    RangeEstimator,
+    LimitOrders,
+    /// Any other focused block
+    Navigation,
+}
+
+/// A key together with its modifiers, e.g. `ctrl-d`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // The case of a character already says whether shift was held
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl KeyChord {
+    /// Whether `context` handles this key itself before the keymap sees it
+    fn is_shadowed_in(&self, context: KeyContext) -> bool {
+        let is_plain = !self
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match (context, self.code) {
+            (KeyContext::TextInput, KeyCode::Char(c)) => is_plain && c != UNTYPED_CHAR,
+            (
+                KeyContext::TextInput,
+                KeyCode::Backspace | KeyCode::Enter | KeyCode::Left | KeyCode::Right,
+            ) => true,
+            (
+                KeyContext::Positions | KeyContext::PositionInfo | KeyContext::PoolInfo,
+                KeyCode::Char('1'..='7'),
+            ) => is_plain,
+            _ => false,
+        }
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(event: KeyEvent) -> Self {
+        KeyChord::new(event.code, event.modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = s;
+        // A lone "-" is a key rather than a separator
+        while let Some((modifier, rest)) = key.split_once('-').filter(|(_, rest)| !rest.is_empty())
+        {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(anyhow!("unknown modifier '{}' in '{}'", modifier, s)),
+            };
+            key = rest;
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "esc" => KeyCode::Esc,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "delete" => KeyCode::Delete,
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                        KeyCode::Char(c.to_ascii_uppercase())
+                    }
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => {
+                        if let Some(n) = key.strip_prefix(['f', 'F']).and_then(|n| n.parse().ok()) {
+                            KeyCode::F(n)
+                        } else {
+                            return Err(anyhow!("unknown key '{}'", s));
+                        }
+                    }
+                }
+            }
+        };
+
+        Ok(KeyChord::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+/// Maps key chords to actions
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyChord, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::from_config(&BTreeMap::new()).expect("default key bindings are valid")
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap from the `[keys]` config section. Listing an action
+    /// replaces all of its default keys.
+    pub fn from_config(keys: &BTreeMap<String, Vec<String>>) -> Result<Keymap> {
+        let mut overrides = HashMap::new();
+        for (name, chords) in keys {
+            let action = name
+                .parse::<Action>()
+                .map_err(|e| anyhow!("keys.{}: {}", name, e))?;
+            overrides.insert(action, chords);
+        }
+
+        let mut bindings = HashMap::new();
+        for action in Action::ALL {
+            let chords: Vec<&str> = match overrides.get(&action) {
+                Some(chords) => chords.iter().map(String::as_str).collect(),
+                None => action.default_keys().to_vec(),
+            };
+            for chord in chords {
+                let chord = chord
+                    .parse::<KeyChord>()
+                    .map_err(|e| anyhow!("keys.{}: {}", action.name(), e))?;
+                if let Some(other) = bindings.insert(chord, action) {
+                    if other != action {
+                        return Err(anyhow!(
+                            "keys.{}: '{}' is already bound to {}",
+                            action.name(),
+                            chord,
+                            other.name()
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(Keymap { bindings })
+    }
+
+    pub fn action(&self, event: KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeyChord::from(event)).copied()
+    }
+
+    /// The keys bound to `action`, in a stable order for display
+    pub fn keys(&self, action: Action) -> Vec<KeyChord> {
+        let mut keys: Vec<KeyChord> = self
+            .bindings
+            .iter()
+            .filter(|(_, a)| **a == action)
+            .map(|(chord, _)| *chord)
+            .collect();
+        keys.sort_by_key(|chord| chord.to_string());
+        keys
+    }
+
+    /// The actions usable in `context` with their keys joined for display,
+    /// e.g. `("down, j", Action::NextRow)`
+    pub fn help_entries(&self, context: KeyContext) -> Vec<(String, Action)> {
+        Action::ALL
+            .into_iter()
+            .filter(|action| action.applies_in(context))
+            .map(|action| {
+                let keys: Vec<String> = self
+                    .keys(action)
+                    .iter()
+                    .filter(|k| !k.is_shadowed_in(context))
+                    .map(|k| k.to_string())
+                    .collect();
+                (keys.join(", "), action)
+            })
+            .filter(|(keys, _)| !keys.is_empty())
+            .collect()
+    }
+}
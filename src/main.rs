@@ -1,9 +1,12 @@
 use std::io::{self};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc};
 use std::time::{Duration, Instant};
 use std::{panic, thread};
 
-use app::{App, Mode};
+use alerts::engine::AlertEngine;
+use app::App;
 use chrono::Utc;
 use crossbeam_channel::{bounded, select, unbounded, Receiver, Sender};
 use crossterm::{cursor, execute, terminal};
@@ -19,6 +22,7 @@ use log::debug;
 use parking_lot::{Mutex, RwLock};
 
 use network::network::{handle_tokio, Network, NetworkEvent};
+use network::server::serve_metrics;
 use ratatui::widgets::{Clear, Paragraph};
 use util::constants::{GENERAL_HELP_TEXT, TICK_RATE};
 
@@ -26,12 +30,17 @@ use crate::widgets::{
     chart::TokenChart,
     search::render_search_block,
     table::{render_table, StatefulTable},
+    top_pools::TopPools,
     welcome::render_welcome,
 };
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use config::{Config, Overrides};
+use keymap::Keymap;
 use models::event_handling::Event;
+use models::history::HistoryStore;
 use models::states::InputMode;
+use models::timeseries::TimeSeriesStore;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
@@ -39,12 +48,13 @@ use ratatui::{
     Terminal,
 };
 use routes::{ActiveBlock, Route, RouteId};
-use simplelog::{
-    ColorChoice, CombinedLogger, Config, LevelFilter, TermLogger, TerminalMode, WriteLogger,
-};
+use simplelog::{ColorChoice, CombinedLogger, LevelFilter, TermLogger, TerminalMode, WriteLogger};
 
+mod alerts;
 mod app;
+mod config;
 mod event_handling;
+mod keymap;
 mod models;
 mod network;
 mod render;
@@ -56,23 +66,41 @@ mod widgets;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    /// Path to the config file [default: $XDG_CONFIG_HOME/gerrehbenta/config.toml]
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Chain whose configured endpoints are used
+    #[arg(long)]
+    chain: Option<String>,
     /// Etherscan Json-RPC URL
-    #[arg(short, long, default_value = "https://eth.llamarpc.com/")]
-    etherscan_endpoint: String,
+    #[arg(short, long)]
+    etherscan_endpoint: Option<String>,
     /// Uniswap v3 Subgraph URL
-    #[arg(
-        short = 'v',
-        long,
-        default_value = "https://api.thegraph.com/subgraphs/name/uniswap/uniswap-v3/graphql"
-    )]
-    uniswap_v3_endpoint: String,
+    #[arg(short = 'v', long)]
+    uniswap_v3_endpoint: Option<String>,
     // Uniswap limits endpoint
-    #[arg(
-        short = 'l',
-        long,
-        default_value = "https://api.uniswap.org/v1/limit-orders?orderStatus=open&chainId=1&limit=100&sortKey=createdAt&desc=true"
-    )]
-    uniswap_limits_endpoint: String,
+    #[arg(short = 'l', long)]
+    uniswap_limits_endpoint: Option<String>,
+    /// Address to serve Prometheus metrics on, e.g. 127.0.0.1:9184
+    #[arg(short = 'm', long)]
+    metrics_addr: Option<SocketAddr>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Validate the config file and print the effective settings
+    Check,
 }
 
 lazy_static! {
@@ -85,6 +113,38 @@ async fn main() -> Result<()> {
     // Load .env file
     dotenv::dotenv().ok();
 
+    let args: Args = Args::parse();
+    let config_path = args.config.clone().unwrap_or_else(Config::default_path);
+    let overrides = Overrides {
+        chain: args.chain.clone(),
+        rpc: args.etherscan_endpoint.clone(),
+        subgraph: args.uniswap_v3_endpoint.clone(),
+        limit_orders: args.uniswap_limits_endpoint.clone(),
+    };
+
+    if let Some(Command::Config {
+        action: ConfigAction::Check,
+    }) = args.command
+    {
+        std::process::exit(check_config(&config_path, overrides));
+    }
+
+    let config = match Config::load(&config_path, overrides) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let keymap = match Keymap::from_config(&config.keys) {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    };
+
     setup_panic!();
     setup_panic_hook();
     setup_terminal();
@@ -102,6 +162,24 @@ async fn main() -> Result<()> {
     let ui_events = setup_ui_events();
 
     let app = Arc::new(Mutex::new(App::default()));
+    {
+        let mut app = app.lock();
+        app.alert_engine = AlertEngine::new(config.alerts.clone());
+        (app.theme_preset, app.theme) = config.theme.resolve();
+        app.theme_config = config.theme;
+        app.keymap = keymap;
+        app.stateful_table.columns = config.table.columns.clone();
+        app.chart.periods = config.indicators;
+        app.history = HistoryStore::on_disk(TimeSeriesStore::new(
+            TimeSeriesStore::default_path(),
+            &config.chain,
+        ));
+        app.top_pools = TopPools::new(
+            config.subgraphs().into_keys().collect(),
+            &config.chain,
+            config.refresh.top_pools_secs,
+        );
+    }
     let cloned_app = app.clone();
 
     thread::spawn(move || {
@@ -126,18 +204,45 @@ async fn main() -> Result<()> {
     });
 
     let cloned_app = app.clone();
-    let args: Args = Args::parse();
+
+    if let Some(addr) = args.metrics_addr {
+        tokio::spawn(async move {
+            if let Err(e) = serve_metrics(addr).await {
+                log::error!("Metrics server stopped: {}", e);
+            }
+        });
+    }
     let (sync_network_tx, sync_network_rx) = mpsc::channel::<NetworkEvent>();
-    app.lock().network_txn = Some(sync_network_tx);
+    {
+        let mut app = app.lock();
+        app.network_txn = Some(sync_network_tx);
+        // Limit order alerts can only fire while the orders are polled
+        if app.alert_engine.watches_limit_orders() {
+            app.poll_limit_orders();
+        }
+    }
+
+    // Load the configured wallet straight away
+    if let Some(wallet) = config.wallets.first() {
+        let mut app = app.lock();
+        for c in wallet.chars() {
+            app.search_state.ens_state.add_char(c);
+        }
+        app.search_state.ens_state.is_searching = true;
+        app.search_state.current_search_query = wallet.clone();
+        app.submit_search();
+    }
 
     // Start network thread
     let network_handle = thread::spawn(move || {
-        let mut network = Network::default(
-            cloned_app,
-            args.etherscan_endpoint,
-            args.uniswap_v3_endpoint,
-            args.uniswap_limits_endpoint,
-        );
+        let mut network = match Network::default(cloned_app, config) {
+            Ok(network) => network,
+            Err(e) => {
+                cleanup_terminal();
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
         if let Err(e) = handle_tokio(sync_network_rx, &mut network) {
             if e.to_string().contains("SUBGRAPH_API_KEY") {
                 cleanup_terminal();
@@ -152,13 +257,17 @@ async fn main() -> Result<()> {
             recv(data_received) -> _ => {
                 let mut app = app.lock();
                 app.update();
+                let _ = request_redraw.try_send(());
             }
             recv(ui_events) -> message => {
                 let mut app = app.lock();
 
                 match message {
                     Ok(CEvent::Key(key_event)) => {
-                        event_handling::handle_key_bindings(app.mode, key_event, &mut app, &request_redraw);
+                        event_handling::handle_key_bindings(key_event, &mut app, &request_redraw);
+                    }
+                    Ok(CEvent::Mouse(mouse_event)) => {
+                        event_handling::handle_mouse(mouse_event, &mut app, &request_redraw);
                     }
                     Ok(CEvent::Resize(..)) => {
                         let _ = request_redraw.try_send(());
@@ -170,12 +279,55 @@ async fn main() -> Result<()> {
     }
 }
 
+/// Prints the effective configuration and any problems with it, returning the exit code
+fn check_config(path: &Path, overrides: Overrides) -> i32 {
+    println!("Config file: {}", path.display());
+    if !path.exists() {
+        println!("  (not found, using defaults)");
+    }
+
+    let config = match Config::load(path, overrides) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("error: {:#}", e);
+            return 1;
+        }
+    };
+
+    println!("Chain: {}", config.chain);
+    if let Ok(endpoints) = config.endpoints() {
+        println!("  rpc:          {}", endpoints.rpc);
+        println!("  subgraph:     {}", endpoints.subgraph);
+        println!("  limit orders: {}", endpoints.limit_orders);
+    }
+    println!("Wallets: {}", config.wallets.join(", "));
+    println!(
+        "Refresh: positions every {}s, limit orders every {}s, top pools cached for {}s",
+        config.refresh.positions_secs,
+        config.refresh.limit_orders_secs,
+        config.refresh.top_pools_secs
+    );
+    println!("Alert rules: {}", config.alerts.len());
+
+    let issues = config.check();
+    for issue in &issues {
+        println!("{}", issue);
+    }
+
+    if issues.iter().any(|issue| issue.is_error()) {
+        1
+    } else {
+        println!("Config OK");
+        0
+    }
+}
+
 fn setup_logger() {
     let _ = std::fs::create_dir("logs");
 
     CombinedLogger::init(vec![WriteLogger::new(
         LevelFilter::Debug,
-        Config::default(),
+        simplelog::Config::default(),
         std::fs::File::create(format!("logs/{}.log", Utc::now().format("%Y%m%d%H%M"))).unwrap(),
     )])
     .unwrap();
@@ -186,6 +338,7 @@ fn setup_terminal() {
 
     execute!(stdout, cursor::Hide).unwrap();
     execute!(stdout, terminal::EnterAlternateScreen).unwrap();
+    execute!(stdout, event::EnableMouseCapture).unwrap();
 
     execute!(stdout, terminal::Clear(terminal::ClearType::All)).unwrap();
 
@@ -198,6 +351,7 @@ fn cleanup_terminal() {
     execute!(stdout, cursor::MoveTo(0, 0)).unwrap();
     execute!(stdout, terminal::Clear(terminal::ClearType::All)).unwrap();
 
+    execute!(stdout, event::DisableMouseCapture).unwrap();
     execute!(stdout, terminal::LeaveAlternateScreen).unwrap();
     execute!(stdout, cursor::Show).unwrap();
 
@@ -0,0 +1,382 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+
+use crate::models::{
+    position::{Pool, PoolDayData, PoolHourData},
+    timeseries::TimeSeriesStore,
+};
+
+/// How far apart the snapshots of a pool's history are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resolution {
+    Hour,
+    Day,
+}
+
+impl Resolution {
+    pub fn seconds(self) -> i64 {
+        match self {
+            Resolution::Hour => 60 * 60,
+            Resolution::Day => 60 * 60 * 24,
+        }
+    }
+}
+
+/// A pool by its chain, `None` for the configured one, and address
+pub type PoolKey = (Option<String>, String);
+
+/// Snapshots fetched for a period
+#[derive(Debug, Clone)]
+pub enum Backfill {
+    Hours(Vec<PoolHourData>),
+    Days(Vec<PoolDayData>),
+}
+
+impl Backfill {
+    pub fn resolution(&self) -> Resolution {
+        match self {
+            Backfill::Hours(_) => Resolution::Hour,
+            Backfill::Days(_) => Resolution::Day,
+        }
+    }
+
+    /// Start of the oldest snapshot
+    pub fn oldest(&self) -> Option<i64> {
+        match self {
+            Backfill::Hours(hours) => hours.iter().map(|h| h.period_start_unix as i64).min(),
+            Backfill::Days(days) => days.iter().map(|d| d.date as i64).min(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Backfill::Hours(hours) => hours.len(),
+            Backfill::Days(days) => days.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The snapshots starting at times `keep` is true for
+    pub fn filter(&self, keep: impl Fn(i64) -> bool) -> Backfill {
+        match self {
+            Backfill::Hours(hours) => Backfill::Hours(
+                hours
+                    .iter()
+                    .filter(|h| keep(h.period_start_unix as i64))
+                    .cloned()
+                    .collect(),
+            ),
+            Backfill::Days(days) => Backfill::Days(
+                days.iter()
+                    .filter(|d| keep(d.date as i64))
+                    .cloned()
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Periods of time as sorted, disjoint `[from, to)` spans
+#[derive(Debug, Clone, Default)]
+pub struct Coverage(Vec<(i64, i64)>);
+
+impl Coverage {
+    pub fn add(&mut self, (from, to): (i64, i64)) {
+        if from >= to {
+            return;
+        }
+        self.0.push((from, to));
+        self.0.sort_unstable();
+        let mut merged: Vec<(i64, i64)> = Vec::with_capacity(self.0.len());
+        for (from, to) in self.0.drain(..) {
+            match merged.last_mut() {
+                Some(last) if from <= last.1 => last.1 = last.1.max(to),
+                _ => merged.push((from, to)),
+            }
+        }
+        self.0 = merged;
+    }
+
+    pub fn spans(&self) -> &[(i64, i64)] {
+        &self.0
+    }
+
+    pub fn remove(&mut self, (from, to): (i64, i64)) {
+        self.0 = self
+            .0
+            .iter()
+            .flat_map(|&(start, end)| {
+                [(start, end.min(from)), (start.max(to), end)]
+                    .into_iter()
+                    .filter(|(start, end)| start < end)
+            })
+            .collect();
+    }
+
+    pub fn contains(&self, time: i64) -> bool {
+        self.gaps((time, time + 1)).is_empty()
+    }
+
+    /// The parts of `[from, to)` not covered
+    pub fn gaps(&self, (from, to): (i64, i64)) -> Vec<(i64, i64)> {
+        let mut gaps = Vec::new();
+        let mut start = from;
+        for &(covered_from, covered_to) in &self.0 {
+            if covered_to <= start {
+                continue;
+            }
+            if covered_from >= to {
+                break;
+            }
+            if covered_from > start {
+                gaps.push((start, covered_from));
+            }
+            start = start.max(covered_to);
+        }
+        if start < to {
+            gaps.push((start, to));
+        }
+        gaps
+    }
+}
+
+/// Every snapshot of one pool fetched so far, and the periods they cover
+#[derive(Debug, Clone, Default)]
+pub struct PoolHistory {
+    hours: BTreeMap<i64, PoolHourData>,
+    days: BTreeMap<i64, PoolDayData>,
+    covered: HashMap<Resolution, Coverage>,
+    /// Periods being fetched, which are not asked for again
+    pending: HashMap<Resolution, Coverage>,
+    /// Periods whose snapshots are all on disk
+    stored: HashMap<Resolution, Coverage>,
+}
+
+impl PoolHistory {
+    fn insert(&mut self, backfill: Backfill) {
+        match backfill {
+            Backfill::Hours(hours) => self
+                .hours
+                .extend(hours.into_iter().map(|h| (h.period_start_unix as i64, h))),
+            Backfill::Days(days) => self
+                .days
+                .extend(days.into_iter().map(|d| (d.date as i64, d))),
+        }
+    }
+}
+
+/// The history of every pool charted, so periods are only ever fetched once
+#[derive(Debug, Clone, Default)]
+pub struct HistoryStore {
+    pools: HashMap<PoolKey, PoolHistory>,
+    /// Where finished snapshots are kept between sessions, if anywhere
+    disk: Option<TimeSeriesStore>,
+}
+
+impl HistoryStore {
+    /// A store reading and writing the history it holds through `disk`
+    pub fn on_disk(disk: TimeSeriesStore) -> HistoryStore {
+        HistoryStore {
+            pools: HashMap::new(),
+            disk: Some(disk),
+        }
+    }
+
+    /// The history of `key`, read from disk the first time it is asked for
+    fn history(&mut self, key: &PoolKey) -> &mut PoolHistory {
+        let disk = &self.disk;
+        self.pools.entry(key.clone()).or_insert_with(|| {
+            let mut history = PoolHistory::default();
+            let Some(disk) = disk else {
+                return history;
+            };
+            for resolution in [Resolution::Hour, Resolution::Day] {
+                let mut series = disk.load(key, resolution);
+                if matches!(&series, Ok(Some(series)) if series.needs_compaction()) {
+                    series = disk.compact(key, resolution);
+                }
+                match series {
+                    Ok(Some(series)) => {
+                        history.insert(series.snapshots);
+                        history.covered.insert(resolution, series.covered.clone());
+                        history.stored.insert(resolution, series.covered);
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::error!("Failed to read the history of pool {}: {:#}", key.1, e),
+                }
+            }
+            history
+        })
+    }
+
+    /// Writes the snapshots of `backfill`, which covers `span`, that are not
+    /// on disk yet. Only periods over by `now` are written, the current
+    /// one's snapshot still changes until it ends.
+    fn persist(&mut self, key: &PoolKey, backfill: &Backfill, (from, to): (i64, i64), now: i64) {
+        let Some(disk) = self.disk.clone() else {
+            return;
+        };
+        let resolution = backfill.resolution();
+        let ongoing = now - now.rem_euclid(resolution.seconds());
+        let stored = self.history(key).stored.entry(resolution).or_default();
+        let new = backfill.filter(|time| time < ongoing && !stored.contains(time));
+        let span = (from, to.min(ongoing));
+        if new.is_empty() && stored.gaps(span).is_empty() {
+            return;
+        }
+        let covered = Some(span).filter(|(from, to)| from < to);
+        match disk.append(key, &new, covered) {
+            Ok(()) => stored.add(span),
+            Err(e) => log::error!("Failed to store the history of pool {}: {:#}", key.1, e),
+        }
+    }
+
+    /// Keeps the snapshots `pool` was fetched with. They are the latest ones,
+    /// so everything from the oldest of them up to `now` is covered.
+    pub fn seed(&mut self, key: &PoolKey, pool: &Pool, now: i64) {
+        let fetched = [
+            Backfill::Hours(pool.pool_hour_data.clone()),
+            Backfill::Days(pool.pool_day_datas.clone()),
+        ];
+        for backfill in fetched {
+            let Some(from) = backfill.oldest() else {
+                continue;
+            };
+            self.persist(key, &backfill, (from, now), now);
+            let history = self.history(key);
+            history
+                .covered
+                .entry(backfill.resolution())
+                .or_default()
+                .add((from, now));
+            history.insert(backfill);
+        }
+    }
+
+    /// The parts of `span` at `resolution` that are neither stored nor being
+    /// fetched, which are marked as being fetched. Gaps shorter than one
+    /// snapshot are left out, the latest one is refreshed with the pool anyway.
+    pub fn request(
+        &mut self,
+        key: &PoolKey,
+        resolution: Resolution,
+        span: (i64, i64),
+    ) -> Vec<(i64, i64)> {
+        let history = self.history(key);
+        let mut known = history
+            .covered
+            .get(&resolution)
+            .cloned()
+            .unwrap_or_default();
+        for &pending in history
+            .pending
+            .get(&resolution)
+            .map_or(&[][..], |p| p.0.as_slice())
+        {
+            known.add(pending);
+        }
+
+        let gaps: Vec<(i64, i64)> = known
+            .gaps(span)
+            .into_iter()
+            .filter(|(from, to)| to - from >= resolution.seconds())
+            .collect();
+        let pending = history.pending.entry(resolution).or_default();
+        for gap in &gaps {
+            pending.add(*gap);
+        }
+        gaps
+    }
+
+    /// Stores what was fetched for `span` by `now`, or lets it be asked for
+    /// again if it failed
+    pub fn loaded(
+        &mut self,
+        key: &PoolKey,
+        resolution: Resolution,
+        span: (i64, i64),
+        result: Result<Backfill>,
+        now: i64,
+    ) {
+        let history = self.history(key);
+        history.pending.entry(resolution).or_default().remove(span);
+        if let Ok(backfill) = result {
+            self.persist(key, &backfill, span, now);
+            let history = self.history(key);
+            history.insert(backfill);
+            history.covered.entry(resolution).or_default().add(span);
+        }
+    }
+
+    /// Replaces the history `pool` came with by everything stored for it, oldest first
+    pub fn apply(&self, key: &PoolKey, pool: &mut Pool) {
+        let Some(history) = self.pools.get(key) else {
+            return;
+        };
+        if !history.hours.is_empty() {
+            pool.pool_hour_data = history.hours.values().cloned().collect();
+        }
+        if !history.days.is_empty() {
+            pool.pool_day_datas = history.days.values().cloned().collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coverage(spans: &[(i64, i64)]) -> Coverage {
+        let mut coverage = Coverage::default();
+        for &span in spans {
+            coverage.add(span);
+        }
+        coverage
+    }
+
+    #[test]
+    fn add_merges_overlapping_and_touching_spans() {
+        let coverage = coverage(&[(10, 20), (30, 40), (15, 25), (25, 30), (50, 60)]);
+        assert_eq!(coverage.spans(), [(10, 40), (50, 60)]);
+        assert_eq!(self::coverage(&[(0, 100), (10, 20)]).spans(), [(0, 100)]);
+    }
+
+    #[test]
+    fn add_ignores_empty_spans() {
+        assert!(coverage(&[(10, 10), (20, 10)]).spans().is_empty());
+    }
+
+    #[test]
+    fn gaps_between_and_around_spans() {
+        let coverage = coverage(&[(10, 20), (30, 40)]);
+        assert_eq!(coverage.gaps((0, 50)), [(0, 10), (20, 30), (40, 50)]);
+        assert_eq!(coverage.gaps((15, 35)), [(20, 30)]);
+        assert!(coverage.gaps((10, 20)).is_empty());
+        assert_eq!(coverage.gaps((40, 45)), [(40, 45)]);
+        assert_eq!(Coverage::default().gaps((0, 5)), [(0, 5)]);
+    }
+
+    #[test]
+    fn remove_splits_spans() {
+        let mut coverage = coverage(&[(0, 100), (200, 300)]);
+        coverage.remove((40, 60));
+        assert_eq!(coverage.spans(), [(0, 40), (60, 100), (200, 300)]);
+        coverage.remove((90, 250));
+        assert_eq!(coverage.spans(), [(0, 40), (60, 90), (250, 300)]);
+        coverage.remove((0, 300));
+        assert!(coverage.spans().is_empty());
+    }
+
+    #[test]
+    fn contains_is_half_open() {
+        let coverage = coverage(&[(10, 20)]);
+        assert!(!coverage.contains(9));
+        assert!(coverage.contains(10));
+        assert!(coverage.contains(19));
+        assert!(!coverage.contains(20));
+    }
+}
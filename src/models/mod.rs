@@ -1,3 +1,5 @@
 pub mod event_handling;
+pub mod history;
 pub mod position;
 pub mod states;
+pub mod timeseries;
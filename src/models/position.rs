@@ -1,5 +1,12 @@
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
 use juniper::GraphQLObject;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::util::uniswap::{
+    adjusted_price, amounts_for_liquidity, fee_growth_inside, fees_owed, parse_u256,
+    sqrt_price_x96_to_sqrt_price, tick_to_price, tick_to_sqrt_price,
+};
 
 #[derive(Debug, Clone, GraphQLObject, Deserialize)]
 #[graphql(description = "Information about a token")]
@@ -10,23 +17,90 @@ pub struct Token {
     pub symbol: String,
     /// The number of decimals of the token
     pub decimals: String,
+    /// The price of the token denominated in ETH
+    #[serde(rename = "derivedETH", default)]
+    pub derived_eth: Option<String>,
+}
+
+impl Token {
+    pub fn decimals(&self) -> u32 {
+        self.decimals.parse().unwrap_or(18)
+    }
+
+    /// USD price of the token given the current ETH price
+    pub fn price_usd(&self, eth_price_usd: f64) -> f64 {
+        self.derived_eth
+            .as_ref()
+            .and_then(|p| p.parse::<f64>().ok())
+            .unwrap_or(0.0)
+            * eth_price_usd
+    }
 }
 
 #[derive(Debug, Clone, GraphQLObject, Deserialize)]
 #[graphql(description = "Information about a transaction")]
 pub struct Transaction {
+    /// The transaction hash
+    #[serde(default)]
+    pub id: Option<String>,
     /// The timestamp of the transaction
     pub timestamp: String,
 }
 
 #[derive(Debug, Clone, GraphQLObject, Deserialize)]
+#[graphql(description = "An initialized tick bounding a position")]
+pub struct Tick {
+    #[serde(rename = "tickIdx")]
+    pub tick_idx: String,
+    #[serde(rename = "feeGrowthOutside0X128")]
+    pub fee_growth_outside0_x128: String,
+    #[serde(rename = "feeGrowthOutside1X128")]
+    pub fee_growth_outside1_x128: String,
+}
+
+/// An initialized tick of a pool, for rebuilding how its liquidity is spread
+#[derive(Debug, Clone, Deserialize)]
+pub struct PoolTick {
+    #[serde(rename = "tickIdx")]
+    pub tick_idx: String,
+    /// Liquidity added when the price crosses the tick upwards, removed downwards
+    #[serde(rename = "liquidityNet")]
+    pub liquidity_net: String,
+}
+
+impl PoolTick {
+    /// The tick and its net liquidity, if both parse
+    pub fn parsed(&self) -> Option<(i32, f64)> {
+        Some((
+            self.tick_idx.parse().ok()?,
+            self.liquidity_net.parse().ok()?,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, GraphQLObject, Deserialize, Serialize)]
 pub struct PoolDayData {
     pub date: f64,
     pub token0Price: String,
     pub token1Price: String,
+    #[serde(rename = "volumeUSD", default)]
+    pub volume_usd: Option<String>,
+    #[serde(rename = "feesUSD", default)]
+    pub fees_usd: Option<String>,
+    #[serde(rename = "tvlUSD", default)]
+    pub tvl_usd: Option<String>,
+    /// token0Price over the period, missing on older snapshots
+    #[serde(default)]
+    pub open: Option<String>,
+    #[serde(default)]
+    pub high: Option<String>,
+    #[serde(default)]
+    pub low: Option<String>,
+    #[serde(default)]
+    pub close: Option<String>,
 }
 
-#[derive(Debug, Clone, GraphQLObject, Deserialize)]
+#[derive(Debug, Clone, GraphQLObject, Deserialize, Serialize)]
 pub struct PoolHourData {
     #[serde(rename = "periodStartUnix")]
     pub period_start_unix: f64,
@@ -36,11 +110,48 @@ pub struct PoolHourData {
     pub token1_price: Option<String>,
     #[serde(rename = "volumeUSD")]
     pub volume_usd: String,
+    #[serde(rename = "feesUSD", default)]
+    pub fees_usd: Option<String>,
+    #[serde(rename = "tvlUSD", default)]
+    pub tvl_usd: Option<String>,
+    /// token0Price over the period, missing on older snapshots
+    #[serde(default)]
+    pub open: Option<String>,
+    #[serde(default)]
+    pub high: Option<String>,
+    #[serde(default)]
+    pub low: Option<String>,
+    #[serde(default)]
+    pub close: Option<String>,
 }
 
 #[derive(Debug, Clone, GraphQLObject, Deserialize)]
 #[graphql(description = "Information about a Uniswap pool")]
 pub struct Pool {
+    /// The pool address
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "feeTier", default)]
+    pub fee_tier: Option<String>,
+    /// Only queried when the pool is looked at on its own, positions carry their tokens
+    #[serde(default)]
+    pub token0: Option<Token>,
+    #[serde(default)]
+    pub token1: Option<Token>,
+    /// Liquidity of the positions in range of the current tick
+    #[serde(default)]
+    pub liquidity: Option<String>,
+    #[serde(rename = "totalValueLockedUSD", default)]
+    pub total_value_locked_usd: Option<String>,
+    /// The current tick, absent until the pool is initialized
+    #[serde(default)]
+    pub tick: Option<String>,
+    #[serde(rename = "sqrtPrice", default)]
+    pub sqrt_price: Option<String>,
+    #[serde(rename = "feeGrowthGlobal0X128", default)]
+    pub fee_growth_global0_x128: Option<String>,
+    #[serde(rename = "feeGrowthGlobal1X128", default)]
+    pub fee_growth_global1_x128: Option<String>,
     #[serde(rename = "token0Price")]
     pub token0_price: String,
     #[serde(rename = "token1Price")]
@@ -51,14 +162,64 @@ pub struct Pool {
     pub pool_hour_data: Vec<PoolHourData>,
 }
 
+impl Pool {
+    pub fn current_tick(&self) -> Option<i32> {
+        self.tick.as_ref().and_then(|t| t.parse().ok())
+    }
+
+    /// The fee tier in hundredths of a basis point, e.g. 3000 for 0.3%
+    pub fn fee_tier(&self) -> Option<u32> {
+        self.fee_tier.as_ref().and_then(|fee| fee.parse().ok())
+    }
+
+    /// The pair name, e.g. `WETH/USDC`, if the tokens were queried
+    pub fn pair(&self) -> Option<String> {
+        Some(format!(
+            "{}/{}",
+            self.token0.as_ref()?.symbol,
+            self.token1.as_ref()?.symbol
+        ))
+    }
+
+    /// The hourly snapshots starting at or after `since`. The pool's hours reach
+    /// as far back as its history has been loaded, not a fixed period.
+    pub fn hours_since(&self, since: f64) -> impl Iterator<Item = &PoolHourData> {
+        self.pool_hour_data
+            .iter()
+            .filter(move |d| d.period_start_unix >= since)
+    }
+
+    /// USD volume of the hours starting at or after `since`
+    pub fn volume_since(&self, since: f64) -> f64 {
+        self.hours_since(since)
+            .filter_map(|d| d.volume_usd.parse::<f64>().ok())
+            .sum()
+    }
+
+    /// Fees paid to liquidity providers on `volume_usd` of swaps
+    pub fn fees_on(&self, volume_usd: f64) -> Option<f64> {
+        Some(volume_usd * self.fee_tier()? as f64 / 1_000_000.0)
+    }
+}
+
 #[derive(Debug, Clone, GraphQLObject, Deserialize)]
 #[graphql(description = "Information about a Uniswap position")]
 pub struct Position {
+    /// The NFT token id of the position
+    #[serde(default)]
+    pub id: String,
+    /// The address holding the position NFT
+    #[serde(default)]
+    pub owner: Option<String>,
     #[serde(rename = "token0")]
     pub token0: Token,
     #[serde(rename = "token1")]
     pub token1: Token,
     pub pool: Pool,
+    #[serde(rename = "tickLower", default)]
+    pub tick_lower: Option<Tick>,
+    #[serde(rename = "tickUpper", default)]
+    pub tick_upper: Option<Tick>,
     #[serde(rename = "withdrawnToken0")]
     pub withdrawn_token0: String,
     #[serde(rename = "withdrawnToken1")]
@@ -67,7 +228,225 @@ pub struct Position {
     pub deposited_token0: String,
     #[serde(rename = "depositedToken1")]
     pub deposited_token1: String,
+    #[serde(rename = "collectedFeesToken0", default)]
+    pub collected_fees_token0: Option<String>,
+    #[serde(rename = "collectedFeesToken1", default)]
+    pub collected_fees_token1: Option<String>,
+    #[serde(rename = "feeGrowthInside0LastX128", default)]
+    pub fee_growth_inside0_last_x128: Option<String>,
+    #[serde(rename = "feeGrowthInside1LastX128", default)]
+    pub fee_growth_inside1_last_x128: Option<String>,
     pub liquidity: String,
     #[serde(default)]
     pub transaction: Option<Transaction>,
 }
+
+impl Position {
+    /// The pair name, e.g. `WETH/USDC`
+    pub fn pair(&self) -> String {
+        format!("{}/{}", self.token0.symbol, self.token1.symbol)
+    }
+
+    /// Whether the position matches a user supplied pair such as `ETH/USDC` or `USDC`.
+    /// Matching is case insensitive and ignores the order of the tokens.
+    pub fn matches_pair(&self, pair: &str) -> bool {
+        let symbols = [
+            self.token0.symbol.to_lowercase(),
+            self.token1.symbol.to_lowercase(),
+        ];
+        pair.split('/')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .all(|s| symbols.iter().any(|symbol| symbol.contains(&s)))
+    }
+
+    pub fn tick_range(&self) -> Option<(i32, i32)> {
+        let lower = self.tick_lower.as_ref()?.tick_idx.parse().ok()?;
+        let upper = self.tick_upper.as_ref()?.tick_idx.parse().ok()?;
+        Some((lower, upper))
+    }
+
+    /// Whether the pool's current tick lies inside the position's range
+    pub fn is_in_range(&self) -> Option<bool> {
+        let (lower, upper) = self.tick_range()?;
+        let tick = self.pool.current_tick()?;
+        Some(tick >= lower && tick < upper)
+    }
+
+    /// Price of `base` denominated in `quote`, where both are symbols of this position's tokens
+    pub fn price_of(&self, base: &str, quote: &str) -> Option<f64> {
+        let (symbol0, symbol1) = (
+            self.token0.symbol.to_lowercase(),
+            self.token1.symbol.to_lowercase(),
+        );
+        let (base, quote) = (base.to_lowercase(), quote.to_lowercase());
+        // The subgraph's `token1Price` is the price of token0 in token1 and vice versa
+        if symbol0 == base && symbol1 == quote {
+            self.pool.token1_price.parse().ok()
+        } else if symbol1 == base && symbol0 == quote {
+            self.pool.token0_price.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    /// Prices of token0 in token1 at the lower and upper ticks
+    pub fn price_range(&self) -> Option<(f64, f64)> {
+        let (lower, upper) = self.tick_range()?;
+        let to_price = |tick: i32| {
+            adjusted_price(
+                tick_to_price(tick),
+                self.token0.decimals(),
+                self.token1.decimals(),
+            )
+        };
+        Some((to_price(lower), to_price(upper)))
+    }
+
+    /// When the position was minted
+    pub fn opened_at(&self) -> Option<DateTime<Utc>> {
+        let timestamp = self.transaction.as_ref()?.timestamp.parse::<i64>().ok()?;
+        DateTime::from_timestamp(timestamp, 0)
+    }
+
+    /// Price of token0 in token1 when the position was opened, solved from the
+    /// tokens deposited over its range. Exact when everything was deposited at
+    /// the mint, and `None` when only one token was as the price was out of range.
+    pub fn entry_price(&self) -> Option<f64> {
+        let (lower, upper) = self.price_range()?;
+        let amount0 = self.deposited_token0.parse::<f64>().ok()?;
+        let amount1 = self.deposited_token1.parse::<f64>().ok()?;
+        if amount0 <= 0.0 || amount1 <= 0.0 {
+            return None;
+        }
+        // amount0 = L (1/√p - 1/√upper) and amount1 = L (√p - √lower), so √p is
+        // the positive root of amount0 √p² + (amount1/√upper - amount0 √lower) √p - amount1
+        let b = amount1 / upper.sqrt() - amount0 * lower.sqrt();
+        let sqrt_price = (-b + (b * b + 4.0 * amount0 * amount1).sqrt()) / (2.0 * amount0);
+        Some(sqrt_price * sqrt_price)
+    }
+
+    /// Fees already collected from the position
+    pub fn collected_fees(&self) -> (f64, f64) {
+        let parse = |s: &Option<String>| {
+            s.as_deref()
+                .and_then(|s| s.parse::<f64>().ok())
+                .unwrap_or(0.0)
+        };
+        (
+            parse(&self.collected_fees_token0),
+            parse(&self.collected_fees_token1),
+        )
+    }
+
+    /// Annualised return in percent of all fees earned, collected or not,
+    /// on the position's current value
+    pub fn fee_apr(&self, eth_price_usd: f64) -> Option<f64> {
+        let (collected0, collected1) = self.collected_fees();
+        let earned = self.uncollected_fees_usd(eth_price_usd)?
+            + collected0 * self.token0.price_usd(eth_price_usd)
+            + collected1 * self.token1.price_usd(eth_price_usd);
+        let value = self.value_usd(eth_price_usd)?;
+        let days = (Utc::now() - self.opened_at()?).num_seconds() as f64 / 86_400.0;
+        if value <= 0.0 || days <= 0.0 {
+            return None;
+        }
+        Some(earned / value * 365.0 / days * 100.0)
+    }
+
+    /// Token amounts currently held by the position, adjusted for decimals
+    pub fn token_amounts(&self) -> Option<(f64, f64)> {
+        let (lower, upper) = self.tick_range()?;
+        let sqrt_price = sqrt_price_x96_to_sqrt_price(self.pool.sqrt_price.as_ref()?)?;
+        let liquidity = self.liquidity.parse::<f64>().ok()?;
+        let (amount0, amount1) = amounts_for_liquidity(
+            liquidity,
+            sqrt_price,
+            tick_to_sqrt_price(lower),
+            tick_to_sqrt_price(upper),
+        );
+        Some((
+            amount0 / 10f64.powi(self.token0.decimals() as i32),
+            amount1 / 10f64.powi(self.token1.decimals() as i32),
+        ))
+    }
+
+    /// Fees accrued since the position was last touched, adjusted for decimals
+    pub fn uncollected_fees(&self) -> Option<(f64, f64)> {
+        let (lower, upper) = self.tick_range()?;
+        let tick = self.pool.current_tick()?;
+        let tick_lower = self.tick_lower.as_ref()?;
+        let tick_upper = self.tick_upper.as_ref()?;
+        let liquidity = parse_u256(&self.liquidity)?;
+
+        let owed = |global: &Option<String>,
+                    lower_outside: &str,
+                    upper_outside: &str,
+                    inside_last: &Option<String>|
+         -> Option<f64> {
+            let inside = fee_growth_inside(
+                tick,
+                lower,
+                upper,
+                parse_u256(global.as_ref()?)?,
+                parse_u256(lower_outside)?,
+                parse_u256(upper_outside)?,
+            );
+            Some(fees_owed(
+                liquidity,
+                inside,
+                parse_u256(inside_last.as_ref()?).unwrap_or(U256::zero()),
+            ))
+        };
+
+        let fees0 = owed(
+            &self.pool.fee_growth_global0_x128,
+            &tick_lower.fee_growth_outside0_x128,
+            &tick_upper.fee_growth_outside0_x128,
+            &self.fee_growth_inside0_last_x128,
+        )?;
+        let fees1 = owed(
+            &self.pool.fee_growth_global1_x128,
+            &tick_lower.fee_growth_outside1_x128,
+            &tick_upper.fee_growth_outside1_x128,
+            &self.fee_growth_inside1_last_x128,
+        )?;
+
+        Some((
+            fees0 / 10f64.powi(self.token0.decimals() as i32),
+            fees1 / 10f64.powi(self.token1.decimals() as i32),
+        ))
+    }
+
+    /// Current USD value of the tokens held by the position
+    pub fn value_usd(&self, eth_price_usd: f64) -> Option<f64> {
+        let (amount0, amount1) = self.token_amounts()?;
+        Some(
+            amount0 * self.token0.price_usd(eth_price_usd)
+                + amount1 * self.token1.price_usd(eth_price_usd),
+        )
+    }
+
+    /// USD value of the fees that have not been collected yet
+    pub fn uncollected_fees_usd(&self, eth_price_usd: f64) -> Option<f64> {
+        let (fees0, fees1) = self.uncollected_fees()?;
+        Some(
+            fees0 * self.token0.price_usd(eth_price_usd)
+                + fees1 * self.token1.price_usd(eth_price_usd),
+        )
+    }
+
+    /// Impermanent loss in percent: the position's value against simply holding
+    /// the net deposited tokens at today's prices
+    pub fn impermanent_loss(&self, eth_price_usd: f64) -> Option<f64> {
+        let parse = |s: &str| s.parse::<f64>().unwrap_or(0.0);
+        let held0 = parse(&self.deposited_token0) - parse(&self.withdrawn_token0);
+        let held1 = parse(&self.deposited_token1) - parse(&self.withdrawn_token1);
+        let hodl = held0 * self.token0.price_usd(eth_price_usd)
+            + held1 * self.token1.price_usd(eth_price_usd);
+        if hodl <= 0.0 {
+            return None;
+        }
+        Some((self.value_usd(eth_price_usd)? - hodl) / hodl * 100.0)
+    }
+}
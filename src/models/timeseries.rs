@@ -0,0 +1,367 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    models::{
+        history::{Backfill, Coverage, PoolKey, Resolution},
+        position::{PoolDayData, PoolHourData},
+    },
+    util::paths::data_dir,
+};
+
+/// A line of a series file. Later snapshots of the same period replace
+/// earlier ones.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record<T> {
+    Snapshot(T),
+    /// Every snapshot of `[from, to)` was written before this line
+    Covered(i64, i64),
+}
+
+/// What is stored of one pool at one resolution
+#[derive(Debug, Clone)]
+pub struct Series {
+    /// The snapshots, oldest first
+    pub snapshots: Backfill,
+    pub covered: Coverage,
+    /// Lines in the file, more than the snapshots and spans once it needs compacting
+    records: usize,
+}
+
+impl Series {
+    /// Whether the file holds replaced snapshots or overlapping spans
+    pub fn needs_compaction(&self) -> bool {
+        self.records > self.snapshots.len() + self.covered.spans().len()
+    }
+}
+
+/// Pool history kept on disk, one append-only file of JSON lines per chain,
+/// pool and resolution, so periods fetched once are there in later sessions
+#[derive(Debug, Clone)]
+pub struct TimeSeriesStore {
+    root: PathBuf,
+    /// The chain of pools keyed without one
+    chain: String,
+}
+
+impl TimeSeriesStore {
+    pub fn new(root: PathBuf, chain: &str) -> TimeSeriesStore {
+        TimeSeriesStore {
+            root,
+            chain: chain.to_owned(),
+        }
+    }
+
+    /// `$XDG_DATA_HOME/gerrehbenta/history`
+    pub fn default_path() -> PathBuf {
+        data_dir().join("history")
+    }
+
+    /// `<root>/<chain>/<pool>/<hour|day>.jsonl`
+    fn path(&self, (chain, pool): &PoolKey, resolution: Resolution) -> PathBuf {
+        // Names come from the config and the subgraph, keep them to one directory each
+        let sanitize = |name: &str| -> String {
+            name.chars()
+                .map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+                    _ => '_',
+                })
+                .collect()
+        };
+        let file = match resolution {
+            Resolution::Hour => "hour.jsonl",
+            Resolution::Day => "day.jsonl",
+        };
+        self.root
+            .join(sanitize(chain.as_deref().unwrap_or(&self.chain)))
+            .join(sanitize(&pool.to_lowercase()))
+            .join(file)
+    }
+
+    /// Appends `snapshots`, then `covered` if every snapshot of it is now stored
+    pub fn append(
+        &self,
+        key: &PoolKey,
+        snapshots: &Backfill,
+        covered: Option<(i64, i64)>,
+    ) -> Result<()> {
+        let path = self.path(key, snapshots.resolution());
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        match snapshots {
+            Backfill::Hours(hours) => write_records(&mut writer, hours, covered)?,
+            Backfill::Days(days) => write_records(&mut writer, days, covered)?,
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Everything stored of `key` at `resolution`, `None` if nothing is
+    pub fn load(&self, key: &PoolKey, resolution: Resolution) -> Result<Option<Series>> {
+        let path = self.path(key, resolution);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let series = match resolution {
+            Resolution::Hour => {
+                let (hours, covered, records) =
+                    read_records(&path, |h: &PoolHourData| h.period_start_unix as i64)?;
+                Series {
+                    snapshots: Backfill::Hours(hours.into_values().collect()),
+                    covered,
+                    records,
+                }
+            }
+            Resolution::Day => {
+                let (days, covered, records) =
+                    read_records(&path, |d: &PoolDayData| d.date as i64)?;
+                Series {
+                    snapshots: Backfill::Days(days.into_values().collect()),
+                    covered,
+                    records,
+                }
+            }
+        };
+        Ok(Some(series))
+    }
+
+    /// Rewrites the file of `key` at `resolution` with each snapshot once and
+    /// the spans merged, returning what it holds
+    pub fn compact(&self, key: &PoolKey, resolution: Resolution) -> Result<Option<Series>> {
+        let Some(series) = self.load(key, resolution)? else {
+            return Ok(None);
+        };
+        let path = self.path(key, resolution);
+        let temp = path.with_extension("jsonl.tmp");
+        let file =
+            File::create(&temp).with_context(|| format!("Failed to create {}", temp.display()))?;
+        let mut writer = BufWriter::new(file);
+        match &series.snapshots {
+            Backfill::Hours(hours) => write_records(&mut writer, hours, None)?,
+            Backfill::Days(days) => write_records(&mut writer, days, None)?,
+        }
+        for &span in series.covered.spans() {
+            write_line(&mut writer, &Record::<()>::Covered(span.0, span.1))?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write {}", temp.display()))?;
+        fs::rename(&temp, &path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        Ok(Some(Series {
+            records: series.snapshots.len() + series.covered.spans().len(),
+            ..series
+        }))
+    }
+}
+
+fn write_line<T: Serialize>(writer: &mut impl Write, record: &Record<T>) -> Result<()> {
+    serde_json::to_writer(&mut *writer, record)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+fn write_records<T: Serialize>(
+    writer: &mut impl Write,
+    snapshots: &[T],
+    covered: Option<(i64, i64)>,
+) -> Result<()> {
+    for snapshot in snapshots {
+        write_line(writer, &Record::Snapshot(snapshot))?;
+    }
+    if let Some((from, to)) = covered {
+        write_line(writer, &Record::<T>::Covered(from, to))?;
+    }
+    Ok(())
+}
+
+/// The snapshots in the file at `path` by `time`, the spans covered and the
+/// number of lines. A line cut short by a crash is skipped.
+fn read_records<T: DeserializeOwned>(
+    path: &Path,
+    time: impl Fn(&T) -> i64,
+) -> Result<(BTreeMap<i64, T>, Coverage, usize)> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut snapshots = BTreeMap::new();
+    let mut covered = Coverage::default();
+    let mut records = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+        match serde_json::from_str(&line) {
+            Ok(Record::Snapshot(snapshot)) => {
+                snapshots.insert(time(&snapshot), snapshot);
+            }
+            Ok(Record::Covered(from, to)) => covered.add((from, to)),
+            Err(e) => {
+                log::warn!("Skipping a bad line of {}: {}", path.display(), e);
+                continue;
+            }
+        }
+        records += 1;
+    }
+    Ok((snapshots, covered, records))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    /// A store under a directory of its own, removed when dropped
+    struct TempStore(TimeSeriesStore);
+
+    impl TempStore {
+        fn new(name: &str) -> TempStore {
+            let root = std::env::temp_dir().join(format!("gerrehbenta-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&root);
+            TempStore(TimeSeriesStore::new(root, "mainnet"))
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0.root);
+        }
+    }
+
+    fn key() -> PoolKey {
+        (None, "0xABC".to_owned())
+    }
+
+    fn hour(start: i64, volume: &str) -> PoolHourData {
+        PoolHourData {
+            period_start_unix: start as f64,
+            token0_price: Some("0.0005".to_owned()),
+            token1_price: Some("2000".to_owned()),
+            volume_usd: volume.to_owned(),
+            fees_usd: None,
+            tvl_usd: None,
+            open: None,
+            high: None,
+            low: None,
+            close: None,
+        }
+    }
+
+    fn hours(series: &Series) -> Vec<(i64, String)> {
+        match &series.snapshots {
+            Backfill::Hours(hours) => hours
+                .iter()
+                .map(|h| (h.period_start_unix as i64, h.volume_usd.clone()))
+                .collect(),
+            Backfill::Days(_) => panic!("expected hours"),
+        }
+    }
+
+    #[test]
+    fn paths_by_chain_pool_and_resolution() {
+        let store = TimeSeriesStore::new(PathBuf::from("/data"), "mainnet");
+        assert_eq!(
+            store.path(&key(), Resolution::Hour),
+            Path::new("/data/mainnet/0xabc/hour.jsonl")
+        );
+        let odd = (Some("../base".to_owned()), "a/b".to_owned());
+        assert_eq!(
+            store.path(&odd, Resolution::Day),
+            Path::new("/data/___base/a_b/day.jsonl")
+        );
+    }
+
+    #[test]
+    fn load_nothing_stored() {
+        let store = TempStore::new("empty");
+        assert!(store.0.load(&key(), Resolution::Hour).unwrap().is_none());
+        assert!(store.0.compact(&key(), Resolution::Hour).unwrap().is_none());
+    }
+
+    #[test]
+    fn append_and_load() {
+        let store = TempStore::new("append");
+        let first = Backfill::Hours(vec![hour(3600, "2"), hour(0, "1")]);
+        store.0.append(&key(), &first, Some((0, 7200))).unwrap();
+        let second = Backfill::Hours(vec![hour(7200, "3")]);
+        store.0.append(&key(), &second, None).unwrap();
+
+        let series = store.0.load(&key(), Resolution::Hour).unwrap().unwrap();
+        assert_eq!(
+            hours(&series),
+            [(0, "1".into()), (3600, "2".into()), (7200, "3".into())]
+        );
+        assert_eq!(series.covered.spans(), [(0, 7200)]);
+        assert!(!series.needs_compaction());
+        // Each resolution has a file of its own
+        assert!(store.0.load(&key(), Resolution::Day).unwrap().is_none());
+    }
+
+    #[test]
+    fn compact_keeps_latest_snapshots_and_merged_spans() {
+        let store = TempStore::new("compact");
+        let key = key();
+        let append = |volume: &str, span| {
+            let backfill = Backfill::Hours(vec![hour(0, volume)]);
+            store.0.append(&key, &backfill, Some(span)).unwrap();
+        };
+        append("1", (0, 3600));
+        append("2", (3600, 7200));
+
+        let series = store.0.load(&key, Resolution::Hour).unwrap().unwrap();
+        assert_eq!(hours(&series), [(0, "2".into())]);
+        assert!(series.needs_compaction());
+
+        let compacted = store.0.compact(&key, Resolution::Hour).unwrap().unwrap();
+        assert!(!compacted.needs_compaction());
+        let path = store.0.path(&key, Resolution::Hour);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        assert!(!path.with_extension("jsonl.tmp").exists());
+
+        let series = store.0.load(&key, Resolution::Hour).unwrap().unwrap();
+        assert_eq!(hours(&series), [(0, "2".into())]);
+        assert_eq!(series.covered.spans(), [(0, 7200)]);
+        assert!(!series.needs_compaction());
+    }
+
+    #[test]
+    fn skips_truncated_line() {
+        let store = TempStore::new("truncated");
+        let key = key();
+        let backfill = Backfill::Hours(vec![hour(0, "1")]);
+        store.0.append(&key, &backfill, Some((0, 3600))).unwrap();
+        // A write cut short, then more appended after it
+        let path = store.0.path(&key, Resolution::Hour);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"snapshot":{"periodStartUnix":36"#)
+            .unwrap();
+        file.write_all(b"\n").unwrap();
+        let backfill = Backfill::Hours(vec![hour(7200, "3")]);
+        store.0.append(&key, &backfill, None).unwrap();
+
+        let series = store.0.load(&key, Resolution::Hour).unwrap().unwrap();
+        assert_eq!(hours(&series), [(0, "1".into()), (7200, "3".into())]);
+        assert_eq!(series.covered.spans(), [(0, 3600)]);
+
+        store.0.compact(&key, Resolution::Hour).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 3);
+        for line in contents.lines() {
+            serde_json::from_str::<Record<PoolHourData>>(line).unwrap();
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::app::App;
-use crate::NetworkEvent;
-use anyhow::Result;
+use crate::network::metrics::{track_response, Upstream};
+use anyhow::{anyhow, Result};
 use log;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
@@ -20,6 +20,8 @@ pub struct TokenInfo {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LimitOrder {
     pub token: String,
+    #[serde(default)]
+    pub token_address: String,
     pub deadline: String,
     pub start_amount: String,
     pub end_amount: String,
@@ -27,6 +29,10 @@ pub struct LimitOrder {
     pub value_usd: String,
     pub market_cap_usd: String,
     pub volume_24h: String,
+    #[serde(default)]
+    pub maker: Option<String>,
+    #[serde(default)]
+    pub order_hash: Option<String>,
 }
 
 fn get_token_symbol(address: &str) -> String {
@@ -60,7 +66,7 @@ async fn get_coingecko_data(address: &str) -> Result<(f64, f64, f64)> {
 
     log::debug!("Making CoinGecko API request to {}", url);
 
-    let response = client.get(&url).send().await?;
+    let response = track_response(Upstream::CoinGecko, client.get(&url).send()).await?;
 
     if !response.status().is_success() {
         return Err(anyhow::anyhow!(
@@ -129,14 +135,13 @@ fn format_number(num: f64) -> String {
     }
 }
 
-pub async fn fetch_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
+pub async fn fetch_limit_orders(
+    app: Arc<Mutex<App>>,
+    endpoint: &str,
+    use_mock_data: bool,
+) -> Result<()> {
     log::debug!("Starting to fetch limit orders");
 
-    // Check if we should use mock data from environment variable
-    let use_mock_data = std::env::var("USE_MOCK_DATA")
-        .map(|v| v.to_lowercase() == "true")
-        .unwrap_or(false);
-
     if use_mock_data {
         log::info!("Using mock limit order data for testing");
         return fetch_mock_limit_orders(app).await;
@@ -144,29 +149,25 @@ pub async fn fetch_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
 
     let client = reqwest::Client::new();
 
-    // Fetch token info from the Uniswap API
-    // Using the v1 API endpoint for limit orders which is more stable
-    let token_info_url = "https://api.uniswap.org/v1/limit-orders?orderStatus=open&chainId=1&limit=100&sortKey=createdAt&desc=true";
-
-    log::debug!("Fetching limit orders from URL: {}", token_info_url);
-    let response = match client.get(token_info_url).send().await {
+    log::debug!("Fetching limit orders from URL: {}", endpoint);
+    let response = match track_response(Upstream::LimitOrders, client.get(endpoint).send()).await {
         Ok(resp) => resp,
         Err(e) => {
             log::error!("Failed to fetch limit orders: {:?}", e);
-            return Ok(());
+            return Err(e.into());
         }
     };
 
     if !response.status().is_success() {
         log::error!("API returned error status: {}", response.status());
-        return Ok(());
+        return Err(anyhow!("API returned error status {}", response.status()));
     }
 
     let orders_text = match response.text().await {
         Ok(text) => text,
         Err(e) => {
             log::error!("Failed to get response text: {:?}", e);
-            return Ok(());
+            return Err(e.into());
         }
     };
 
@@ -180,7 +181,7 @@ pub async fn fetch_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
                 "First 100 chars of response: {}",
                 &orders_text[..orders_text.len().min(100)]
             );
-            return Ok(());
+            return Err(anyhow!("Invalid response from the limit orders API"));
         }
     };
 
@@ -281,6 +282,7 @@ pub async fn fetch_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
             );
             limit_orders.push(LimitOrder {
                 token: token_symbol,
+                token_address: token_address.to_string(),
                 deadline,
                 start_amount,
                 end_amount,
@@ -288,6 +290,14 @@ pub async fn fetch_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
                 value_usd: format_number(value_usd),
                 market_cap_usd: format_number(market_cap),
                 volume_24h: format_number(volume),
+                maker: order
+                    .get("maker")
+                    .and_then(|m| m.as_str())
+                    .map(str::to_string),
+                order_hash: order
+                    .get("orderHash")
+                    .and_then(|h| h.as_str())
+                    .map(str::to_string),
             });
         }
     }
@@ -313,6 +323,7 @@ async fn fetch_mock_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
     let mock_orders = vec![
         LimitOrder {
             token: "WETH".to_string(),
+            token_address: "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2".to_string(),
             deadline: chrono::Utc::now().to_rfc3339(),
             start_amount: "1.5".to_string(),
             end_amount: "1.45".to_string(),
@@ -320,9 +331,12 @@ async fn fetch_mock_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
             value_usd: "4,800.00".to_string(),
             market_cap_usd: "300.12B".to_string(),
             volume_24h: "12.5B".to_string(),
+            maker: None,
+            order_hash: None,
         },
         LimitOrder {
             token: "USDC".to_string(),
+            token_address: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".to_string(),
             deadline: chrono::Utc::now().to_rfc3339(),
             start_amount: "5000".to_string(),
             end_amount: "4990".to_string(),
@@ -330,9 +344,12 @@ async fn fetch_mock_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
             value_usd: "5,000.00".to_string(),
             market_cap_usd: "42.5B".to_string(),
             volume_24h: "6.8B".to_string(),
+            maker: None,
+            order_hash: None,
         },
         LimitOrder {
             token: "SHIB".to_string(),
+            token_address: "0x95ad61b0a150d79219dcf64e1e6cc01f0b64c4ce".to_string(),
             deadline: chrono::Utc::now().to_rfc3339(),
             start_amount: "10000000".to_string(),
             end_amount: "9950000".to_string(),
@@ -340,9 +357,12 @@ async fn fetch_mock_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
             value_usd: "250.00".to_string(),
             market_cap_usd: "6.2B".to_string(),
             volume_24h: "180.5M".to_string(),
+            maker: None,
+            order_hash: None,
         },
         LimitOrder {
             token: "UNI".to_string(),
+            token_address: "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984".to_string(),
             deadline: chrono::Utc::now().to_rfc3339(),
             start_amount: "250".to_string(),
             end_amount: "248".to_string(),
@@ -350,9 +370,12 @@ async fn fetch_mock_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
             value_usd: "2,187.50".to_string(),
             market_cap_usd: "4.8B".to_string(),
             volume_24h: "145.2M".to_string(),
+            maker: None,
+            order_hash: None,
         },
         LimitOrder {
             token: "USDT".to_string(),
+            token_address: "0xdac17f958d2ee523a2206206994597c13d831ec7".to_string(),
             deadline: chrono::Utc::now().to_rfc3339(),
             start_amount: "3500".to_string(),
             end_amount: "3485".to_string(),
@@ -360,6 +383,8 @@ async fn fetch_mock_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
             value_usd: "3,500.00".to_string(),
             market_cap_usd: "95.7B".to_string(),
             volume_24h: "42.3B".to_string(),
+            maker: None,
+            order_hash: None,
         },
     ];
 
@@ -371,16 +396,5 @@ async fn fetch_mock_limit_orders(app: Arc<Mutex<App>>) -> Result<()> {
     let mut app = app.lock();
     app.update_limit_orders(mock_orders);
 
-    // Schedule next update for mock data
-    let network_txn = app.network_txn.clone();
-    drop(app);
-
-    if let Some(tx) = network_txn {
-        tokio::spawn(async move {
-            tokio::time::sleep(tokio::time::Duration::from_secs(15)).await;
-            let _ = tx.send(NetworkEvent::FetchLimitOrders);
-        });
-    }
-
     Ok(())
 }
@@ -0,0 +1,190 @@
+use std::{future::Future, time::Instant};
+
+use lazy_static::lazy_static;
+use prometheus::{
+    register_gauge_vec_with_registry, register_histogram_vec_with_registry,
+    register_int_counter_vec_with_registry, Encoder, GaugeVec, HistogramVec, IntCounterVec,
+    Registry, TextEncoder,
+};
+
+use crate::models::position::Position;
+
+lazy_static! {
+    static ref REGISTRY: Registry =
+        Registry::new_custom(Some("gerrehbenta".to_owned()), None).expect("metrics registry");
+    static ref POSITION_VALUE_USD: GaugeVec = register_gauge_vec_with_registry!(
+        "position_value_usd",
+        "Current USD value of a position",
+        &["wallet", "position", "pair"],
+        REGISTRY
+    )
+    .unwrap();
+    static ref POSITION_UNCOLLECTED_FEES_USD: GaugeVec = register_gauge_vec_with_registry!(
+        "position_uncollected_fees_usd",
+        "USD value of a position's uncollected fees",
+        &["wallet", "position", "pair"],
+        REGISTRY
+    )
+    .unwrap();
+    static ref POSITION_IN_RANGE: GaugeVec = register_gauge_vec_with_registry!(
+        "position_in_range",
+        "1 if the pool price is inside the position's range, 0 otherwise",
+        &["wallet", "position", "pair"],
+        REGISTRY
+    )
+    .unwrap();
+    static ref POSITION_IMPERMANENT_LOSS_PERCENT: GaugeVec = register_gauge_vec_with_registry!(
+        "position_impermanent_loss_percent",
+        "Position value against holding the deposited tokens, in percent",
+        &["wallet", "position", "pair"],
+        REGISTRY
+    )
+    .unwrap();
+    static ref POOL_PRICE: GaugeVec = register_gauge_vec_with_registry!(
+        "pool_price",
+        "Price of token0 denominated in token1",
+        &["pool", "pair"],
+        REGISTRY
+    )
+    .unwrap();
+    static ref POOL_VOLUME_24H_USD: GaugeVec = register_gauge_vec_with_registry!(
+        "pool_volume_24h_usd",
+        "Pool volume over the last 24 hours in USD",
+        &["pool", "pair"],
+        REGISTRY
+    )
+    .unwrap();
+    static ref UPSTREAM_REQUESTS: IntCounterVec = register_int_counter_vec_with_registry!(
+        "upstream_requests_total",
+        "Requests made to upstream services",
+        &["upstream", "outcome"],
+        REGISTRY
+    )
+    .unwrap();
+    static ref UPSTREAM_REQUEST_DURATION: HistogramVec = register_histogram_vec_with_registry!(
+        "upstream_request_duration_seconds",
+        "Latency of requests made to upstream services",
+        &["upstream"],
+        REGISTRY
+    )
+    .unwrap();
+}
+
+/// Upstream services the network layer talks to
+#[derive(Debug, Clone, Copy)]
+pub enum Upstream {
+    Subgraph,
+    Rpc,
+    LimitOrders,
+    CoinGecko,
+    Webhook,
+}
+
+impl Upstream {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Upstream::Subgraph => "subgraph",
+            Upstream::Rpc => "rpc",
+            Upstream::LimitOrders => "limit_orders",
+            Upstream::CoinGecko => "coingecko",
+            Upstream::Webhook => "webhook",
+        }
+    }
+}
+
+/// Awaits `request`, recording its latency and outcome against `upstream`
+pub async fn track<T, E>(
+    upstream: Upstream,
+    request: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let start = Instant::now();
+    let result = request.await;
+    observe(upstream, start, result.is_ok());
+    result
+}
+
+/// Like [`track`], but only counts HTTP responses with a 2xx status as successful
+pub async fn track_response(
+    upstream: Upstream,
+    request: impl Future<Output = reqwest::Result<reqwest::Response>>,
+) -> reqwest::Result<reqwest::Response> {
+    let start = Instant::now();
+    let result = request.await;
+    observe(
+        upstream,
+        start,
+        result
+            .as_ref()
+            .is_ok_and(|response| response.status().is_success()),
+    );
+    result
+}
+
+fn observe(upstream: Upstream, start: Instant, success: bool) {
+    UPSTREAM_REQUEST_DURATION
+        .with_label_values(&[upstream.as_str()])
+        .observe(start.elapsed().as_secs_f64());
+    UPSTREAM_REQUESTS
+        .with_label_values(&[
+            upstream.as_str(),
+            if success { "success" } else { "failure" },
+        ])
+        .inc();
+}
+
+/// Replaces the position and pool gauges with the latest data for `wallet`
+pub fn record_positions(wallet: &str, positions: &[Position], eth_price_usd: f64) {
+    for gauge in [
+        &*POSITION_VALUE_USD,
+        &*POSITION_UNCOLLECTED_FEES_USD,
+        &*POSITION_IN_RANGE,
+        &*POSITION_IMPERMANENT_LOSS_PERCENT,
+        &*POOL_PRICE,
+        &*POOL_VOLUME_24H_USD,
+    ] {
+        gauge.reset();
+    }
+
+    for position in positions {
+        let pair = position.pair();
+        let labels = [wallet, position.id.as_str(), pair.as_str()];
+
+        if let Some(value) = position.value_usd(eth_price_usd) {
+            POSITION_VALUE_USD.with_label_values(&labels).set(value);
+        }
+        if let Some(fees) = position.uncollected_fees_usd(eth_price_usd) {
+            POSITION_UNCOLLECTED_FEES_USD
+                .with_label_values(&labels)
+                .set(fees);
+        }
+        if let Some(in_range) = position.is_in_range() {
+            POSITION_IN_RANGE
+                .with_label_values(&labels)
+                .set(if in_range { 1.0 } else { 0.0 });
+        }
+        if let Some(il) = position.impermanent_loss(eth_price_usd) {
+            POSITION_IMPERMANENT_LOSS_PERCENT
+                .with_label_values(&labels)
+                .set(il);
+        }
+
+        let pool_labels = [position.pool.id.as_str(), pair.as_str()];
+        if let Ok(price) = position.pool.token1_price.parse::<f64>() {
+            POOL_PRICE.with_label_values(&pool_labels).set(price);
+        }
+        let day_ago = chrono::Utc::now().timestamp() as f64 - 60.0 * 60.0 * 24.0;
+        let volume = position.pool.volume_since(day_ago);
+        POOL_VOLUME_24H_USD
+            .with_label_values(&pool_labels)
+            .set(volume);
+    }
+}
+
+/// Renders every metric in the Prometheus text exposition format
+pub fn encode() -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        log::error!("Failed to encode metrics: {}", e);
+    }
+    buffer
+}
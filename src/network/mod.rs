@@ -1,4 +1,6 @@
 pub mod ethers;
 pub mod limit_orders;
+pub mod metrics;
 pub mod network;
+pub mod pools;
 pub mod server;
@@ -3,13 +3,22 @@ use std::sync::{mpsc::Receiver, Arc};
 
 use super::ethers::types::AddressInfo;
 use super::limit_orders::{fetch_limit_orders, LimitOrder};
-use crate::app::Mode;
+use super::metrics::{track, Upstream};
+use super::pools::{
+    fetch_history, fetch_pool, fetch_ticks, fetch_ticks_onchain, fetch_top_pools, PoolQuery,
+};
 use crate::{
+    alerts::{engine::Alert, sinks::deliver},
     app::App,
-    models::position::Position,
+    config::{Config, Endpoints},
+    models::{
+        history::{PoolKey, Resolution},
+        position::Position,
+    },
     network::server::fetch_positions,
     routes::{ActiveBlock, Route, RouteId},
     widgets::chart::TokenChart,
+    DATA_RECEIVED,
 };
 use ethers::{
     core::types::{Address, NameOrAddress},
@@ -32,30 +41,140 @@ pub enum NetworkEvent {
     },
     GetAddressPositionInfo {
         address: Address,
-        positions: Option<Vec<Position>>,
     },
     FetchLimitOrders,
+    FetchPool {
+        query: PoolQuery,
+        chain: Option<String>,
+    },
+    FetchTopPools {
+        chain: String,
+        fee_tier: Option<u32>,
+    },
+    FetchTicks {
+        pool: String,
+        chain: Option<String>,
+        ticks: (i32, i32),
+    },
+    BackfillHistory {
+        pool: PoolKey,
+        resolution: Resolution,
+        span: (i64, i64),
+    },
+    DeliverAlert {
+        alert: Alert,
+    },
 }
 
 pub struct Network {
-    uniswap_v3_endpoint: String,
-    etherscan_endpoint: String,
-    uniswap_limits_endpoint: String,
+    config: Config,
+    endpoints: Endpoints,
     app: Arc<Mutex<App>>,
 }
 
 impl Network {
-    pub fn default(
-        app: Arc<Mutex<App>>,
-        etherscan_endpoint: String,
-        uniswap_v3_endpoint: String,
-        uniswap_limits_endpoint: String,
-    ) -> Self {
-        Self {
-            etherscan_endpoint,
-            uniswap_v3_endpoint,
-            uniswap_limits_endpoint,
+    pub fn default(app: Arc<Mutex<App>>, config: Config) -> Result<Self> {
+        Ok(Self {
+            endpoints: config.endpoints()?,
+            config,
             app,
+        })
+    }
+
+    /// Fetches the positions of `owner` and hands them to the app
+    async fn load_positions(&self, owner: &str) -> Result<()> {
+        log::debug!("Fetching positions for address: {}", owner);
+        let (mut positions, eth_price_usd) = fetch_positions(
+            &self.endpoints.subgraph,
+            self.config.credentials.subgraph_api_key.as_deref(),
+            owner,
+        )
+        .await?;
+        log::debug!("Successfully fetched {} positions", positions.len());
+
+        let mut app = self.app.lock();
+        for position in &mut positions {
+            let key = (None, position.pool.id.clone());
+            app.merge_history(&key, &mut position.pool);
+        }
+        app.stateful_table
+            .update_positions(&positions, eth_price_usd);
+        app.positions = positions;
+        app.eth_price_usd = eth_price_usd;
+        app.backfill_chart();
+        let _ = DATA_RECEIVED.0.try_send(());
+
+        Ok(())
+    }
+
+    /// Fetches the ticks of `pool` over `span` for whichever of the pool
+    /// explorer and the fee estimator asked for them. Pools of the configured
+    /// chain are read from the contract when the subgraph can't be.
+    async fn load_ticks(&self, pool: &str, chain: Option<&str>, span: (i32, i32)) {
+        let result = match self.subgraph(chain) {
+            Ok(subgraph) => {
+                fetch_ticks(
+                    &subgraph,
+                    self.config.credentials.subgraph_api_key.as_deref(),
+                    pool,
+                    span,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        let result = match result {
+            Err(e) if chain.is_none_or(|chain| chain == self.config.chain) => {
+                log::warn!(
+                    "Failed to fetch the ticks of pool {} from the subgraph, reading the contract: {:#}",
+                    pool,
+                    e
+                );
+                fetch_ticks_onchain(&self.endpoints.rpc, pool, span).await
+            }
+            result => result,
+        };
+        if let Err(e) = &result {
+            log::error!("Failed to fetch the ticks of pool {}: {:#}", pool, e);
+        }
+
+        let mut app = self.app.lock();
+        let copy = match &result {
+            Ok(ticks) => Ok(ticks.clone()),
+            Err(e) => Err(anyhow::anyhow!("{:#}", e)),
+        };
+        app.pool_explorer.depth.loaded(pool, span, copy);
+        app.range_estimator.depth.loaded(pool, span, result);
+        let _ = DATA_RECEIVED.0.try_send(());
+    }
+
+    /// The subgraph of `chain`, or of the configured chain
+    fn subgraph(&self, chain: Option<&str>) -> Result<String> {
+        match chain {
+            Some(chain) if *chain != self.config.chain => self
+                .config
+                .subgraphs()
+                .remove(chain)
+                .ok_or_else(|| anyhow::anyhow!("endpoints.{}.subgraph is not set", chain)),
+            _ => Ok(self.endpoints.subgraph.clone()),
+        }
+    }
+
+    fn schedule_positions_refresh(&self, address: Address) {
+        self.schedule(
+            NetworkEvent::GetAddressPositionInfo { address },
+            self.config.refresh.positions_secs,
+        );
+    }
+
+    /// Sends `event` back to the network thread after `secs` seconds
+    fn schedule(&self, event: NetworkEvent, secs: u64) {
+        let network_txn = self.app.lock().network_txn.clone();
+        if let Some(tx) = network_txn {
+            tokio::spawn(async move {
+                tokio::time::sleep(tokio::time::Duration::from_secs(secs)).await;
+                let _ = tx.send(event);
+            });
         }
     }
 
@@ -65,10 +184,10 @@ impl Network {
                 log::debug!("Handling GetENSAddressInfo event");
                 let res = match name_or_address {
                     NameOrAddress::Name(name) => {
-                        Self::get_name_info(&self.etherscan_endpoint, &name).await
+                        Self::get_name_info(&self.endpoints.rpc, &name).await
                     }
                     NameOrAddress::Address(address) => {
-                        Self::get_address_info(&self.etherscan_endpoint, address).await
+                        Self::get_address_info(&self.endpoints.rpc, address).await
                     }
                 };
                 // Handle the result of the name or address lookup
@@ -94,39 +213,24 @@ impl Network {
 
                 // Update app state with the resolved address
                 log::debug!("Found address info: {:?}", address_info);
-                let mut app = self.app.lock();
-
-                // Set up the UI to display the address information
-                app.search_state.is_searching = false;
-                app.wallet_address = Some(address_info.address.to_string());
-
-                // Fetch positions for the wallet address
                 let full_address = format!("{:?}", address_info.address);
-                log::debug!("Fetching positions for address: {}", full_address);
-                match fetch_positions(&full_address).await {
-                    Ok((positions, volume_data)) => {
-                        log::debug!("Successfully fetched {} positions", positions.len());
-                        let positions_clone = positions.clone();
-                        // Parse tokenDayDatas for each position
-                        let token_day_datas: Vec<Vec<(f64, f64)>> = positions_clone
-                            .iter()
-                            .map(|pos| {
-                                pos.pool
-                                    .pool_day_datas
-                                    .iter()
-                                    .map(|d| {
-                                        let date = d.date;
-                                        let volume = d.token0Price.parse::<f64>().unwrap_or(0.0); // fallback: use token0Price as volume if no volume field
-                                        (date, volume)
-                                    })
-                                    .collect()
-                            })
-                            .collect();
-                        app.positions = positions;
-                        app.stateful_table
-                            .update_positions(&positions_clone, &token_day_datas);
-                        app.mode = Mode::MyPositions;
-                        app.change_active_block(ActiveBlock::MyPositions);
+                {
+                    let mut app = self.app.lock();
+                    app.search_state.is_searching = false;
+                    app.wallet_address = Some(full_address.clone());
+                }
+
+                match self.load_positions(&full_address).await {
+                    Ok(()) => {
+                        let mut app = self.app.lock();
+                        app.search_state.ens_state.is_searching = false;
+                        app.set_route(Route::new(
+                            RouteId::MyPositions(Some(address_info.clone())),
+                            ActiveBlock::MyPositions,
+                        ));
+                        app.backfill_chart();
+                        drop(app);
+                        self.schedule_positions_refresh(address_info.address);
                         Ok(())
                     }
                     Err(e) => {
@@ -136,6 +240,7 @@ impl Network {
                             Err(e)
                         } else {
                             // For other errors, show them in the UI
+                            let mut app = self.app.lock();
                             app.search_state.ens_state.set_error(e.to_string());
                             app.search_state.is_searching = false;
                             Ok(())
@@ -143,35 +248,142 @@ impl Network {
                     }
                 }
             }
-            NetworkEvent::GetAddressPositionInfo { address, positions } => {
-                //#TODO Load positions
+            NetworkEvent::GetAddressPositionInfo { address } => {
+                let full_address = format!("{:?}", address);
+                // Stop refreshing once another wallet has been searched for
+                if self.app.lock().wallet_address.as_deref() != Some(full_address.as_str()) {
+                    return Ok(());
+                }
+
+                if let Err(e) = self.load_positions(&full_address).await {
+                    log::error!("Failed to refresh positions: {}", e);
+                }
+                self.schedule_positions_refresh(address);
                 Ok(())
             }
             NetworkEvent::FetchLimitOrders => {
                 let app = self.app.clone();
-                fetch_limit_orders(app).await?;
+                let result =
+                    fetch_limit_orders(app, &self.endpoints.limit_orders, self.config.mock_data)
+                        .await;
+                let secs = self.config.refresh.limit_orders_secs;
+                self.app
+                    .lock()
+                    .limit_orders_table
+                    .refreshed(result.err().map(|e| format!("{:#}", e)), secs);
+                let _ = DATA_RECEIVED.0.try_send(());
 
                 // Schedule next update
-                let network_txn = self.app.lock().network_txn.clone();
-                if let Some(tx) = network_txn {
-                    tokio::spawn(async move {
-                        tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-                        let _ = tx.send(NetworkEvent::FetchLimitOrders);
-                    });
-                }
+                self.schedule(NetworkEvent::FetchLimitOrders, secs);
 
                 Ok(())
             }
+            NetworkEvent::FetchPool { query, chain } => {
+                let result = match self.subgraph(chain.as_deref()) {
+                    Ok(subgraph) => {
+                        fetch_pool(
+                            &subgraph,
+                            self.config.credentials.subgraph_api_key.as_deref(),
+                            &query,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = &result {
+                    log::error!("Failed to fetch pool {:?}: {:#}", query, e);
+                }
+                let mut result = result;
+                let depth = {
+                    let mut app = self.app.lock();
+                    if let Ok(page) = &mut result {
+                        let key = (chain.clone(), page.pool.id.clone());
+                        app.merge_history(&key, &mut page.pool);
+                    }
+                    let depth = app.pool_explorer.loaded(&query, result);
+                    app.backfill_chart();
+                    depth
+                };
+                let _ = DATA_RECEIVED.0.try_send(());
+                if let Some((pool, ticks)) = depth {
+                    self.load_ticks(&pool, chain.as_deref(), ticks).await;
+                }
+                Ok(())
+            }
+            NetworkEvent::BackfillHistory {
+                pool,
+                resolution,
+                span,
+            } => {
+                let result = match self.subgraph(pool.0.as_deref()) {
+                    Ok(subgraph) => {
+                        fetch_history(
+                            &subgraph,
+                            self.config.credentials.subgraph_api_key.as_deref(),
+                            &pool.1,
+                            resolution,
+                            span,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = &result {
+                    log::error!("Failed to backfill the history of pool {}: {:#}", pool.1, e);
+                }
+                let mut app = self.app.lock();
+                let now = chrono::Utc::now().timestamp();
+                app.history.loaded(&pool, resolution, span, result, now);
+                app.apply_history();
+                let _ = DATA_RECEIVED.0.try_send(());
+                Ok(())
+            }
+            NetworkEvent::FetchTicks { pool, chain, ticks } => {
+                self.load_ticks(&pool, chain.as_deref(), ticks).await;
+                Ok(())
+            }
+            NetworkEvent::FetchTopPools { chain, fee_tier } => {
+                let result = match self.subgraph(Some(&chain)) {
+                    Ok(subgraph) => {
+                        fetch_top_pools(
+                            &subgraph,
+                            self.config.credentials.subgraph_api_key.as_deref(),
+                            fee_tier,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = &result {
+                    log::error!("Failed to fetch top pools on {}: {:#}", chain, e);
+                }
+                self.app.lock().top_pools.loaded((chain, fee_tier), result);
+                let _ = DATA_RECEIVED.0.try_send(());
+                Ok(())
+            }
+            NetworkEvent::DeliverAlert { alert } => {
+                // Deliver in the background so slow sinks and retries don't hold up other events
+                tokio::spawn(async move {
+                    for sink in &alert.sinks {
+                        if let Err(e) = deliver(sink, &alert).await {
+                            log::error!("Failed to deliver alert '{}': {}", alert.rule, e);
+                        }
+                    }
+                });
+                Ok(())
+            }
         }
     }
 
     async fn get_name_info(endpoint: &str, ens_id: &str) -> Result<Option<AddressInfo>> {
         let provider = Provider::<Http>::try_from(endpoint)?;
-        let address = provider.resolve_name(&ens_id).await?;
+        let address = track(Upstream::Rpc, provider.resolve_name(&ens_id)).await?;
 
-        let balance = provider
-            .get_balance(address, None /* //#TODO handle error */)
-            .await?;
+        let balance = track(
+            Upstream::Rpc,
+            provider.get_balance(address, None /* //#TODO handle error */),
+        )
+        .await?;
         //#TODO: Have an error type for this
         Ok(Some(AddressInfo {
             address,
@@ -184,7 +396,7 @@ impl Network {
         let provider = Provider::<Http>::try_from(endpoint)?;
         let ens_id = provider.lookup_address(address).await.ok();
 
-        let balance = provider.get_balance(address, None).await?;
+        let balance = track(Upstream::Rpc, provider.get_balance(address, None)).await?;
 
         Ok(Some(AddressInfo {
             address,
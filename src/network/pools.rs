@@ -0,0 +1,464 @@
+use std::str::FromStr;
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use ethers::{
+    contract::abigen,
+    providers::{Http, Provider},
+    types::Address,
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde_json::Value;
+
+use crate::{
+    models::{
+        history::{Backfill, Resolution},
+        position::{Pool, PoolTick, Position},
+    },
+    network::{
+        metrics::{track, Upstream},
+        server::query_subgraph,
+    },
+};
+
+abigen!(
+    UniswapV3Pool,
+    r#"[
+        function tickSpacing() external view returns (int24)
+        function tickBitmap(int16 wordPosition) external view returns (uint256)
+        function ticks(int24 tick) external view returns (uint128 liquidityGross, int128 liquidityNet, uint256 feeGrowthOutside0X128, uint256 feeGrowthOutside1X128, int56 tickCumulativeOutside, uint160 secondsPerLiquidityOutsideX128, uint32 secondsOutside, bool initialized)
+    ]"#
+);
+
+/// Top liquidity providers listed for a pool
+const TOP_POSITIONS: usize = 10;
+
+/// What to look a pool up by, parsed from input such as
+/// `0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640` or `WETH/USDC 0.05%`
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolQuery {
+    Address(String),
+    /// The most liquid pool of a pair, in either token order,
+    /// with the given fee tier in hundredths of a basis point
+    Pair {
+        token0: String,
+        token1: String,
+        fee_tier: Option<u32>,
+    },
+}
+
+impl FromStr for PoolQuery {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(hex) = s.strip_prefix("0x") {
+            if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(anyhow!("'{}' is not a pool address", s));
+            }
+            return Ok(PoolQuery::Address(s.to_lowercase()));
+        }
+
+        let parts: Vec<&str> = s
+            .split(|c: char| c == '/' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect();
+        match parts.as_slice() {
+            [token0, token1] => Ok(PoolQuery::Pair {
+                token0: token0.to_string(),
+                token1: token1.to_string(),
+                fee_tier: None,
+            }),
+            [token0, token1, fee] => Ok(PoolQuery::Pair {
+                token0: token0.to_string(),
+                token1: token1.to_string(),
+                fee_tier: Some(parse_fee_tier(fee)?),
+            }),
+            _ => Err(anyhow!(
+                "expected a pool address or a pair such as 'WETH/USDC 0.3%'"
+            )),
+        }
+    }
+}
+
+/// Reads `0.3%`, `0.3` or `3000` as the fee tier 3000
+fn parse_fee_tier(fee: &str) -> Result<u32> {
+    let percent = fee.ends_with('%');
+    let value = fee
+        .trim_end_matches('%')
+        .parse::<f64>()
+        .map_err(|_| anyhow!("'{}' is not a fee tier", fee))?;
+    // Fee tiers are at most 1%, so small numbers are percentages
+    if percent || value <= 1.0 {
+        Ok((value * 10_000.0).round() as u32)
+    } else {
+        Ok(value as u32)
+    }
+}
+
+/// A pool with its history and largest positions
+#[derive(Debug, Clone)]
+pub struct PoolPage {
+    pub pool: Pool,
+    /// Ordered by liquidity, largest first
+    pub positions: Vec<Position>,
+    pub eth_price_usd: f64,
+}
+
+pub async fn fetch_pool(
+    subgraph_endpoint: &str,
+    api_key: Option<&str>,
+    query: &PoolQuery,
+) -> Result<PoolPage> {
+    let id = match query {
+        PoolQuery::Address(address) => address.clone(),
+        PoolQuery::Pair {
+            token0,
+            token1,
+            fee_tier,
+        } => find_pool(subgraph_endpoint, api_key, token0, token1, *fee_tier).await?,
+    };
+
+    let query = format!(
+        r#"{{
+            pool(id: "{id}") {{
+                id
+                feeTier
+                tick
+                sqrtPrice
+                liquidity
+                feeGrowthGlobal0X128
+                feeGrowthGlobal1X128
+                token0Price
+                token1Price
+                totalValueLockedUSD
+                token0 {{ symbol name decimals derivedETH }}
+                token1 {{ symbol name decimals derivedETH }}
+                poolHourData(first: 168, orderBy: periodStartUnix, orderDirection: desc) {{
+                    periodStartUnix
+                    token0Price
+                    token1Price
+                    volumeUSD
+                    feesUSD
+                    tvlUSD
+                    open
+                    high
+                    low
+                    close
+                }}
+                poolDayData(first: 365, orderBy: date, orderDirection: desc) {{
+                    date
+                    token0Price
+                    token1Price
+                    volumeUSD
+                    feesUSD
+                    tvlUSD
+                    open
+                    high
+                    low
+                    close
+                }}
+            }}
+            positions(first: {TOP_POSITIONS}, orderBy: liquidity, orderDirection: desc, where: {{pool: "{id}", liquidity_gt: 0}}) {{
+                id
+                owner
+                token0 {{ symbol name decimals derivedETH }}
+                token1 {{ symbol name decimals derivedETH }}
+                pool {{
+                    id
+                    feeTier
+                    tick
+                    sqrtPrice
+                    feeGrowthGlobal0X128
+                    feeGrowthGlobal1X128
+                    token0Price
+                    token1Price
+                }}
+                tickLower {{tickIdx feeGrowthOutside0X128 feeGrowthOutside1X128}}
+                tickUpper {{tickIdx feeGrowthOutside0X128 feeGrowthOutside1X128}}
+                withdrawnToken0
+                withdrawnToken1
+                depositedToken0
+                depositedToken1
+                collectedFeesToken0
+                collectedFeesToken1
+                feeGrowthInside0LastX128
+                feeGrowthInside1LastX128
+                liquidity
+                transaction {{
+                    id
+                    timestamp
+                }}
+            }}
+            bundle(id: "1") {{
+                ethPriceUSD
+            }}
+        }}"#
+    );
+
+    let data = checked(query_subgraph(subgraph_endpoint, api_key, &query).await?)?;
+    if data["data"]["pool"].is_null() {
+        return Err(anyhow!("there is no pool at {}", id));
+    }
+
+    let mut pool: Pool = serde_json::from_value(data["data"]["pool"].clone())?;
+    // The charts expect oldest first
+    pool.pool_hour_data
+        .sort_by(|a, b| a.period_start_unix.total_cmp(&b.period_start_unix));
+    pool.pool_day_datas
+        .sort_by(|a, b| a.date.total_cmp(&b.date));
+
+    let positions = serde_json::from_value(data["data"]["positions"].clone())?;
+    let eth_price_usd = data["data"]["bundle"]["ethPriceUSD"]
+        .as_str()
+        .and_then(|p| p.parse::<f64>().ok())
+        .unwrap_or(0.0);
+
+    Ok(PoolPage {
+        pool,
+        positions,
+        eth_price_usd,
+    })
+}
+
+/// The address of the pool of `symbol0` and `symbol1` with the most value locked
+async fn find_pool(
+    subgraph_endpoint: &str,
+    api_key: Option<&str>,
+    symbol0: &str,
+    symbol1: &str,
+    fee_tier: Option<u32>,
+) -> Result<String> {
+    // There is no case insensitive equality filter, matching both ends of the symbol comes closest
+    let token = |symbol: &str| {
+        format!(
+            r#"{{symbol_starts_with_nocase: "{0}", symbol_ends_with_nocase: "{0}"}}"#,
+            symbol.replace('"', "")
+        )
+    };
+    let fee_filter = fee_tier.map_or(String::new(), |fee| format!(", feeTier: {}", fee));
+    let pools = |a: &str, b: &str| {
+        format!(
+            r#"pools(first: 1, orderBy: totalValueLockedUSD, orderDirection: desc, where: {{token0_: {}, token1_: {}{}}}) {{ id totalValueLockedUSD }}"#,
+            token(a),
+            token(b),
+            fee_filter
+        )
+    };
+    let query = format!(
+        "{{ forward: {} reverse: {} }}",
+        pools(symbol0, symbol1),
+        pools(symbol1, symbol0)
+    );
+
+    let data = checked(query_subgraph(subgraph_endpoint, api_key, &query).await?)?;
+    let tvl = |pool: &Value| {
+        pool["totalValueLockedUSD"]
+            .as_str()
+            .and_then(|tvl| tvl.parse::<f64>().ok())
+            .unwrap_or(0.0)
+    };
+    ["forward", "reverse"]
+        .iter()
+        .filter_map(|key| data["data"][key].as_array()?.first())
+        .max_by(|a, b| tvl(a).total_cmp(&tvl(b)))
+        .and_then(|pool| pool["id"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            let fee = fee_tier.map_or(String::new(), |fee| {
+                format!(" with a {}% fee", fee as f64 / 10_000.0)
+            });
+            anyhow!("there is no {}/{} pool{}", symbol0, symbol1, fee)
+        })
+}
+
+/// Pools fetched for the leaderboard, the largest by TVL. The subgraph can't order
+/// pools by their 24h figures, so other columns only sort these.
+pub const TOP_POOLS: usize = 100;
+
+/// The pools with the most value locked, optionally of a single fee tier,
+/// with their last day of hourly volume
+pub async fn fetch_top_pools(
+    subgraph_endpoint: &str,
+    api_key: Option<&str>,
+    fee_tier: Option<u32>,
+) -> Result<Vec<Pool>> {
+    let filter = fee_tier.map_or(String::new(), |fee| {
+        format!(", where: {{feeTier: {}}}", fee)
+    });
+    let query = format!(
+        r#"{{
+            pools(first: {TOP_POOLS}, orderBy: totalValueLockedUSD, orderDirection: desc{filter}) {{
+                id
+                feeTier
+                token0Price
+                token1Price
+                totalValueLockedUSD
+                token0 {{ symbol name decimals }}
+                token1 {{ symbol name decimals }}
+                poolHourData(first: 24, orderBy: periodStartUnix, orderDirection: desc) {{
+                    periodStartUnix
+                    volumeUSD
+                }}
+            }}
+        }}"#
+    );
+
+    let data = checked(query_subgraph(subgraph_endpoint, api_key, &query).await?)?;
+    Ok(serde_json::from_value(data["data"]["pools"].clone())?)
+}
+
+/// Entities fetched per request, the most the subgraph returns at once
+const PAGE_SIZE: usize = 1000;
+
+/// The initialized ticks of `pool` from `from` to `to`, in order
+pub async fn fetch_ticks(
+    subgraph_endpoint: &str,
+    api_key: Option<&str>,
+    pool: &str,
+    (from, to): (i32, i32),
+) -> Result<Vec<PoolTick>> {
+    let mut ticks: Vec<PoolTick> = Vec::new();
+    // Paging with `skip` is capped, so each page starts after the last tick seen
+    let mut after = from - 1;
+    loop {
+        let query = format!(
+            r#"{{
+                ticks(first: {PAGE_SIZE}, orderBy: tickIdx, orderDirection: asc, where: {{pool: "{pool}", tickIdx_gt: {after}, tickIdx_lte: {to}}}) {{
+                    tickIdx
+                    liquidityNet
+                }}
+            }}"#
+        );
+        let data = checked(query_subgraph(subgraph_endpoint, api_key, &query).await?)?;
+        let page: Vec<PoolTick> = serde_json::from_value(data["data"]["ticks"].clone())?;
+        let full = page.len() == PAGE_SIZE;
+        match page.last().and_then(|tick| tick.tick_idx.parse().ok()) {
+            Some(last) => after = last,
+            None => break,
+        }
+        ticks.extend(page);
+        if !full {
+            break;
+        }
+    }
+    Ok(ticks)
+}
+
+/// Contract reads in flight at once when reading ticks on chain
+const RPC_CONCURRENCY: usize = 8;
+
+/// The initialized ticks of `pool` from `from` to `to`, in order, read from the
+/// pool contract: its tick bitmap for which ticks are initialized, then `ticks()`
+/// for the net liquidity of each. Every initialized tick is one call, so keep
+/// the span narrow, see `depth_span`.
+pub async fn fetch_ticks_onchain(
+    rpc_endpoint: &str,
+    pool: &str,
+    (from, to): (i32, i32),
+) -> Result<Vec<PoolTick>> {
+    let provider = Arc::new(Provider::<Http>::try_from(rpc_endpoint)?);
+    let contract = UniswapV3Pool::new(pool.parse::<Address>()?, provider);
+    let spacing = track(Upstream::Rpc, contract.tick_spacing().call()).await?;
+    if spacing <= 0 {
+        return Err(anyhow!("pool {} has a tick spacing of {}", pool, spacing));
+    }
+
+    // Each bit of a bitmap word is one tick spacing, 256 to a word
+    let (first, last) = (from.div_euclid(spacing), to.div_euclid(spacing));
+    let words = first.div_euclid(256)..=last.div_euclid(256);
+    let bitmaps: Vec<_> = stream::iter(words.map(|word| {
+        let call = contract.tick_bitmap(word as i16);
+        async move { Ok::<_, anyhow::Error>((word, track(Upstream::Rpc, call.call()).await?)) }
+    }))
+    .buffer_unordered(RPC_CONCURRENCY)
+    .try_collect()
+    .await?;
+    let initialized = bitmaps.into_iter().flat_map(|(word, bitmap)| {
+        (0..256)
+            .filter(move |bit| bitmap.bit(*bit))
+            .map(move |bit| (word * 256 + bit as i32) * spacing)
+            .filter(|tick| (from..=to).contains(tick))
+    });
+
+    let mut ticks: Vec<_> = stream::iter(initialized.map(|tick| {
+        let call = contract.ticks(tick);
+        async move { Ok::<_, anyhow::Error>((tick, track(Upstream::Rpc, call.call()).await?)) }
+    }))
+    .buffer_unordered(RPC_CONCURRENCY)
+    .try_collect()
+    .await?;
+    ticks.sort_by_key(|(tick, _)| *tick);
+    Ok(ticks
+        .into_iter()
+        .map(|(tick, (_, liquidity_net, ..))| PoolTick {
+            tick_idx: tick.to_string(),
+            liquidity_net: liquidity_net.to_string(),
+        })
+        .collect())
+}
+
+/// The hourly or daily snapshots of `pool` starting in `[from, to)`, oldest first
+pub async fn fetch_history(
+    subgraph_endpoint: &str,
+    api_key: Option<&str>,
+    pool: &str,
+    resolution: Resolution,
+    (from, to): (i64, i64),
+) -> Result<Backfill> {
+    let (entity, time) = match resolution {
+        Resolution::Hour => ("poolHourDatas", "periodStartUnix"),
+        Resolution::Day => ("poolDayDatas", "date"),
+    };
+    let mut rows: Vec<Value> = Vec::new();
+    let mut after = from - 1;
+    loop {
+        let query = format!(
+            r#"{{
+                {entity}(first: {PAGE_SIZE}, orderBy: {time}, orderDirection: asc, where: {{pool: "{pool}", {time}_gt: {after}, {time}_lt: {to}}}) {{
+                    {time}
+                    token0Price
+                    token1Price
+                    volumeUSD
+                    feesUSD
+                    tvlUSD
+                    open
+                    high
+                    low
+                    close
+                }}
+            }}"#
+        );
+        let data = checked(query_subgraph(subgraph_endpoint, api_key, &query).await?)?;
+        let page = data["data"][entity].as_array().cloned().unwrap_or_default();
+        let full = page.len() == PAGE_SIZE;
+        match page.last().and_then(|row| row[time].as_i64()) {
+            Some(last) => after = last,
+            None => break,
+        }
+        rows.extend(page);
+        if !full {
+            break;
+        }
+    }
+
+    let rows = Value::Array(rows);
+    Ok(match resolution {
+        Resolution::Hour => Backfill::Hours(serde_json::from_value(rows)?),
+        Resolution::Day => Backfill::Days(serde_json::from_value(rows)?),
+    })
+}
+
+/// Turns GraphQL errors in a subgraph response into an error
+fn checked(data: Value) -> Result<Value> {
+    match data["errors"].as_array().and_then(|errors| errors.first()) {
+        Some(error) => Err(anyhow!(
+            "{}",
+            error["message"]
+                .as_str()
+                .unwrap_or("the subgraph query failed")
+        )),
+        None => Ok(data),
+    }
+}
@@ -1,12 +1,21 @@
 use crate::models::position::Position;
+use crate::network::metrics::{self, track_response, Upstream};
 use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{
+    body::Incoming, server::conn::http1, service::service_fn, Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
 use serde_json::Value;
-use std::env;
+use std::{convert::Infallible, net::SocketAddr};
+use tokio::net::TcpListener;
 
-const UNISWAP_SUBGRAPH_URL: &str =
-    "https://gateway.thegraph.com/api/subgraphs/id/5zvR82QoaXYFyDEKLZ9t6v9adgnptxYpKpSbxtgVENFV";
-
-pub async fn fetch_positions(owner: &str) -> Result<(Vec<Position>, Vec<(f64, f64)>)> {
+pub async fn fetch_positions(
+    subgraph_endpoint: &str,
+    api_key: Option<&str>,
+    owner: &str,
+) -> Result<(Vec<Position>, f64)> {
     log::debug!("Fetching positions for owner: {}", owner);
     // Ensure the address has the 0x prefix and is lowercase
     let owner_address = if owner.starts_with("0x") {
@@ -18,20 +27,28 @@ pub async fn fetch_positions(owner: &str) -> Result<(Vec<Position>, Vec<(f64, f6
     let query = format!(
         r#"{{
             positions(where: {{owner: "{}", liquidity_gt: 0}}) {{
+                id
                 token0 {{
                     symbol
                     name
                     decimals
+                    derivedETH
                     volumeUSD
                 }}
                 token1 {{
                     symbol
                     name
                     decimals
+                    derivedETH
                     volumeUSD
                 }}
                 pool {{
                     id
+                    feeTier
+                    tick
+                    sqrtPrice
+                    feeGrowthGlobal0X128
+                    feeGrowthGlobal1X128
                     token0Price
                     token1Price
                     poolHourData(first: 24, orderBy: periodStartUnix, orderDirection: desc) {{
@@ -39,51 +56,101 @@ pub async fn fetch_positions(owner: &str) -> Result<(Vec<Position>, Vec<(f64, f6
                         token0Price
                         token1Price
                         volumeUSD
+                        feesUSD
+                        tvlUSD
+                        open
+                        high
+                        low
+                        close
                     }}
                     poolDayData(first: 7, orderBy: date, orderDirection: desc) {{
                         date
                         token0Price
                         token1Price
+                        volumeUSD
+                        feesUSD
+                        tvlUSD
+                        open
+                        high
+                        low
+                        close
                     }}
                 }}
-                tickLower {{feeGrowthOutside0X128 feeGrowthOutside1X128}}
-                tickUpper {{feeGrowthOutside0X128 feeGrowthOutside1X128}}
+                tickLower {{tickIdx feeGrowthOutside0X128 feeGrowthOutside1X128}}
+                tickUpper {{tickIdx feeGrowthOutside0X128 feeGrowthOutside1X128}}
                 withdrawnToken0
                 withdrawnToken1
                 depositedToken0
                 depositedToken1
+                collectedFeesToken0
+                collectedFeesToken1
+                feeGrowthInside0LastX128
+                feeGrowthInside1LastX128
                 liquidity
                 transaction {{
+                    id
                     timestamp
                 }}
             }}
-            tokenDayDatas(first: 24, orderBy: date, orderDirection: desc) {{
-                date
-                volumeUSD
+            bundle(id: "1") {{
+                ethPriceUSD
             }}
         }}"#,
         owner_address
     );
 
-    let api_key = match env::var("SUBGRAPH_API_KEY") {
-        Ok(key) => key,
-        Err(_) => {
-            return Err(anyhow!("SUBGRAPH_API_KEY environment variable is not set. Please set it by running:\n\nexport SUBGRAPH_API_KEY=your_api_key_here\n\nYou can get an API key from https://thegraph.com/studio/apikeys/"));
+    let data = query_subgraph(subgraph_endpoint, api_key, &query).await?;
+
+    log::debug!("Received response from subgraph: {:?}", data);
+    if let Some(errors) = data.get("errors") {
+        log::error!("GraphQL errors: {:?}", errors);
+        return Ok((Vec::new(), 0.0));
+    }
+
+    // Parse positions
+    let positions = data["data"]["positions"].clone();
+    let positions: Vec<Position> = match serde_json::from_value(positions) {
+        Ok(positions) => positions,
+        Err(e) => {
+            log::error!("Failed to parse positions: {}", e);
+            Vec::new()
+        }
+    };
+    log::debug!("Parsed {} positions", positions.len());
+
+    let eth_price_usd = data["data"]["bundle"]["ethPriceUSD"]
+        .as_str()
+        .and_then(|p| p.parse::<f64>().ok())
+        .unwrap_or(0.0);
+
+    Ok((positions, eth_price_usd))
+}
+
+/// Posts a GraphQL `query` to the subgraph and returns the parsed response,
+/// which may still hold GraphQL `errors`
+pub async fn query_subgraph(
+    subgraph_endpoint: &str,
+    api_key: Option<&str>,
+    query: &str,
+) -> Result<Value> {
+    let api_key = match api_key {
+        Some(key) => key,
+        None => {
+            return Err(anyhow!("SUBGRAPH_API_KEY is not set. Please set it by running:\n\nexport SUBGRAPH_API_KEY=your_api_key_here\n\nor add `subgraph_api_key` to the [credentials] section of your config file.\nYou can get an API key from https://thegraph.com/studio/apikeys/"));
         }
     };
 
     let client = reqwest::Client::new();
     log::debug!("Making request to Uniswap subgraph with query: {}", query);
-    let mut response = match client
-        .post(UNISWAP_SUBGRAPH_URL)
+    let request = client
+        .post(subgraph_endpoint)
         .header("Content-Type", "application/json")
         .header("Authorization", format!("Bearer {}", api_key))
         .json(&serde_json::json!({
             "query": query
         }))
-        .send()
-        .await
-    {
+        .send();
+    let mut response = match track_response(Upstream::Subgraph, request).await {
         Ok(res) => res,
         Err(e) => {
             log::error!("Failed to make request to subgraph: {}", e);
@@ -100,46 +167,44 @@ pub async fn fetch_positions(owner: &str) -> Result<(Vec<Position>, Vec<(f64, f6
     }
 
     let response_body = response.text().await?;
-    let data: Value = match serde_json::from_str(&response_body) {
-        Ok(data) => data,
+    match serde_json::from_str(&response_body) {
+        Ok(data) => Ok(data),
         Err(e) => {
             log::error!("Failed to parse subgraph response: {}", e);
-            return Err(e.into());
+            Err(e.into())
         }
-    };
+    }
+}
 
-    log::debug!("Received response from subgraph: {:?}", data);
-    if let Some(errors) = data.get("errors") {
-        log::error!("GraphQL errors: {:?}", errors);
-        return Ok((Vec::new(), Vec::new()));
+/// Serves the Prometheus `/metrics` endpoint on `addr` until the process exits
+pub async fn serve_metrics(addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    log::info!("Serving metrics on http://{}/metrics", addr);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service_fn(handle_metrics_request))
+                .await
+            {
+                log::warn!("Metrics connection error: {}", e);
+            }
+        });
     }
+}
 
-    // Parse positions
-    let positions = data["data"]["positions"].clone();
-    let positions: Vec<Position> = match serde_json::from_value(positions) {
-        Ok(positions) => positions,
-        Err(e) => {
-            log::error!("Failed to parse positions: {}", e);
-            Vec::new()
-        }
+async fn handle_metrics_request(
+    request: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Full::new(Bytes::from(metrics::encode()))),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Full::new(Bytes::from_static(b"Not Found"))),
     };
-    log::debug!("Parsed {} positions", positions.len());
-
-    // Parse volume data
-    let volume_data = data["data"]["tokenDayDatas"]
-        .as_array()
-        .unwrap_or(&Vec::new())
-        .iter()
-        .map(|day| {
-            let date = day["date"].as_i64().unwrap_or(0) as f64;
-            let volume = day["volumeUSD"]
-                .as_str()
-                .unwrap_or("0")
-                .parse::<f64>()
-                .unwrap_or(0.0);
-            (date, volume)
-        })
-        .collect();
 
-    Ok((positions, volume_data))
+    Ok(response.unwrap_or_default())
 }
@@ -1,17 +1,26 @@
 use crate::{
-    app::{App, Mode},
+    app::App,
+    models::position::Position,
     network::network::NetworkEvent,
+    routes::RouteId,
     widgets::{
+        breadcrumbs::render_breadcrumbs,
+        help::render_help_popup,
+        notifications::render_notifications,
+        position_info::render_position_info,
         table::{render_table, StatefulTable},
         welcome::render_welcome,
     },
 };
 
+use std::io::{self, Write};
+
 use log::debug;
 use ratatui::{
     backend::Backend,
     layout::{Constraint, Layout, Rect},
     prelude::Terminal,
+    style::Style,
     widgets::{Block, Clear, Tabs},
     Frame,
 };
@@ -30,15 +39,35 @@ pub fn draw<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) {
             // Clear the screen
             frame.render_widget(Clear, frame.area());
 
+            let theme = app.theme;
+
             // Set background color
-            frame.render_widget(Block::default(), frame.area());
+            frame.render_widget(
+                Block::default().style(Style::default().bg(theme.background())),
+                frame.area(),
+            );
+
+            // Every screen but the welcome one sits below the breadcrumbs
+            let route_id = app.get_current_route().get_id();
+            let area = match route_id {
+                RouteId::Welcome => frame.area(),
+                _ => {
+                    let layout = Layout::default()
+                        .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+                        .split(frame.area());
+                    render_breadcrumbs(frame, layout[0], &app.routes, &app.keymap, &theme);
+                    layout[1]
+                }
+            };
 
+            // Set again by whichever chart is drawn below
+            app.chart.cursor.undrawn();
             let stateful_table = &mut app.stateful_table;
-            match app.mode {
-                Mode::Welcome => {
+            match route_id {
+                RouteId::Welcome => {
                     let layout = Layout::default()
                         .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
-                        .split(frame.area());
+                        .split(area);
 
                     let (
                         banner,
@@ -49,7 +78,7 @@ pub fn draw<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) {
                         details_block,
                         ens_block,
                         prompt_message_block,
-                    ) = render_welcome(layout[0]);
+                    ) = render_welcome(layout[0], &theme);
                     frame.render_widget(banner, banner_block);
                     frame.render_widget(details, details_block);
                     frame.render_widget(prompt_message, prompt_message_block);
@@ -59,21 +88,83 @@ pub fn draw<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) {
                         &mut app.search_state.ens_state,
                     );
                 }
-                Mode::MyPositions => {
+                RouteId::MyPositions(_) => {
                     debug!("Drawing My Positions");
                     render_table(
                         frame,
                         stateful_table,
-                        frame.area(),
+                        area,
                         &app.positions,
-                        app.chart_time_range,
-                        app.chart_view,
+                        &mut app.chart,
+                        &theme,
                     );
                 }
-                _ => {}
+                RouteId::PositionInfo(Some(snapshot)) => {
+                    // Prefer the latest data, the route only holds the position as it was when opened
+                    let index = app.positions.iter().position(|p| p.id == snapshot.id);
+                    let position = index
+                        .and_then(|i| app.positions.get(i))
+                        .unwrap_or(&snapshot);
+                    render_position_info(
+                        frame,
+                        area,
+                        position,
+                        app.eth_price_usd,
+                        &mut app.chart,
+                        &theme,
+                    );
+                }
+                RouteId::PositionInfo(None) => {}
+                RouteId::PoolInfo => {
+                    let pool = app.pool_explorer.page.as_ref().map(|page| &page.pool.id);
+                    let ranges = position_ranges(&app.positions, pool);
+                    app.pool_explorer
+                        .render(frame, area, &mut app.chart, &ranges, &theme);
+                }
+                RouteId::TopPools => {
+                    app.top_pools.render(frame, area, &theme);
+                }
+                RouteId::RangeEstimator => {
+                    let pool = app.range_estimator.page.as_ref().map(|page| &page.pool.id);
+                    let ranges = position_ranges(&app.positions, pool);
+                    app.range_estimator.render(frame, area, &ranges, &theme);
+                }
+                RouteId::LimitOrders => {
+                    app.limit_orders_table
+                        .render(frame, area, &app.limit_orders, &theme);
+                }
+            }
+
+            render_notifications(frame, &app.notifications, frame.area(), &theme);
+
+            if app.show_help {
+                let context = app.key_context();
+                render_help_popup(
+                    frame,
+                    frame.area(),
+                    &theme,
+                    &app.keymap,
+                    context,
+                    &mut app.help_scroll,
+                );
             }
         })
         .unwrap();
+
+    if app.ring_bell {
+        app.ring_bell = false;
+        print!("\x07");
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Tick ranges of the positions in `pool`
+fn position_ranges(positions: &[Position], pool: Option<&String>) -> Vec<(i32, i32)> {
+    positions
+        .iter()
+        .filter(|position| Some(&position.pool.id) == pool)
+        .filter_map(Position::tick_range)
+        .collect()
 }
 
 fn draw_main<B: Backend>(frame: &mut Frame, app: &mut App, area: Rect) {
@@ -1,30 +1,90 @@
-use crate::{models::position::Position, network::ethers::types::AddressInfo};
+use std::mem::discriminant;
 
+use ratatui::widgets::TableState;
+
+use crate::{app::ChartState, models::position::Position, network::ethers::types::AddressInfo};
+
+/// A screen of the app, the last one on `App::routes` is drawn
 #[derive(Clone)]
 pub enum RouteId {
     Welcome,
     MyPositions(Option<AddressInfo>),
-    PositionInfo(Option<Position>),
+    /// Boxed, a position is far larger than the other screens' data
+    PositionInfo(Option<Box<Position>>),
+    PoolInfo,
+    TopPools,
+    RangeEstimator,
     LimitOrders,
 }
 
+impl RouteId {
+    /// Shown in the breadcrumbs
+    pub fn title(&self) -> String {
+        match self {
+            RouteId::Welcome => "Search".to_string(),
+            RouteId::MyPositions(Some(info)) => match &info.ens_id {
+                Some(ens) => format!("Positions of {}", ens),
+                None => {
+                    let address = format!("{:?}", info.address);
+                    format!(
+                        "Positions of {}…{}",
+                        &address[..6],
+                        &address[address.len() - 4..]
+                    )
+                }
+            },
+            RouteId::MyPositions(None) => "Positions".to_string(),
+            RouteId::PositionInfo(Some(position)) => {
+                format!("{} #{}", position.pair(), position.id)
+            }
+            RouteId::PositionInfo(None) => "Position".to_string(),
+            RouteId::PoolInfo => "Pool Explorer".to_string(),
+            RouteId::TopPools => "Top Pools".to_string(),
+            RouteId::RangeEstimator => "Fee Estimator".to_string(),
+            RouteId::LimitOrders => "Limit Orders".to_string(),
+        }
+    }
+
+    /// Whether both are the same screen, whatever they show on it
+    pub fn is_same_screen(&self, other: &RouteId) -> bool {
+        discriminant(self) == discriminant(other)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ActiveBlock {
     SearchBar,
     Main,
     MyPositions,
+    PositionInfo,
+    PoolInfo,
+    TopPools,
+    RangeEstimator,
     LimitOrders,
 }
 
+/// Scroll, selection and chart of a screen, saved when another one is opened over it
+/// and put back when it is returned to
+#[derive(Clone, Default)]
+pub struct ViewState {
+    pub table: Option<TableState>,
+    pub chart: Option<ChartState>,
+}
+
 #[derive(Clone)]
 pub struct Route {
     id: RouteId,
     active_block: ActiveBlock,
+    view: ViewState,
 }
 
 impl Route {
     pub fn new(id: RouteId, active_block: ActiveBlock) -> Self {
-        Self { id, active_block }
+        Self {
+            id,
+            active_block,
+            view: ViewState::default(),
+        }
     }
 
     pub fn get_active_block(&self) -> ActiveBlock {
@@ -34,6 +94,26 @@ impl Route {
     pub fn get_id(&self) -> RouteId {
         self.id.to_owned()
     }
+
+    pub fn id(&self) -> &RouteId {
+        &self.id
+    }
+
+    pub fn id_mut(&mut self) -> &mut RouteId {
+        &mut self.id
+    }
+
+    pub fn set_active_block(&mut self, active_block: ActiveBlock) {
+        self.active_block = active_block;
+    }
+
+    pub fn view(&self) -> &ViewState {
+        &self.view
+    }
+
+    pub fn set_view(&mut self, view: ViewState) {
+        self.view = view;
+    }
 }
 
 impl Default for Route {
@@ -41,6 +121,7 @@ impl Default for Route {
         Self {
             id: RouteId::Welcome,
             active_block: ActiveBlock::SearchBar,
+            view: ViewState::default(),
         }
     }
 }
@@ -6,6 +6,7 @@ use self::de::deserialize_option_color_hex_string;
 macro_rules! def_theme_struct_with_defaults {
     ($($name:ident => $color:expr),+) => {
         #[derive(Debug, Clone, Copy, Deserialize)]
+        #[serde(deny_unknown_fields)]
         pub struct Theme {
             $(
                 #[serde(deserialize_with = "deserialize_option_color_hex_string")]
@@ -28,6 +29,28 @@ macro_rules! def_theme_struct_with_defaults {
                 }
             }
         }
+        impl Theme {
+            /// Returns `self` with every color set in `overrides` replaced
+            pub fn merge(self, overrides: Theme) -> Theme {
+                Self {
+                    $( $name: overrides.$name.or(self.$name), )+
+                }
+            }
+
+            /// A theme without any colors set, useful as an empty set of overrides
+            pub fn unset() -> Theme {
+                Self {
+                    $( $name: None, )+
+                }
+            }
+
+            /// A theme where every color is the terminal's default
+            fn uncolored() -> Theme {
+                Self {
+                    $( $name: Some(Color::Reset), )+
+                }
+            }
+        }
     };
 }
 
@@ -43,9 +66,92 @@ def_theme_struct_with_defaults!(
     border_secondary => Color::Reset,
     border_axis => Color::Blue,
     highlight_focused => Color::LightBlue,
-    highlight_unfocused => Color::DarkGray
+    highlight_unfocused => Color::DarkGray,
+    series_primary => Color::Green,
+    series_secondary => Color::Yellow
 );
 
+/// Built-in color schemes, selectable with `preset` in the `[theme]` config table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    Monochrome,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 4] = [
+        ThemePreset::Dark,
+        ThemePreset::Light,
+        ThemePreset::HighContrast,
+        ThemePreset::Monochrome,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemePreset::Dark => "dark",
+            ThemePreset::Light => "light",
+            ThemePreset::HighContrast => "high-contrast",
+            ThemePreset::Monochrome => "monochrome",
+        }
+    }
+
+    pub fn next(self) -> ThemePreset {
+        let idx = ThemePreset::ALL
+            .iter()
+            .position(|p| *p == self)
+            .unwrap_or(0);
+        ThemePreset::ALL[(idx + 1) % ThemePreset::ALL.len()]
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemePreset::Dark => Theme::default(),
+            ThemePreset::Light => Theme {
+                background: Some(Color::Reset),
+                gray: Some(Color::Gray),
+                profit: Some(Color::Green),
+                loss: Some(Color::Red),
+                text_normal: Some(Color::Black),
+                text_primary: Some(Color::Blue),
+                text_secondary: Some(Color::Magenta),
+                border_primary: Some(Color::DarkGray),
+                border_secondary: Some(Color::Gray),
+                border_axis: Some(Color::DarkGray),
+                highlight_focused: Some(Color::Blue),
+                highlight_unfocused: Some(Color::Gray),
+                series_primary: Some(Color::Blue),
+                series_secondary: Some(Color::Magenta),
+            },
+            ThemePreset::HighContrast => Theme {
+                background: Some(Color::Black),
+                gray: Some(Color::White),
+                profit: Some(Color::LightGreen),
+                loss: Some(Color::LightRed),
+                text_normal: Some(Color::White),
+                text_primary: Some(Color::LightYellow),
+                text_secondary: Some(Color::LightCyan),
+                border_primary: Some(Color::White),
+                border_secondary: Some(Color::White),
+                border_axis: Some(Color::White),
+                highlight_focused: Some(Color::LightYellow),
+                highlight_unfocused: Some(Color::White),
+                series_primary: Some(Color::LightGreen),
+                series_secondary: Some(Color::LightMagenta),
+            },
+            ThemePreset::Monochrome => Theme::uncolored(),
+        }
+    }
+}
+
+/// Whether the user asked for no colors, see <https://no-color.org>
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
 fn hex_to_color(hex: &str) -> Option<Color> {
     if hex.len() == 7 {
         let hash = &hex[0..1];
@@ -1,6 +1,6 @@
 pub const RATE_LIMIT: usize = 60;
 
 pub const GENERAL_HELP_TEXT: &str =
-    "<esc>: Cancel, q: Quit, ?: Keybindings, s: Focus on the Search bar";
+    "<esc>: Cancel, q: Quit, ?: Keybindings, s: Focus on the Search bar, ctrl-t: Theme";
 
 pub const TICK_RATE: u64 = 200;
@@ -0,0 +1,44 @@
+/// Formats a token amount with more decimals the smaller it is
+pub fn format_amount(amount: f64) -> String {
+    if amount.abs() >= 1000.0 {
+        format!("{:.2}", amount)
+    } else if amount.abs() >= 1.0 {
+        format!("{:.4}", amount)
+    } else {
+        format!("{:.6}", amount)
+    }
+}
+
+/// Formats a USD value compactly, e.g. `$1.23M`
+pub fn format_usd(value: f64) -> String {
+    let abs = value.abs();
+    let sign = if value < 0.0 { "-" } else { "" };
+    if abs >= 1e9 {
+        format!("{}${:.2}B", sign, abs / 1e9)
+    } else if abs >= 1e6 {
+        format!("{}${:.2}M", sign, abs / 1e6)
+    } else if abs >= 1e4 {
+        format!("{}${:.1}K", sign, abs / 1e3)
+    } else {
+        format!("{}${:.2}", sign, abs)
+    }
+}
+
+/// Formats an axis label with about four significant digits, compacting large
+/// values, e.g. `2069`, `0.0005120` or `1.23M`
+pub fn format_axis(value: f64) -> String {
+    let abs = value.abs();
+    let sign = if value < 0.0 { "-" } else { "" };
+    if abs >= 1e9 {
+        format!("{}{:.2}B", sign, abs / 1e9)
+    } else if abs >= 1e6 {
+        format!("{}{:.2}M", sign, abs / 1e6)
+    } else if abs >= 1e4 {
+        format!("{}{:.1}K", sign, abs / 1e3)
+    } else if abs == 0.0 {
+        "0".to_string()
+    } else {
+        let decimals = (3 - abs.log10().floor() as i32).clamp(0, 12) as usize;
+        format!("{}{:.*}", sign, decimals, abs)
+    }
+}
@@ -0,0 +1,217 @@
+//! Technical indicators over `(time, value)` series sorted by time. Each
+//! returns points from the first time it has enough values for on.
+
+/// Hours in a year, to annualize volatility from hourly returns
+pub const HOURS_PER_YEAR: f64 = 24.0 * 365.0;
+
+/// Lines drawn `width` standard deviations either side of a moving average
+#[derive(Debug, Clone, Default)]
+pub struct Bands {
+    pub upper: Vec<(f64, f64)>,
+    pub middle: Vec<(f64, f64)>,
+    pub lower: Vec<(f64, f64)>,
+}
+
+fn mean(values: &[(f64, f64)]) -> f64 {
+    values.iter().map(|(_, v)| v).sum::<f64>() / values.len() as f64
+}
+
+/// Population standard deviation
+fn std_dev(values: &[(f64, f64)]) -> f64 {
+    let mean = mean(values);
+    let variance =
+        values.iter().map(|(_, v)| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt()
+}
+
+/// Simple moving average of the last `period` values
+pub fn sma(series: &[(f64, f64)], period: usize) -> Vec<(f64, f64)> {
+    if period == 0 {
+        return vec![];
+    }
+    series
+        .windows(period)
+        .map(|window| (window[period - 1].0, mean(window)))
+        .collect()
+}
+
+/// Exponential moving average weighting the latest value by `2 / (period + 1)`,
+/// starting from the simple average of the first `period` values
+pub fn ema(series: &[(f64, f64)], period: usize) -> Vec<(f64, f64)> {
+    if period == 0 || series.len() < period {
+        return vec![];
+    }
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut average = mean(&series[..period]);
+    let mut points = vec![(series[period - 1].0, average)];
+    for &(time, value) in &series[period..] {
+        average += alpha * (value - average);
+        points.push((time, average));
+    }
+    points
+}
+
+/// Bollinger bands: the simple moving average over `period` values and
+/// `width` standard deviations of those values above and below it
+pub fn bollinger(series: &[(f64, f64)], period: usize, width: f64) -> Bands {
+    let mut bands = Bands::default();
+    if period == 0 {
+        return bands;
+    }
+    for window in series.windows(period) {
+        let (time, mean, deviation) = (window[period - 1].0, mean(window), std_dev(window));
+        bands.upper.push((time, mean + width * deviation));
+        bands.middle.push((time, mean));
+        bands.lower.push((time, mean - width * deviation));
+    }
+    bands
+}
+
+/// Log returns from each value to the next, at the time of the later one,
+/// skipping values that are not positive
+pub fn log_returns(series: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let positive: Vec<_> = series.iter().filter(|(_, v)| *v > 0.0).collect();
+    positive
+        .windows(2)
+        .map(|pair| (pair[1].0, (pair[1].1 / pair[0].1).ln()))
+        .collect()
+}
+
+/// Realized volatility in percent: the standard deviation of the last `window`
+/// log returns, annualized for `periods_per_year` values a year
+pub fn realized_volatility(
+    series: &[(f64, f64)],
+    window: usize,
+    periods_per_year: f64,
+) -> Vec<(f64, f64)> {
+    if window == 0 {
+        return vec![];
+    }
+    log_returns(series)
+        .windows(window)
+        .map(|returns| {
+            let time = returns[window - 1].0;
+            (time, std_dev(returns) * periods_per_year.sqrt() * 100.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::E;
+
+    use super::*;
+
+    /// `values` at times 0, 1, 2...
+    fn series(values: &[f64]) -> Vec<(f64, f64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (i as f64, *value))
+            .collect()
+    }
+
+    fn assert_close(actual: &[(f64, f64)], expected: &[(f64, f64)]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?} != {expected:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert_eq!(a.0, e.0, "{actual:?} != {expected:?}");
+            assert!((a.1 - e.1).abs() < 1e-9, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn sma_of_last_values() {
+        let prices = series(&[1.0, 2.0, 6.0, 4.0, 8.0]);
+        assert_close(&sma(&prices, 3), &[(2.0, 3.0), (3.0, 4.0), (4.0, 6.0)]);
+        assert_close(&sma(&prices, 1), &prices);
+        assert_close(&sma(&prices, 5), &[(4.0, 4.2)]);
+    }
+
+    #[test]
+    fn ema_seeded_with_sma() {
+        // Weights of 2/3 after the average of the first two
+        let prices = series(&[2.0, 4.0, 6.0, 8.0, 4.0]);
+        assert_close(
+            &ema(&prices, 2),
+            &[(1.0, 3.0), (2.0, 5.0), (3.0, 7.0), (4.0, 5.0)],
+        );
+        assert_close(&ema(&prices, 5), &[(4.0, 4.8)]);
+    }
+
+    #[test]
+    fn bollinger_population_deviation() {
+        // Mean 5 and population standard deviation 2
+        let prices = series(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        let bands = bollinger(&prices, 8, 2.0);
+        assert_close(&bands.middle, &[(7.0, 5.0)]);
+        assert_close(&bands.upper, &[(7.0, 9.0)]);
+        assert_close(&bands.lower, &[(7.0, 1.0)]);
+
+        let bands = bollinger(&prices, 2, 1.0);
+        assert_eq!(bands.middle.len(), 7);
+        assert_close(&bands.upper[..1], &[(1.0, 4.0)]);
+        assert_close(&bands.lower[..1], &[(1.0, 2.0)]);
+    }
+
+    #[test]
+    fn realized_volatility_annualized() {
+        // Log returns of 1, -1, 1: a deviation of 1 over any two of them
+        let prices = series(&[1.0, E, 1.0, E]);
+        assert_close(
+            &log_returns(&prices),
+            &[(1.0, 1.0), (2.0, -1.0), (3.0, 1.0)],
+        );
+        assert_close(
+            &realized_volatility(&prices, 2, 4.0),
+            &[(2.0, 200.0), (3.0, 200.0)],
+        );
+        assert_close(
+            &realized_volatility(&prices, 2, HOURS_PER_YEAR),
+            &[
+                (2.0, HOURS_PER_YEAR.sqrt() * 100.0),
+                (3.0, HOURS_PER_YEAR.sqrt() * 100.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn log_returns_skip_non_positive() {
+        let prices = series(&[1.0, 0.0, E, -1.0, E * E]);
+        assert_close(&log_returns(&prices), &[(2.0, 1.0), (4.0, 1.0)]);
+    }
+
+    #[test]
+    fn period_zero() {
+        let prices = series(&[1.0, 2.0, 3.0]);
+        assert!(sma(&prices, 0).is_empty());
+        assert!(ema(&prices, 0).is_empty());
+        let bands = bollinger(&prices, 0, 2.0);
+        assert!(bands.upper.is_empty() && bands.middle.is_empty() && bands.lower.is_empty());
+        assert!(realized_volatility(&prices, 0, HOURS_PER_YEAR).is_empty());
+    }
+
+    #[test]
+    fn period_longer_than_series() {
+        let prices = series(&[1.0, 2.0, 3.0]);
+        assert!(sma(&prices, 4).is_empty());
+        assert!(ema(&prices, 4).is_empty());
+        assert!(bollinger(&prices, 4, 2.0).middle.is_empty());
+        // Three prices make only two returns
+        assert!(realized_volatility(&prices, 3, HOURS_PER_YEAR).is_empty());
+        assert!(sma(&[], 1).is_empty() && ema(&[], 1).is_empty());
+    }
+
+    #[test]
+    fn flat_series() {
+        let prices = series(&[5.0; 6]);
+        assert_close(&sma(&prices, 3), &series(&[5.0; 6])[2..]);
+        assert_close(&ema(&prices, 3), &series(&[5.0; 6])[2..]);
+        let bands = bollinger(&prices, 3, 2.0);
+        assert_close(&bands.upper, &bands.middle);
+        assert_close(&bands.lower, &bands.middle);
+        assert_close(
+            &realized_volatility(&prices, 3, HOURS_PER_YEAR),
+            &[(3.0, 0.0), (4.0, 0.0), (5.0, 0.0)],
+        );
+    }
+}
@@ -1,2 +1,8 @@
 pub mod constants;
+pub mod format;
+pub mod indicators;
 pub mod list;
+pub mod paths;
+pub mod pool_series;
+pub mod series;
+pub mod uniswap;
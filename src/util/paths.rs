@@ -0,0 +1,22 @@
+use std::{env, path::PathBuf};
+
+const APP_NAME: &str = "gerrehbenta";
+
+/// `$XDG_CONFIG_HOME/gerrehbenta`, falling back to `~/.config/gerrehbenta`
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// `$XDG_DATA_HOME/gerrehbenta`, falling back to `~/.local/share/gerrehbenta`
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(fallback)))
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_NAME)
+}
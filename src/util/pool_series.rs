@@ -0,0 +1,339 @@
+//! The series a pool's chart is drawn from, built from its hourly or daily
+//! snapshots over the window and view a `ChartState` picks
+
+use crate::{
+    app::{ChartState, ChartView, PriceScale},
+    models::{history::Resolution, position::Pool},
+    util::series::{fill_gaps, Fill},
+};
+
+/// What a snapshot is worth to the chart
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapshot {
+    pub time: f64,
+    pub token0: f64,
+    pub token1: f64,
+    /// The amount charted outside the price view, e.g. the volume
+    pub amount: f64,
+}
+
+/// The snapshots of `pool` at the resolution asked for, oldest first
+pub fn snapshots(pool: &Pool, is_hourly: bool, view: ChartView) -> Vec<Snapshot> {
+    let parse = |value: Option<&String>| value.and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+    let mut snapshots: Vec<Snapshot> = if is_hourly {
+        pool.pool_hour_data
+            .iter()
+            .map(|d| Snapshot {
+                time: d.period_start_unix,
+                token0: parse(d.token0_price.as_ref()),
+                token1: parse(d.token1_price.as_ref()),
+                amount: parse(match view {
+                    ChartView::Price | ChartView::Candles | ChartView::Volume => {
+                        Some(&d.volume_usd)
+                    }
+                    ChartView::Fees => d.fees_usd.as_ref(),
+                    ChartView::Tvl => d.tvl_usd.as_ref(),
+                }),
+            })
+            .collect()
+    } else {
+        pool.pool_day_datas
+            .iter()
+            .map(|d| Snapshot {
+                time: d.date,
+                token0: parse(Some(&d.token0Price)),
+                token1: parse(Some(&d.token1Price)),
+                amount: parse(match view {
+                    ChartView::Price | ChartView::Candles | ChartView::Volume => {
+                        d.volume_usd.as_ref()
+                    }
+                    ChartView::Fees => d.fees_usd.as_ref(),
+                    ChartView::Tvl => d.tvl_usd.as_ref(),
+                }),
+            })
+            .collect()
+    };
+    snapshots.sort_by(|a, b| a.time.total_cmp(&b.time));
+    snapshots
+}
+
+/// The series of a pool's chart over its window
+#[derive(Debug, Clone)]
+pub struct PoolSeries {
+    /// Times at the left and right edges
+    pub window: [f64; 2],
+    pub is_hourly: bool,
+    /// Seconds between snapshots
+    pub step: f64,
+    /// Whether the latest snapshot of a preset range is too old, or there were
+    /// none and the latest ones before it are shown instead
+    pub stale: bool,
+    /// Every snapshot, oldest first, which indicators warm up on
+    pub snapshots: Vec<Snapshot>,
+    /// Prices of the token shown first, token1 once the pair is inverted
+    pub token0: Vec<(f64, f64)>,
+    /// Prices of the token shown second
+    pub token1: Vec<(f64, f64)>,
+    /// The view's amount of every period
+    pub amounts: Vec<(f64, f64)>,
+    pub inverted: bool,
+}
+
+impl PoolSeries {
+    pub fn new(pool: &Pool, chart: &ChartState, now: f64) -> Self {
+        let is_hourly = chart.resolution(now) == Resolution::Hour;
+        Self::from_snapshots(snapshots(pool, is_hourly, chart.view), chart, now)
+    }
+
+    /// The series of `snapshots`, sorted oldest first. Periods without swaps have
+    /// no snapshot: prices and TVL carry on from the snapshot before, which may
+    /// be left of the window, and nothing is traded.
+    pub fn from_snapshots(snapshots: Vec<Snapshot>, chart: &ChartState, now: f64) -> Self {
+        let (from, to) = chart.window(now);
+        let resolution = chart.resolution(now);
+        let is_hourly = resolution == Resolution::Hour;
+        let preset = chart.viewport.is_none();
+        let step = resolution.seconds() as f64;
+
+        let mut window = [from, to];
+        let mut latest = snapshots
+            .iter()
+            .rev()
+            .find(|s| from <= s.time && s.time <= to)
+            .map(|s| s.time);
+        // A preset range without hourly snapshots shows as many of the latest ones
+        let mut fallback = false;
+        if latest.is_none() && is_hourly && preset {
+            let count = ((to - from) / 3600.0) as usize;
+            let recent = &snapshots[snapshots.len().saturating_sub(count)..];
+            if let (Some(first), Some(last)) = (recent.first(), recent.last()) {
+                window = [first.time, last.time];
+                latest = Some(last.time);
+                fallback = true;
+            }
+        }
+        let max_age = if is_hourly {
+            to - from
+        } else {
+            60.0 * 60.0 * 24.0 * 7.0
+        };
+        let stale = preset && (fallback || latest.is_none_or(|time| now - time > max_age));
+
+        let before = snapshots
+            .partition_point(|s| s.time < window[0])
+            .saturating_sub(1);
+        let in_view: &[Snapshot] = match latest {
+            Some(_) => &snapshots[before..],
+            None => &[],
+        };
+        let series = |value: fn(&Snapshot) -> f64, fill: Fill| -> Vec<(f64, f64)> {
+            let points: Vec<_> = in_view
+                .iter()
+                .filter(|s| s.time <= window[1])
+                .map(|s| (s.time, value(s)))
+                .collect();
+            fill_gaps(&points, step, window[1], fill)
+                .into_iter()
+                .filter(|(time, _)| *time >= window[0])
+                .collect()
+        };
+        let (mut token0, mut token1) = (
+            series(|s| s.token0, Fill::CarryForward),
+            series(|s| s.token1, Fill::CarryForward),
+        );
+        let amounts = match chart.view {
+            ChartView::Tvl => series(|s| s.amount, Fill::CarryForward),
+            _ => series(|s| s.amount, Fill::Zero),
+        };
+        // The pool's prices are each other's inverse, so inverting the pair swaps them
+        if chart.inverted {
+            std::mem::swap(&mut token0, &mut token1);
+        }
+        Self {
+            window,
+            is_hourly,
+            step,
+            stale,
+            snapshots,
+            token0,
+            token1,
+            amounts,
+            inverted: chart.inverted,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.token0.is_empty() && self.token1.is_empty()
+    }
+
+    /// Prices of the pool's token1, which a position's range is in
+    pub fn range_prices(&self) -> &[(f64, f64)] {
+        if self.inverted {
+            &self.token0
+        } else {
+            &self.token1
+        }
+    }
+
+    /// Prices of the token shown first at every snapshot, for indicators
+    pub fn first_prices(&self) -> Vec<(f64, f64)> {
+        self.snapshots
+            .iter()
+            .map(|s| (s.time, if self.inverted { s.token1 } else { s.token0 }))
+            .filter(|(_, price)| *price > 0.0)
+            .collect()
+    }
+
+    /// The pool's token1 price at the snapshot covering `time`, wherever the window is
+    pub fn token1_at(&self, time: f64) -> Option<f64> {
+        let covering = self.snapshots.partition_point(|s| s.time <= time);
+        Some(self.snapshots.get(covering.checked_sub(1)?)?.token1)
+    }
+}
+
+/// The value of the point covering `time` in a series sorted by time
+pub fn value_at(series: &[(f64, f64)], time: f64) -> Option<f64> {
+    let covering = series.partition_point(|(t, _)| *t <= time);
+    Some(series.get(covering.checked_sub(1)?)?.1)
+}
+
+/// Percentage of `prices` for which `contains` holds, `None` without prices
+pub fn share_in_range(prices: &[(f64, f64)], contains: impl Fn(f64) -> bool) -> Option<f64> {
+    if prices.is_empty() {
+        return None;
+    }
+    let in_range = prices.iter().filter(|(_, price)| contains(*price)).count();
+    Some(in_range as f64 / prices.len() as f64 * 100.0)
+}
+
+/// The first price of `series` that isn't zero, which percent changes are from
+pub fn first_price(series: &[(f64, f64)]) -> Option<f64> {
+    series
+        .iter()
+        .map(|(_, price)| *price)
+        .find(|price| *price != 0.0)
+}
+
+/// Where `price` is plotted on `scale`, `first` being the first price of its
+/// series. `None` where the scale has no place for it.
+pub fn on_scale(scale: PriceScale, price: f64, first: Option<f64>) -> Option<f64> {
+    match scale {
+        PriceScale::Shared | PriceScale::Dual => Some(price),
+        PriceScale::Log => (price > 0.0).then(|| price.log10()),
+        PriceScale::Percent => Some((price / first? - 1.0) * 100.0),
+    }
+}
+
+/// `series` plotted on `scale`, dropping the prices it has no place for
+pub fn scaled(scale: PriceScale, series: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let first = first_price(series);
+    series
+        .iter()
+        .filter_map(|(time, price)| Some((*time, on_scale(scale, *price, first)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: f64 = 60.0 * 60.0;
+    const NOW: f64 = 1_000.0 * HOUR;
+
+    fn snapshot(time: f64, token1: f64) -> Snapshot {
+        Snapshot {
+            time,
+            token0: 1.0 / token1,
+            token1,
+            amount: 10.0,
+        }
+    }
+
+    /// A day up to `NOW`, or `viewport` when given
+    fn chart(viewport: Option<(f64, f64)>) -> ChartState {
+        ChartState {
+            viewport,
+            ..ChartState::default()
+        }
+    }
+
+    #[test]
+    fn carries_the_price_from_before_the_window_and_fills_gaps() {
+        let snapshots = vec![
+            snapshot(NOW - 30.0 * HOUR, 2.0),
+            snapshot(NOW - 2.0 * HOUR, 4.0),
+        ];
+        let series = PoolSeries::from_snapshots(snapshots, &chart(None), NOW);
+        assert_eq!(series.window, [NOW - 24.0 * HOUR, NOW]);
+        assert!(!series.stale);
+        assert_eq!(series.token1.len(), 25);
+        assert_eq!(series.token1.first(), Some(&(NOW - 24.0 * HOUR, 2.0)));
+        assert_eq!(series.token1.last(), Some(&(NOW, 4.0)));
+        // Nothing is traded in the periods without a snapshot
+        assert_eq!(series.amounts.iter().filter(|(_, v)| *v > 0.0).count(), 1);
+    }
+
+    #[test]
+    fn preset_without_recent_snapshots_falls_back_to_the_latest() {
+        let snapshots: Vec<_> = (0..30)
+            .map(|i| snapshot(NOW - 100.0 * HOUR + i as f64 * HOUR, 2.0))
+            .collect();
+        let series = PoolSeries::from_snapshots(snapshots, &chart(None), NOW);
+        assert_eq!(series.window, [NOW - 94.0 * HOUR, NOW - 71.0 * HOUR]);
+        assert!(series.stale);
+        assert_eq!(series.token1.len(), 24);
+    }
+
+    #[test]
+    fn viewport_is_kept_and_never_stale() {
+        let viewport = (NOW - 50.0 * HOUR, NOW - 40.0 * HOUR);
+        let snapshots = vec![snapshot(NOW - 60.0 * HOUR, 2.0)];
+        let series = PoolSeries::from_snapshots(snapshots, &chart(Some(viewport)), NOW);
+        assert_eq!(series.window, [viewport.0, viewport.1]);
+        assert!(!series.stale);
+        assert!(series.is_empty());
+    }
+
+    #[test]
+    fn inverting_swaps_the_prices_but_not_the_range_prices() {
+        let snapshots = vec![snapshot(NOW - HOUR, 4.0)];
+        let inverted = ChartState {
+            inverted: true,
+            ..chart(None)
+        };
+        let series = PoolSeries::from_snapshots(snapshots, &inverted, NOW);
+        assert_eq!(series.token0.last(), Some(&(NOW, 4.0)));
+        assert_eq!(series.range_prices().last(), Some(&(NOW, 4.0)));
+        assert_eq!(series.first_prices(), [(NOW - HOUR, 4.0)]);
+        assert_eq!(series.token1_at(NOW), Some(4.0));
+        assert_eq!(series.token1_at(NOW - 2.0 * HOUR), None);
+    }
+
+    #[test]
+    fn scales() {
+        let series = [(0.0, 0.0), (1.0, 10.0), (2.0, 100.0), (3.0, 5.0)];
+        assert_eq!(scaled(PriceScale::Dual, &series), series);
+        assert_eq!(
+            scaled(PriceScale::Log, &series),
+            [(1.0, 1.0), (2.0, 2.0), (3.0, 5f64.log10())]
+        );
+        assert_eq!(
+            scaled(PriceScale::Percent, &series),
+            [(0.0, -100.0), (1.0, 0.0), (2.0, 900.0), (3.0, -50.0)]
+        );
+        assert!(scaled(PriceScale::Percent, &[(0.0, 0.0)]).is_empty());
+        assert_eq!(on_scale(PriceScale::Percent, 15.0, Some(10.0)), Some(50.0));
+    }
+
+    #[test]
+    fn looks_up_values_and_the_share_in_range() {
+        let series = [(0.0, 1.0), (10.0, 2.0), (20.0, 3.0)];
+        assert_eq!(value_at(&series, 15.0), Some(2.0));
+        assert_eq!(value_at(&series, -1.0), None);
+        assert_eq!(
+            share_in_range(&series, |p| p >= 2.0).map(f64::round),
+            Some(67.0)
+        );
+        assert_eq!(share_in_range(&[], |_| true), None);
+    }
+}
@@ -0,0 +1,288 @@
+//! Gap filling and resampling of `(time, value)` series sorted by time
+
+/// Monday 1970-01-05, where weeks start
+const FIRST_MONDAY: f64 = 60.0 * 60.0 * 24.0 * 4.0;
+
+/// What a period without a point is worth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fill {
+    /// The value before it, for levels like prices and TVL
+    CarryForward,
+    /// Nothing, for amounts per period like volume and fees
+    Zero,
+}
+
+/// How the values falling in one bucket are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// The latest, for levels
+    Last,
+    /// Their total, for amounts per period
+    Sum,
+}
+
+/// Periods points are resampled to, each starting at a multiple of its
+/// length since the epoch, or on a Monday for weeks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucket {
+    Hour,
+    FourHours,
+    Day,
+    Week,
+}
+
+impl Bucket {
+    pub const ALL: [Bucket; 4] = [Bucket::Hour, Bucket::FourHours, Bucket::Day, Bucket::Week];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Bucket::Hour => "hourly",
+            Bucket::FourHours => "4h",
+            Bucket::Day => "daily",
+            Bucket::Week => "weekly",
+        }
+    }
+
+    pub fn seconds(&self) -> f64 {
+        match self {
+            Bucket::Hour => 60.0 * 60.0,
+            Bucket::FourHours => 60.0 * 60.0 * 4.0,
+            Bucket::Day => 60.0 * 60.0 * 24.0,
+            Bucket::Week => 60.0 * 60.0 * 24.0 * 7.0,
+        }
+    }
+
+    /// Start of the bucket `time` falls in
+    pub fn start(&self, time: f64) -> f64 {
+        let origin = match self {
+            Bucket::Week => FIRST_MONDAY,
+            _ => 0.0,
+        };
+        time - (time - origin).rem_euclid(self.seconds())
+    }
+
+    /// The finest bucket at least `step` long that splits `span` into at
+    /// most `count` buckets, weeks if none does
+    pub fn fitting(step: f64, span: f64, count: usize) -> Bucket {
+        Bucket::ALL
+            .into_iter()
+            .filter(|bucket| bucket.seconds() >= step)
+            .find(|bucket| span / bucket.seconds() <= count as f64)
+            .unwrap_or(Bucket::Week)
+    }
+}
+
+/// Adds a point every `step` where the series has none, from its first point
+/// up to `until`, worth what `fill` says
+pub fn fill_gaps(series: &[(f64, f64)], step: f64, until: f64, fill: Fill) -> Vec<(f64, f64)> {
+    let mut filled = Vec::with_capacity(series.len());
+    for (i, &(time, value)) in series.iter().enumerate() {
+        filled.push((time, value));
+        let next = series.get(i + 1).map_or(until + step, |(next, _)| *next);
+        let missing = match fill {
+            Fill::CarryForward => value,
+            Fill::Zero => 0.0,
+        };
+        // Half a step of slack so points a little off the grid aren't doubled
+        let mut time = time + step;
+        while time < next - step / 2.0 {
+            filled.push((time, missing));
+            time += step;
+        }
+    }
+    filled
+}
+
+/// One point per `bucket` with any, at its start, combining its values as
+/// `aggregate` says
+pub fn resample(series: &[(f64, f64)], bucket: Bucket, aggregate: Aggregate) -> Vec<(f64, f64)> {
+    let mut resampled: Vec<(f64, f64)> = Vec::new();
+    for &(time, value) in series {
+        let start = bucket.start(time);
+        match resampled.last_mut() {
+            Some((last, total)) if *last == start => match aggregate {
+                Aggregate::Last => *total = value,
+                Aggregate::Sum => *total += value,
+            },
+            _ => resampled.push((start, value)),
+        }
+    }
+    resampled
+}
+
+/// Splits the series' time span into `count` equal slices and keeps the
+/// lowest and highest point of each, in time order, so a line through them
+/// looks the same at a resolution of `count` dots across. The first and last
+/// points are always kept so the line spans the same times.
+pub fn downsample(series: &[(f64, f64)], count: usize) -> Vec<(f64, f64)> {
+    let (Some(first), Some(last)) = (series.first(), series.last()) else {
+        return vec![];
+    };
+    if count == 0 || series.len() <= count * 2 {
+        return series.to_vec();
+    }
+    let width = (last.0 - first.0) / count as f64;
+    let slice = |time: f64| (((time - first.0) / width) as usize).min(count - 1);
+
+    let mut kept = Vec::with_capacity(count * 2 + 2);
+    let mut i = 0;
+    while i < series.len() {
+        let current = slice(series[i].0);
+        let end = i + series[i..].partition_point(|(time, _)| slice(*time) == current);
+        let by_value = |a: &usize, b: &usize| series[*a].1.total_cmp(&series[*b].1);
+        let low = (i..end).min_by(by_value).unwrap_or(i);
+        let high = (i..end).max_by(by_value).unwrap_or(i);
+        let mut points = vec![low, high];
+        if i == 0 {
+            points.push(0);
+        }
+        if end == series.len() {
+            points.push(end - 1);
+        }
+        points.sort_unstable();
+        points.dedup();
+        kept.extend(points.into_iter().map(|point| series[point]));
+        i = end;
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: f64 = 60.0 * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+
+    /// `values` an hour apart from `start`
+    fn hourly(start: f64, values: &[f64]) -> Vec<(f64, f64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (start + i as f64 * HOUR, *value))
+            .collect()
+    }
+
+    #[test]
+    fn fill_gaps_carries_levels_forward() {
+        let series = [(0.0, 1.0), (3.0 * HOUR, 2.0)];
+        assert_eq!(
+            fill_gaps(&series, HOUR, 5.0 * HOUR, Fill::CarryForward),
+            hourly(0.0, &[1.0, 1.0, 1.0, 2.0, 2.0, 2.0])
+        );
+    }
+
+    #[test]
+    fn fill_gaps_zeroes_amounts() {
+        let series = [(0.0, 5.0), (3.0 * HOUR, 2.0)];
+        assert_eq!(
+            fill_gaps(&series, HOUR, 4.0 * HOUR, Fill::Zero),
+            hourly(0.0, &[5.0, 0.0, 0.0, 2.0, 0.0])
+        );
+    }
+
+    #[test]
+    fn fill_gaps_leaves_complete_series() {
+        let series = hourly(0.0, &[1.0, 2.0, 3.0]);
+        assert_eq!(fill_gaps(&series, HOUR, 2.0 * HOUR, Fill::Zero), series);
+        // Points a little off the grid are not doubled
+        let jittered = [(0.0, 1.0), (HOUR + 60.0, 2.0), (2.0 * HOUR - 60.0, 3.0)];
+        assert_eq!(fill_gaps(&jittered, HOUR, 2.0 * HOUR, Fill::Zero), jittered);
+        assert!(fill_gaps(&[], HOUR, DAY, Fill::Zero).is_empty());
+    }
+
+    #[test]
+    fn bucket_starts() {
+        assert_eq!(Bucket::Hour.start(HOUR + 59.0), HOUR);
+        assert_eq!(Bucket::FourHours.start(4.0 * HOUR - 1.0), 0.0);
+        assert_eq!(Bucket::FourHours.start(4.0 * HOUR), 4.0 * HOUR);
+        assert_eq!(Bucket::Day.start(DAY + 23.0 * HOUR), DAY);
+        // 1970-01-01 was a Thursday, the week before started on Monday the 29th
+        assert_eq!(Bucket::Week.start(0.0), FIRST_MONDAY - 7.0 * DAY);
+        assert_eq!(Bucket::Week.start(FIRST_MONDAY), FIRST_MONDAY);
+        assert_eq!(
+            Bucket::Week.start(FIRST_MONDAY + 7.0 * DAY - 1.0),
+            FIRST_MONDAY
+        );
+    }
+
+    #[test]
+    fn resample_sums_at_bucket_edges() {
+        // Starting two hours into a bucket, so the first one is partial
+        let series = hourly(2.0 * HOUR, &[1.0; 8]);
+        assert_eq!(
+            resample(&series, Bucket::FourHours, Aggregate::Sum),
+            [(0.0, 2.0), (4.0 * HOUR, 4.0), (8.0 * HOUR, 2.0)]
+        );
+    }
+
+    #[test]
+    fn resample_keeps_last_level() {
+        let series = hourly(22.0 * HOUR, &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(
+            resample(&series, Bucket::Day, Aggregate::Last),
+            [(0.0, 2.0), (DAY, 4.0)]
+        );
+    }
+
+    #[test]
+    fn resample_weeks_from_monday() {
+        let series = [
+            (FIRST_MONDAY - 1.0, 1.0),
+            (FIRST_MONDAY, 2.0),
+            (FIRST_MONDAY + 6.0 * DAY, 3.0),
+        ];
+        assert_eq!(
+            resample(&series, Bucket::Week, Aggregate::Sum),
+            [(FIRST_MONDAY - 7.0 * DAY, 1.0), (FIRST_MONDAY, 5.0)]
+        );
+    }
+
+    #[test]
+    fn fitting_buckets() {
+        // A month of hours over 100 columns needs days, over 200 four hours
+        assert_eq!(Bucket::fitting(HOUR, 30.0 * DAY, 100), Bucket::Day);
+        assert_eq!(Bucket::fitting(HOUR, 30.0 * DAY, 200), Bucket::FourHours);
+        assert_eq!(Bucket::fitting(HOUR, DAY, 100), Bucket::Hour);
+        // Never finer than the snapshots
+        assert_eq!(Bucket::fitting(DAY, DAY, 100), Bucket::Day);
+        assert_eq!(Bucket::fitting(HOUR, 10_000.0 * DAY, 10), Bucket::Week);
+    }
+
+    #[test]
+    fn downsample_keeps_extremes_and_ends() {
+        let series: Vec<(f64, f64)> = (0..1000).map(|i| (i as f64, (i % 7) as f64)).collect();
+        let kept = downsample(&series, 10);
+        assert!(kept.len() <= 22);
+        assert_eq!(kept.first(), series.first());
+        assert_eq!(kept.last(), series.last());
+        assert!(kept.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        // Every slice of a hundred still reaches both extremes
+        for slice in kept.chunk_by(|a, b| (a.0 / 100.0) as usize == (b.0 / 100.0) as usize) {
+            assert!(slice.iter().any(|(_, value)| *value == 0.0));
+            assert!(slice.iter().any(|(_, value)| *value == 6.0));
+        }
+    }
+
+    #[test]
+    fn downsample_keeps_ends_between_extremes() {
+        // The ends are neither the lowest nor the highest of their slices
+        let series: Vec<(f64, f64)> = (0..100)
+            .map(|i| (i as f64, if i % 2 == 0 { 5.0 } else { (i % 10) as f64 }))
+            .collect();
+        let kept = downsample(&series, 5);
+        assert_eq!(kept.first(), Some(&(0.0, 5.0)));
+        assert_eq!(kept.last(), Some(&(99.0, 9.0)));
+        let kept = downsample(&hourly(0.0, &[3.0, 1.0, 9.0, 2.0, 4.0, 0.0, 8.0, 5.0]), 2);
+        assert_eq!(kept.first(), Some(&(0.0, 3.0)));
+        assert_eq!(kept.last(), Some(&(7.0 * HOUR, 5.0)));
+    }
+
+    #[test]
+    fn downsample_short_series_unchanged() {
+        let series = hourly(0.0, &[1.0, 2.0, 3.0]);
+        assert_eq!(downsample(&series, 2), series);
+        assert_eq!(downsample(&series, 0), series);
+        assert!(downsample(&[], 10).is_empty());
+    }
+}
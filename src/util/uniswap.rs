@@ -0,0 +1,148 @@
+use ethers::types::{U256, U512};
+
+/// 2^96, the fixed point scale of `sqrtPriceX96`
+const Q96: f64 = 79_228_162_514_264_337_593_543_950_336.0;
+
+/// The lowest and highest ticks a position can use
+pub const MIN_TICK: i32 = -887_272;
+pub const MAX_TICK: i32 = 887_272;
+
+/// Returns the raw price (token1 per token0, without decimals) at a tick
+pub fn tick_to_price(tick: i32) -> f64 {
+    1.0001f64.powi(tick)
+}
+
+/// Returns the tick at or below a raw price
+pub fn price_to_tick(raw_price: f64) -> i32 {
+    ((raw_price.ln() / 1.0001f64.ln()).floor() as i32).clamp(MIN_TICK, MAX_TICK)
+}
+
+/// Rounds `tick` to the nearest multiple of `spacing` a position can be bounded by
+pub fn nearest_usable_tick(tick: i32, spacing: i32) -> i32 {
+    let rounded = (tick as f64 / spacing as f64).round() as i32 * spacing;
+    if rounded < MIN_TICK {
+        rounded + spacing
+    } else if rounded > MAX_TICK {
+        rounded - spacing
+    } else {
+        rounded
+    }
+}
+
+/// Tick spacing the factory enables for a fee tier
+pub fn tick_spacing(fee_tier: u32) -> Option<i32> {
+    match fee_tier {
+        100 => Some(1),
+        500 => Some(10),
+        3000 => Some(60),
+        10000 => Some(200),
+        _ => None,
+    }
+}
+
+/// Returns the square root of the raw price at a tick
+pub fn tick_to_sqrt_price(tick: i32) -> f64 {
+    1.0001f64.powf(tick as f64 / 2.0)
+}
+
+/// Converts a subgraph `sqrtPrice` (Q64.96) into a floating point square root price
+pub fn sqrt_price_x96_to_sqrt_price(sqrt_price_x96: &str) -> Option<f64> {
+    sqrt_price_x96.parse::<f64>().ok().map(|p| p / Q96)
+}
+
+/// Human readable price of token0 denominated in token1
+pub fn adjusted_price(raw_price: f64, decimals0: u32, decimals1: u32) -> f64 {
+    raw_price * 10f64.powi(decimals0 as i32 - decimals1 as i32)
+}
+
+/// Raw token amounts held by `liquidity` over `[sqrt_a, sqrt_b]` at `sqrt_price`
+pub fn amounts_for_liquidity(
+    liquidity: f64,
+    sqrt_price: f64,
+    sqrt_a: f64,
+    sqrt_b: f64,
+) -> (f64, f64) {
+    let (sqrt_a, sqrt_b) = if sqrt_a > sqrt_b {
+        (sqrt_b, sqrt_a)
+    } else {
+        (sqrt_a, sqrt_b)
+    };
+
+    if sqrt_price <= sqrt_a {
+        (liquidity * (sqrt_b - sqrt_a) / (sqrt_a * sqrt_b), 0.0)
+    } else if sqrt_price < sqrt_b {
+        (
+            liquidity * (sqrt_b - sqrt_price) / (sqrt_price * sqrt_b),
+            liquidity * (sqrt_price - sqrt_a),
+        )
+    } else {
+        (0.0, liquidity * (sqrt_b - sqrt_a))
+    }
+}
+
+/// Rebuilds the liquidity active across a pool from its initialized ticks, given
+/// as `(tick, liquidity_net)`, and the liquidity active at `current_tick`.
+/// Returns `(from_tick, liquidity)` segments sorted by tick, each lasting until
+/// the next, the first starting at `MIN_TICK`.
+pub fn active_liquidity(
+    ticks: &[(i32, f64)],
+    current_tick: i32,
+    current_liquidity: f64,
+) -> Vec<(i32, f64)> {
+    let mut ticks = ticks.to_vec();
+    ticks.sort_by_key(|(tick, _)| *tick);
+    let split = ticks.partition_point(|(tick, _)| *tick <= current_tick);
+
+    // Crossing a tick downwards removes its net liquidity, upwards adds it
+    let mut below = Vec::with_capacity(split);
+    let mut liquidity = current_liquidity;
+    for &(tick, net) in ticks[..split].iter().rev() {
+        below.push((tick, liquidity));
+        liquidity -= net;
+    }
+    let mut segments = vec![(MIN_TICK, liquidity.max(0.0))];
+    segments.extend(below.into_iter().rev().map(|(tick, l)| (tick, l.max(0.0))));
+
+    let mut liquidity = current_liquidity;
+    for &(tick, net) in &ticks[split..] {
+        liquidity += net;
+        segments.push((tick, liquidity.max(0.0)));
+    }
+    segments
+}
+
+/// Fee growth inside a tick range, following the core contract's `getFeeGrowthInside`
+pub fn fee_growth_inside(
+    current_tick: i32,
+    tick_lower: i32,
+    tick_upper: i32,
+    global: U256,
+    lower_outside: U256,
+    upper_outside: U256,
+) -> U256 {
+    let below = if current_tick >= tick_lower {
+        lower_outside
+    } else {
+        global.overflowing_sub(lower_outside).0
+    };
+    let above = if current_tick < tick_upper {
+        upper_outside
+    } else {
+        global.overflowing_sub(upper_outside).0
+    };
+
+    global.overflowing_sub(below).0.overflowing_sub(above).0
+}
+
+/// Raw fees owed to `liquidity` since the fee growth checkpoint `inside_last`
+pub fn fees_owed(liquidity: U256, inside: U256, inside_last: U256) -> f64 {
+    let delta = inside.overflowing_sub(inside_last).0;
+    let owed: U512 = liquidity.full_mul(delta) >> 128;
+
+    owed.to_string().parse::<f64>().unwrap_or(0.0)
+}
+
+/// Parses a decimal subgraph `BigInt` string into a `U256`
+pub fn parse_u256(value: &str) -> Option<U256> {
+    U256::from_dec_str(value).ok()
+}
@@ -0,0 +1,72 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::{
+    keymap::{Action, Keymap},
+    routes::Route,
+    theme::Theme,
+};
+
+/// Draws the path through `routes` on one line, with the keys to go back and get help
+pub fn render_breadcrumbs(
+    frame: &mut Frame,
+    area: Rect,
+    routes: &[Route],
+    keymap: &Keymap,
+    theme: &Theme,
+) {
+    let mut crumbs = Vec::new();
+    for (i, route) in routes.iter().enumerate() {
+        if i > 0 {
+            crumbs.push(Span::styled(
+                " › ",
+                Style::default().fg(theme.text_secondary()),
+            ));
+        }
+        let style = if i + 1 == routes.len() {
+            Style::default()
+                .fg(theme.text_primary())
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.text_secondary())
+        };
+        crumbs.push(Span::styled(route.id().title(), style));
+    }
+
+    // The shortest key bound to `action`, e.g. `esc` rather than `backspace`
+    let key = |action: Action| {
+        keymap
+            .keys(action)
+            .iter()
+            .map(|k| k.to_string())
+            .min_by_key(|k| k.len())
+            .unwrap_or_default()
+    };
+    let mut hints = Vec::new();
+    if routes.len() > 1 {
+        hints.push(format!("{} back", key(Action::Back)));
+    }
+    hints.push(format!("{} help", key(Action::Help)));
+
+    let hint = hints.join(" · ");
+    let layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(hint.chars().count() as u16),
+        ])
+        .split(area);
+
+    frame.render_widget(Paragraph::new(Line::from(crumbs)), layout[0]);
+    frame.render_widget(
+        Paragraph::new(hint)
+            .style(Style::default().fg(theme.text_secondary()))
+            .alignment(Alignment::Right),
+        layout[1],
+    );
+}
@@ -0,0 +1,229 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Widget},
+    Frame,
+};
+
+use crate::{theme::Theme, util::format::format_amount, widgets::chart::format_time};
+
+/// A price's open, high, low and close over the period starting at `time`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candle {
+    pub time: f64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl Candle {
+    /// Parses a snapshot's OHLC fields, `None` if any is missing or invalid
+    pub fn parse(time: f64, [open, high, low, close]: [&Option<String>; 4]) -> Option<Candle> {
+        let parse = |value: &Option<String>| value.as_deref()?.parse::<f64>().ok();
+        Some(Candle {
+            time,
+            open: parse(open)?,
+            high: parse(high)?,
+            low: parse(low)?,
+            close: parse(close)?,
+        })
+    }
+
+    /// The candle of the inverse price, `1 / price`
+    pub fn inverted(self) -> Candle {
+        Candle {
+            time: self.time,
+            open: 1.0 / self.open,
+            high: 1.0 / self.low,
+            low: 1.0 / self.high,
+            close: 1.0 / self.close,
+        }
+    }
+
+    /// The candle over both `self` and the period right after it, `next`
+    fn merge(self, next: Candle) -> Candle {
+        Candle {
+            time: self.time,
+            open: self.open,
+            high: self.high.max(next.high),
+            low: self.low.min(next.low),
+            close: next.close,
+        }
+    }
+}
+
+/// Candles drawn as bodies from open to close with wicks to the high and low,
+/// merging neighbouring candles when there are more than columns
+pub struct Candlestick<'a> {
+    candles: &'a [Candle],
+    block: Option<Block<'a>>,
+    rising_style: Style,
+    falling_style: Style,
+    label_style: Style,
+}
+
+impl<'a> Candlestick<'a> {
+    pub fn new(candles: &'a [Candle]) -> Self {
+        Self {
+            candles,
+            block: None,
+            rising_style: Style::default(),
+            falling_style: Style::default(),
+            label_style: Style::default(),
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// Style of candles closing at or above their open
+    pub fn rising_style(mut self, style: Style) -> Self {
+        self.rising_style = style;
+        self
+    }
+
+    /// Style of candles closing below their open
+    pub fn falling_style(mut self, style: Style) -> Self {
+        self.falling_style = style;
+        self
+    }
+
+    /// Style of the price labels on the left
+    pub fn label_style(mut self, style: Style) -> Self {
+        self.label_style = style;
+        self
+    }
+}
+
+impl Widget for Candlestick<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = match &self.block {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.clone().render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        if self.candles.is_empty() || inner.height < 2 {
+            return;
+        }
+
+        let low = self
+            .candles
+            .iter()
+            .map(|c| c.low)
+            .fold(f64::INFINITY, f64::min);
+        let high = self
+            .candles
+            .iter()
+            .map(|c| c.high)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let (low, high) = if low < high {
+            (low, high)
+        } else {
+            (low * 0.95, high * 1.05 + 0.01)
+        };
+
+        // Prices at the top, middle and bottom of the plot on the left
+        let labels = [high, (low + high) / 2.0, low].map(format_amount);
+        let label_width = labels.iter().map(|l| l.len()).max().unwrap_or(0) as u16 + 1;
+        if inner.width <= label_width {
+            return;
+        }
+        let rows = [
+            inner.top(),
+            inner.top() + (inner.height - 1) / 2,
+            inner.bottom() - 1,
+        ];
+        for (label, y) in labels.iter().zip(rows) {
+            buf.set_string(inner.x, y, label, self.label_style);
+        }
+        let plot = Rect {
+            x: inner.x + label_width,
+            width: inner.width - label_width,
+            ..inner
+        };
+
+        let per_column = self.candles.len().div_ceil(plot.width as usize);
+        let candles: Vec<Candle> = self
+            .candles
+            .chunks(per_column)
+            .filter_map(|chunk| chunk.iter().copied().reduce(Candle::merge))
+            .collect();
+        let slot = plot.width / candles.len() as u16;
+        let body_width = slot.saturating_sub(1).max(1);
+        let row = |price: f64| {
+            let from_bottom = (price - low) / (high - low) * (plot.height - 1) as f64;
+            plot.bottom() - 1 - from_bottom.round().clamp(0.0, (plot.height - 1) as f64) as u16
+        };
+
+        for (i, candle) in candles.iter().enumerate() {
+            let x = plot.x + i as u16 * slot;
+            let style = if candle.close >= candle.open {
+                self.rising_style
+            } else {
+                self.falling_style
+            };
+            let wick_x = x + body_width / 2;
+            for y in row(candle.high)..=row(candle.low) {
+                buf.set_string(wick_x, y, "│", style);
+            }
+            let (top, bottom) = (
+                row(candle.open.max(candle.close)),
+                row(candle.open.min(candle.close)),
+            );
+            let body = if top == bottom && candle.open != candle.close {
+                "▬"
+            } else if top == bottom {
+                "─"
+            } else {
+                "█"
+            };
+            for y in top..=bottom {
+                for x in x..x + body_width {
+                    buf.set_string(x, y, body, style);
+                }
+            }
+        }
+    }
+}
+
+/// Draws `candles` titled `title`, with the dates they span below
+pub fn render_candle_chart(
+    f: &mut Frame,
+    area: Rect,
+    candles: &[Candle],
+    title: &str,
+    is_hourly: bool,
+    theme: &Theme,
+) {
+    let date = |time: f64| format_time(time, is_hourly);
+    let mut block = Block::default()
+        .title(Span::styled(
+            title.to_string(),
+            Style::default()
+                .fg(theme.text_primary())
+                .add_modifier(Modifier::BOLD),
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_primary()));
+    if let (Some(first), Some(last)) = (candles.first(), candles.last()) {
+        block = block
+            .title_bottom(Line::from(date(first.time)).left_aligned())
+            .title_bottom(Line::from(date(last.time)).right_aligned());
+    }
+    f.render_widget(
+        Candlestick::new(candles)
+            .block(block)
+            .rising_style(Style::default().fg(theme.profit()))
+            .falling_style(Style::default().fg(theme.loss()))
+            .label_style(Style::default().fg(theme.border_axis())),
+        area,
+    );
+}
@@ -1,22 +1,60 @@
 use ratatui::{
+    layout::{Constraint, Direction, Layout, Position, Rect},
     style::{Color, Modifier, Style},
     symbols,
-    text::Span,
-    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType},
+    text::{Line, Span},
+    widgets::{Axis, Block, Borders, Chart, Clear, Dataset, GraphType, Paragraph, Wrap},
+    Frame,
 };
 
-use crate::models::event_handling::SinSignal;
+use crate::{
+    app::{ChartState, ChartTimeRange, ChartView, Indicator, PriceScale},
+    models::{
+        event_handling::SinSignal,
+        position::{self, Pool},
+    },
+    theme::Theme,
+    util::{
+        format::{format_amount, format_axis, format_usd},
+        indicators::{bollinger, ema, realized_volatility, sma, HOURS_PER_YEAR},
+        pool_series::{first_price, on_scale, scaled, share_in_range, value_at, PoolSeries},
+        series::{self, resample, Aggregate, Bucket},
+        uniswap::MAX_TICK,
+    },
+    widgets::candlestick::{render_candle_chart, Candle},
+};
 use chrono::{NaiveDateTime, TimeZone, Utc};
 
+/// A named line in a color, e.g. a moving average
+pub type Overlay = (String, Color, Vec<(f64, f64)>);
+
 pub struct TokenChart {
     pub token0_prices: Vec<(f64, f64)>,
     pub token1_prices: Vec<(f64, f64)>,
     pub token0_ticker: String,
     pub token1_ticker: String,
+    /// Times at the left and right edges, `[0.0, 0.0]` to fit the data
     pub window: [f64; 2],
     pub max_price: f64,
     pub min_price: f64,
     pub is_hourly: bool,
+    /// Lines drawn over the prices, e.g. moving averages, with their colors
+    pub overlays: Vec<Overlay>,
+    /// Horizontal lines across the window drawn over the prices, e.g. a range's bounds
+    pub bounds: Vec<(&'static str, [(f64, f64); 2])>,
+    /// Points marked with a dot, e.g. where a position was opened
+    pub markers: Vec<[(f64, f64); 1]>,
+    /// A vertical line at the time under the chart cursor
+    pub cursor: Option<[(f64, f64); 2]>,
+    /// How the series are drawn, bars for per-period amounts like volume
+    pub graph_type: GraphType,
+    pub title: String,
+    pub y_title: &'static str,
+    /// Formats the values on the y axis, e.g. to undo a log scale
+    pub y_format: fn(f64) -> String,
+    /// The scale and offset taking the y axis to token1's own prices when
+    /// they are rescaled onto token0's, labelled on the right
+    pub right_axis: Option<(f64, f64)>,
 }
 
 impl TokenChart {
@@ -30,6 +68,15 @@ impl TokenChart {
             max_price: 0.0,
             min_price: 0.0,
             is_hourly: false,
+            overlays: Vec::new(),
+            bounds: Vec::new(),
+            markers: Vec::new(),
+            cursor: None,
+            graph_type: GraphType::Line,
+            title: "Token Prices (USD)".to_string(),
+            y_title: "Price (USD)",
+            y_format: format_axis,
+            right_axis: None,
         }
     }
 
@@ -40,129 +87,1066 @@ impl TokenChart {
     ) {
         self.token0_prices = token0_data.to_vec();
         self.token1_prices = token1_data.to_vec();
-        // Calculate window bounds
-        if !self.token0_prices.is_empty() {
+        // The window is the viewport when set, otherwise it spans the data
+        if self.window == [0.0, 0.0] && !self.token0_prices.is_empty() {
             self.window = [
                 self.token0_prices.first().map(|(x, _)| *x).unwrap_or(0.0),
                 self.token0_prices.last().map(|(x, _)| *x).unwrap_or(0.0),
             ];
         }
-        // Find min/max price for y-axis scaling
+        // Find min/max price of the points in the window for y-axis scaling
         let all_prices = self
             .token0_prices
             .iter()
             .chain(self.token1_prices.iter())
-            .map(|(_, y)| *y)
+            .copied()
             .collect::<Vec<_>>();
-        let min_price = all_prices.iter().cloned().fold(f64::INFINITY, f64::min);
-        let max_price = all_prices.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        [self.min_price, self.max_price] = self.extent(&all_prices);
+    }
+
+    /// Plots token1's prices rescaled to span the same height as token0's,
+    /// so both move visibly however far apart they are, with their own axis
+    /// on the right
+    pub fn update_with_dual_axes(
+        &mut self,
+        token0_data: &[(f64, f64)],
+        token1_data: &[(f64, f64)],
+    ) {
+        self.update_with_price_data(token0_data, &[]);
+        let [min1, max1] = self.extent(token1_data);
+        if min1 > max1 {
+            return;
+        }
+        let (min0, max0) = (self.min_price, self.max_price);
+        self.token1_prices = token1_data
+            .iter()
+            .map(|(x, y)| (*x, min0 + (y - min1) / (max1 - min1) * (max0 - min0)))
+            .collect();
+        let scale = (max1 - min1) / (max0 - min0);
+        self.right_axis = Some((scale, min1 - min0 * scale));
+    }
+
+    /// Where a price of token1 is plotted, rescaled when it has its own axis
+    pub fn token1_y(&self, price: f64) -> f64 {
+        match self.right_axis {
+            Some((scale, offset)) => (price - offset) / scale,
+            None => price,
+        }
+    }
+
+    /// The lowest and highest of `points` in the window, a little apart if they
+    /// are the same
+    fn extent(&self, points: &[(f64, f64)]) -> [f64; 2] {
+        let [from, to] = self.window;
+        let prices = points
+            .iter()
+            .filter(|(x, _)| from <= *x && *x <= to)
+            .map(|(_, y)| *y);
+        let min_price = prices.clone().fold(f64::INFINITY, f64::min);
+        let max_price = prices.fold(f64::NEG_INFINITY, f64::max);
         // If all prices are the same, add a small buffer
-        let (min_price, max_price) = if min_price == max_price {
-            (min_price * 0.95, max_price * 1.05 + 0.01)
+        if min_price == max_price {
+            [min_price * 0.95, max_price * 1.05 + 0.01]
         } else {
-            (min_price, max_price)
-        };
-        self.max_price = max_price;
-        self.min_price = min_price;
+            [min_price, max_price]
+        }
+    }
+
+    /// Draws `points` as a line named `name`, widening the y axis to fit the
+    /// ones in the window
+    pub fn add_overlay(&mut self, name: String, color: Color, points: Vec<(f64, f64)>) {
+        let [from, to] = self.window;
+        if points.iter().any(|(x, _)| from <= *x && *x <= to) {
+            let [min, max] = self.extent(&points);
+            self.min_price = self.min_price.min(min);
+            self.max_price = self.max_price.max(max);
+        }
+        self.overlays.push((name, color, points));
+    }
+
+    /// Draws a line named `name` at `price` across the current window,
+    /// widening the y axis to fit it
+    pub fn add_bound(&mut self, name: &'static str, price: f64) {
+        self.bounds
+            .push((name, [(self.window[0], price), (self.window[1], price)]));
+        self.min_price = self.min_price.min(price);
+        self.max_price = self.max_price.max(price);
+    }
+
+    /// Keeps only the points of each series that show at the braille
+    /// resolution of the plot over `area`, so long ranges draw quickly
+    pub fn downsample(&mut self, area: Rect) {
+        let dots = self.plot_area(area).width as usize * 2;
+        self.token0_prices = series::downsample(&self.token0_prices, dots);
+        self.token1_prices = series::downsample(&self.token1_prices, dots);
+    }
+
+    /// Marks the point at `time` and `price` with a dot
+    pub fn add_marker(&mut self, time: f64, price: f64) {
+        self.markers.push([(time, price)]);
+    }
+
+    /// Draws a vertical line at `time`, the chart cursor
+    pub fn set_cursor(&mut self, time: f64) {
+        self.cursor = Some([(time, self.min_price), (time, self.max_price)]);
+    }
+
+    /// Dates at the start, middle and end of the window
+    fn x_labels(&self) -> Vec<String> {
+        if self.token0_prices.is_empty() {
+            return vec![];
+        }
+        let mid = (self.window[0] + self.window[1]) / 2.0;
+        [self.window[0], mid, self.window[1]]
+            .into_iter()
+            .map(|time| format_time(time, self.is_hourly))
+            .collect()
+    }
+
+    /// Prices at the bottom, middle and top of the y axis
+    fn y_labels(&self) -> Vec<String> {
+        if self.max_price > 0.0 || self.min_price < 0.0 {
+            let (min, max) = (self.min_price, self.max_price);
+            [min, (min + max) / 2.0, max]
+                .into_iter()
+                .map(self.y_format)
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Token1's prices at the bottom, middle and top of the right axis
+    fn right_labels(&self) -> Vec<String> {
+        self.right_axis.map_or(vec![], |(scale, offset)| {
+            let (min, max) = (self.min_price, self.max_price);
+            [min, (min + max) / 2.0, max]
+                .into_iter()
+                .map(|y| (self.y_format)(y * scale + offset))
+                .collect()
+        })
+    }
+
+    /// Columns taken by the right axis, its labels and a space on either side
+    fn right_width(&self) -> u16 {
+        match self.right_labels().iter().map(|l| l.len()).max() {
+            Some(width) => width as u16 + 2,
+            None => 0,
+        }
+    }
+
+    /// Where `render_token_chart` plots the data inside `area`, following the
+    /// layout of ratatui's `Chart`: the y labels, or the part of the first x
+    /// label left of the y axis, and the axis on the left and the x labels and
+    /// axis below. The right axis, if any, is right of it.
+    pub fn plot_area(&self, area: Rect) -> Rect {
+        let mut inner = Block::default().borders(Borders::ALL).inner(area);
+        inner.width = inner.width.saturating_sub(self.right_width());
+        let y_labels = self.y_labels().iter().map(|l| l.len()).max().unwrap_or(0) as u16;
+        let first_x_label = self
+            .x_labels()
+            .first()
+            .map_or(0, |l| l.len().saturating_sub(1)) as u16;
+        let left = y_labels.max(first_x_label).min(inner.width / 3) + 1;
+        Rect {
+            x: inner.x + left,
+            y: inner.y,
+            width: inner.width.saturating_sub(left),
+            height: inner.height.saturating_sub(2),
+        }
     }
 }
 
-pub fn render_volume_chart<'a>(token_chart: &TokenChart) -> Chart {
-    let x_labels = if !token_chart.token0_prices.is_empty() {
-        let start = token_chart.window[0] as i64;
-        let mid = ((token_chart.window[0] + token_chart.window[1]) / 2.0) as i64;
-        let end = token_chart.window[1] as i64;
-        let fmt = if token_chart.is_hourly {
-            "%Y-%m-%d %H:%M"
+/// `time` as a date, with the hour on hourly charts
+pub fn format_time(time: f64, is_hourly: bool) -> String {
+    let fmt = if is_hourly {
+        "%Y-%m-%d %H:%M"
+    } else {
+        "%Y-%m-%d"
+    };
+    Utc.timestamp_opt(time as i64, 0)
+        .single()
+        .map(|dt| dt.format(fmt).to_string())
+        .unwrap_or_else(|| (time as i64).to_string())
+}
+
+/// A crosshair over the chart, snapped to the point nearest to where it was put
+#[derive(Debug, Clone, Default)]
+pub struct ChartCursor {
+    /// Time of the point under the crosshair, `None` while hidden
+    time: Option<f64>,
+    /// Times of the points last drawn, oldest first
+    times: Vec<f64>,
+    /// Where the points were last plotted, `None` when no chart is on screen
+    plot: Option<Rect>,
+    /// Times at the left and right edges of the plot
+    window: [f64; 2],
+}
+
+impl ChartCursor {
+    pub fn time(&self) -> Option<f64> {
+        self.time
+    }
+
+    /// Whether the crosshair is over a chart on screen
+    pub fn is_shown(&self) -> bool {
+        self.time.is_some() && self.plot.is_some()
+    }
+
+    pub fn hide(&mut self) {
+        self.time = None;
+    }
+
+    /// Moves the crosshair `by` points, showing it on the latest point first
+    pub fn step(&mut self, by: isize) {
+        let Some(last) = self.times.len().checked_sub(1) else {
+            return;
+        };
+        let index = match self.index() {
+            Some(i) => i.saturating_add_signed(by).min(last),
+            None => last,
+        };
+        self.time = Some(self.times[index]);
+    }
+
+    /// Puts the crosshair on the point nearest to the `column` of the
+    /// terminal, returning whether `(column, row)` is over the plot
+    pub fn point_at(&mut self, column: u16, row: u16) -> bool {
+        match self.plot {
+            Some(plot) if plot.contains(Position::new(column, row)) => {
+                let fraction =
+                    (column - plot.x) as f64 / plot.width.saturating_sub(1).max(1) as f64;
+                self.time = Some(self.window[0] + fraction * (self.window[1] - self.window[0]));
+                self.snap();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Records that `chart` is drawn over `area`, adding the crosshair to it if shown
+    pub fn place(&mut self, chart: &mut TokenChart, area: Rect) {
+        self.plot = Some(chart.plot_area(area));
+        self.window = chart.window;
+        self.times = chart.token0_prices.iter().map(|(time, _)| *time).collect();
+        self.snap();
+        if let Some(time) = self.time {
+            chart.set_cursor(time);
+        }
+    }
+
+    /// Forgets the last chart, to be called before every draw so the mouse
+    /// only moves the crosshair while a chart is on screen
+    pub fn undrawn(&mut self) {
+        self.plot = None;
+        self.times.clear();
+    }
+
+    /// Where the points were plotted in the last draw
+    pub fn plot(&self) -> Option<Rect> {
+        self.plot
+    }
+
+    /// Column of the crosshair, if shown over the plot
+    pub fn column(&self) -> Option<u16> {
+        let (plot, time) = (self.plot?, self.time?);
+        let span = self.window[1] - self.window[0];
+        let fraction = if span > 0.0 {
+            (time - self.window[0]) / span
         } else {
-            "%Y-%m-%d"
+            0.0
         };
-        vec![
-            Span::styled(
-                format!(
-                    "{}",
-                    Utc.timestamp_opt(start, 0)
-                        .single()
-                        .map(|dt| dt.format(fmt).to_string())
-                        .unwrap_or_else(|| start.to_string())
-                ),
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-            Span::raw(
-                Utc.timestamp_opt(mid, 0)
-                    .single()
-                    .map(|dt| dt.format(fmt).to_string())
-                    .unwrap_or_else(|| mid.to_string()),
-            ),
-            Span::styled(
-                format!(
-                    "{}",
-                    Utc.timestamp_opt(end, 0)
-                        .single()
-                        .map(|dt| dt.format(fmt).to_string())
-                        .unwrap_or_else(|| end.to_string())
-                ),
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-        ]
+        Some(plot.x + (fraction * plot.width.saturating_sub(1) as f64).round() as u16)
+    }
+
+    /// Index of the point nearest to the crosshair
+    fn index(&self) -> Option<usize> {
+        let time = self.time?;
+        let after = self.times.partition_point(|t| *t < time);
+        [after.checked_sub(1), Some(after)]
+            .into_iter()
+            .flatten()
+            .filter(|i| *i < self.times.len())
+            .min_by(|a, b| {
+                (self.times[*a] - time)
+                    .abs()
+                    .total_cmp(&(self.times[*b] - time).abs())
+            })
+    }
+
+    fn snap(&mut self) {
+        if let Some(i) = self.index() {
+            self.time = Some(self.times[i]);
+        }
+    }
+}
+
+/// Shades the columns of `token_chart`'s plot over `area` at times for which
+/// `shaded` is true, e.g. when a position was out of range
+pub fn shade_plot(
+    f: &mut Frame,
+    area: Rect,
+    token_chart: &TokenChart,
+    shaded: impl Fn(f64) -> bool,
+    color: Color,
+) {
+    let plot = token_chart.plot_area(area);
+    let [from, to] = token_chart.window;
+    let last = plot.width.saturating_sub(1).max(1) as f64;
+    let buf = f.buffer_mut();
+    for column in plot.left()..plot.right() {
+        let time = from + (column - plot.x) as f64 / last * (to - from);
+        if !shaded(time) {
+            continue;
+        }
+        for row in plot.top()..plot.bottom() {
+            if let Some(cell) = buf.cell_mut(Position::new(column, row)) {
+                cell.set_bg(color);
+            }
+        }
+    }
+}
+
+/// Draws `lines` about the point under the crosshair in a box at the top of
+/// `plot`, on the side away from the crosshair at `column`
+pub fn render_cursor_tooltip(
+    f: &mut Frame,
+    plot: Rect,
+    column: u16,
+    lines: Vec<Line>,
+    theme: &Theme,
+) {
+    let width = (lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2).min(plot.width);
+    let height = (lines.len() as u16 + 2).min(plot.height);
+    let x = if column < plot.x + plot.width / 2 {
+        plot.right().saturating_sub(width)
     } else {
-        vec![]
+        plot.x
     };
-    let y_labels = if token_chart.max_price > 0.0 || token_chart.min_price < 0.0 {
-        let min = token_chart.min_price;
-        let max = token_chart.max_price;
-        let mid = (min + max) / 2.0;
-        vec![
-            Span::styled(
-                format!("{:.2}", min),
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-            Span::raw(format!("{:.2}", mid)),
-            Span::styled(
-                format!("{:.2}", max),
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-        ]
-    } else {
-        vec![]
+    let area = Rect::new(x, plot.y, width, height);
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border_secondary())),
+        ),
+        area,
+    );
+}
+
+pub fn render_volume_chart<'a>(token_chart: &'a TokenChart, theme: &Theme) -> Chart<'a> {
+    // The first and last labels are bold
+    let styled = |labels: Vec<String>| {
+        let last = labels.len().saturating_sub(1);
+        labels
+            .into_iter()
+            .enumerate()
+            .map(|(i, label)| {
+                if i == 0 || i == last {
+                    Span::styled(label, Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    Span::raw(label)
+                }
+            })
+            .collect::<Vec<_>>()
     };
-    let datasets = vec![
+    let x_labels = styled(token_chart.x_labels());
+    let y_labels = styled(token_chart.y_labels());
+    let mut datasets = vec![
         Dataset::default()
             .name(token_chart.token0_ticker.as_str())
             .marker(symbols::Marker::Braille)
-            .graph_type(GraphType::Line)
-            .style(Style::default().fg(Color::Green))
+            .graph_type(token_chart.graph_type)
+            .style(Style::default().fg(theme.series_primary()))
             .data(&token_chart.token0_prices),
         Dataset::default()
             .name(token_chart.token1_ticker.as_str())
             .marker(symbols::Marker::Braille)
-            .graph_type(GraphType::Line)
-            .style(Style::default().fg(Color::Yellow))
+            .graph_type(token_chart.graph_type)
+            .style(Style::default().fg(theme.series_secondary()))
             .data(&token_chart.token1_prices),
     ];
+    datasets.extend(token_chart.overlays.iter().map(|(name, color, line)| {
+        Dataset::default()
+            .name(name.as_str())
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(*color))
+            .data(line)
+    }));
+    datasets.extend(token_chart.bounds.iter().map(|(name, line)| {
+        Dataset::default()
+            .name(*name)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(theme.highlight_focused()))
+            .data(line)
+    }));
+    datasets.extend(token_chart.markers.iter().map(|point| {
+        Dataset::default()
+            .marker(symbols::Marker::Dot)
+            .graph_type(GraphType::Scatter)
+            .style(Style::default().fg(theme.text_primary()))
+            .data(point)
+    }));
+    if let Some(line) = &token_chart.cursor {
+        datasets.push(
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(theme.text_secondary()))
+                .data(line),
+        );
+    }
     Chart::new(datasets)
         .block(
             Block::default()
                 .title(Span::styled(
-                    "Token Prices (USD)",
+                    token_chart.title.as_str(),
                     Style::default()
-                        .fg(Color::LightGreen)
+                        .fg(theme.text_primary())
                         .add_modifier(Modifier::BOLD),
                 ))
-                .borders(Borders::ALL),
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border_primary())),
         )
         .x_axis(
             Axis::default()
                 .title("Time")
-                .style(Style::default().fg(Color::Gray))
+                .style(Style::default().fg(theme.border_axis()))
                 .labels(x_labels)
                 .bounds(token_chart.window),
         )
         .y_axis(
             Axis::default()
-                .title("Price (USD)")
-                .style(Style::default().fg(Color::Gray))
+                .title(token_chart.y_title)
+                .style(Style::default().fg(theme.border_axis()))
                 .labels(y_labels)
                 .bounds([token_chart.min_price, token_chart.max_price]),
         )
 }
+
+/// Draws `token_chart` over `area` like `render_volume_chart`, with the right
+/// axis inside the same border when token1 has one
+pub fn render_token_chart(f: &mut Frame, area: Rect, token_chart: &TokenChart, theme: &Theme) {
+    let chart = render_volume_chart(token_chart, theme);
+    let right_width = token_chart.right_width();
+    if right_width == 0 {
+        f.render_widget(chart, area);
+        return;
+    }
+
+    // The chart's border is left open on the right and closed around the axis
+    let [chart_area, axis_area] =
+        Layout::horizontal([Constraint::Min(0), Constraint::Length(right_width + 1)]).areas(area);
+    let border_style = Style::default().fg(theme.border_primary());
+    f.render_widget(
+        chart.block(
+            Block::default()
+                .title(Span::styled(
+                    token_chart.title.as_str(),
+                    Style::default()
+                        .fg(theme.text_primary())
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::TOP | Borders::LEFT | Borders::BOTTOM)
+                .border_style(border_style),
+        ),
+        chart_area,
+    );
+    f.render_widget(
+        Block::default()
+            .borders(Borders::TOP | Borders::RIGHT | Borders::BOTTOM)
+            .border_style(border_style),
+        axis_area,
+    );
+
+    // Labelled at the same rows as the left axis, bottom first
+    let plot = token_chart.plot_area(area);
+    let bottom = plot.bottom().saturating_sub(1);
+    let rows = [
+        bottom,
+        bottom - plot.height.saturating_sub(1) / 2,
+        plot.top(),
+    ];
+    let style = Style::default().fg(theme.series_secondary());
+    for (label, row) in token_chart.right_labels().iter().zip(rows) {
+        f.buffer_mut()
+            .set_string(axis_area.x + 1, row, label, style);
+    }
+}
+
+/// A position's range and entry drawn over the price chart of its pool, in
+/// prices of token0 in token1
+pub struct RangeOverlay {
+    /// The lower bound, `None` at the lowest usable tick
+    pub lower: Option<f64>,
+    /// The upper bound, `None` at the highest usable tick
+    pub upper: Option<f64>,
+    /// When the position was minted
+    pub opened_at: Option<f64>,
+    /// The price at the mint, if known from the deposits
+    pub entry_price: Option<f64>,
+}
+
+impl RangeOverlay {
+    pub fn new(pos: &position::Position) -> Option<RangeOverlay> {
+        let (lower, upper) = pos.price_range()?;
+        let (tick_lower, tick_upper) = pos.tick_range()?;
+        // Full range positions are bounded by the usable ticks nearest to the
+        // limits, at most the widest tick spacing (200) away
+        let bounded = |tick: i32| tick.abs() < MAX_TICK - 200;
+        Some(RangeOverlay {
+            lower: Some(lower).filter(|_| bounded(tick_lower)),
+            upper: Some(upper).filter(|_| bounded(tick_upper)),
+            opened_at: pos.opened_at().map(|t| t.timestamp() as f64),
+            entry_price: pos.entry_price(),
+        })
+    }
+
+    pub fn contains(&self, price: f64) -> bool {
+        self.lower.is_none_or(|lower| price >= lower)
+            && self.upper.is_none_or(|upper| price < upper)
+    }
+}
+
+/// Candles of `pool`'s token0 price over `window`, `None` when there are none
+/// or a snapshot has no OHLC so the line chart is drawn instead
+fn pool_candles(pool: &Pool, is_hourly: bool, [from, to]: [f64; 2]) -> Option<Vec<Candle>> {
+    let mut candles = if is_hourly {
+        pool.pool_hour_data
+            .iter()
+            .filter(|d| from <= d.period_start_unix && d.period_start_unix <= to)
+            .map(|d| Candle::parse(d.period_start_unix, [&d.open, &d.high, &d.low, &d.close]))
+            .collect::<Option<Vec<_>>>()?
+    } else {
+        pool.pool_day_datas
+            .iter()
+            .filter(|d| from <= d.date && d.date <= to)
+            .map(|d| Candle::parse(d.date, [&d.open, &d.high, &d.low, &d.close]))
+            .collect::<Option<Vec<_>>>()?
+    };
+    candles.sort_by(|a, b| a.time.total_cmp(&b.time));
+    (!candles.is_empty()).then_some(candles)
+}
+
+/// Draws the realized volatility of `pool`'s hourly prices over `window`,
+/// measured over `hours` hourly returns
+fn render_volatility(
+    frame: &mut Frame,
+    area: Rect,
+    pool: &Pool,
+    [from, to]: [f64; 2],
+    hours: usize,
+    theme: &Theme,
+) {
+    let mut prices: Vec<(f64, f64)> = pool
+        .pool_hour_data
+        .iter()
+        .filter_map(|d| Some((d.period_start_unix, d.token1_price.as_ref()?.parse().ok()?)))
+        .collect();
+    prices.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let volatility: Vec<_> = realized_volatility(&prices, hours, HOURS_PER_YEAR)
+        .into_iter()
+        .filter(|(time, _)| from <= *time && *time <= to)
+        .collect();
+    let title = format!("Realized volatility ({}h, annualized)", hours);
+    if volatility.is_empty() {
+        let message = Paragraph::new("Not enough hourly prices in the range shown")
+            .style(Style::default().fg(theme.text_secondary()))
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.border_primary())),
+            );
+        frame.render_widget(message, area);
+        return;
+    }
+
+    let mut chart = TokenChart::new();
+    chart.token0_ticker = format!("{}h", hours);
+    chart.is_hourly = true;
+    chart.window = [from, to];
+    chart.update_with_price_data(&volatility, &[]);
+    chart.min_price = 0.0;
+    chart.title = title;
+    chart.y_title = "%";
+    chart.y_format = |y| format!("{:.0}%", y);
+    render_token_chart(frame, area, &chart, theme);
+}
+
+/// Volume in USD of `pool` over the snapshot starting at `time`
+fn volume_at(pool: &Pool, is_hourly: bool, time: f64) -> Option<f64> {
+    if is_hourly {
+        let hour = pool
+            .pool_hour_data
+            .iter()
+            .find(|d| d.period_start_unix == time)?;
+        hour.volume_usd.parse().ok()
+    } else {
+        let day = pool.pool_day_datas.iter().find(|d| d.date == time)?;
+        day.volume_usd.as_deref()?.parse().ok()
+    }
+}
+
+/// What a pool's chart is drawn from
+struct PoolChart<'a> {
+    pool: &'a Pool,
+    series: PoolSeries,
+    /// Tickers of the tokens shown first and second
+    tickers: (&'a str, &'a str),
+    overlay: Option<&'a RangeOverlay>,
+    theme: &'a Theme,
+}
+
+impl PoolChart<'_> {
+    /// Percentage of the prices shown inside the overlay's range
+    fn in_range_share(&self) -> Option<f64> {
+        let overlay = self.overlay?;
+        share_in_range(self.series.range_prices(), |price| overlay.contains(price))
+    }
+
+    /// The prices on the scale `chart_state` picks, with the overlay's lines and
+    /// the indicators over them
+    fn price_chart(&self, chart_state: &ChartState) -> TokenChart {
+        let series = &self.series;
+        let scale = chart_state.scale;
+        let mut chart = self.token_chart();
+        match scale {
+            PriceScale::Shared => chart.update_with_price_data(&series.token0, &series.token1),
+            PriceScale::Dual => chart.update_with_dual_axes(&series.token0, &series.token1),
+            PriceScale::Log | PriceScale::Percent => chart.update_with_price_data(
+                &scaled(scale, &series.token0),
+                &scaled(scale, &series.token1),
+            ),
+        }
+        match scale {
+            PriceScale::Log => chart.y_format = |y| format_axis(10f64.powf(y)),
+            PriceScale::Percent => {
+                chart.y_format = |y| format!("{:+.2}%", y);
+                chart.y_title = "Change";
+            }
+            PriceScale::Shared | PriceScale::Dual => {}
+        }
+        if scale != PriceScale::Shared {
+            chart.title = format!("Token Prices ({})", scale.as_str());
+        }
+
+        if let Some(overlay) = self.overlay {
+            let first = first_price(series.range_prices());
+            let to_axis = |price: f64| {
+                let y = on_scale(scale, price, first)?;
+                Some(if series.inverted {
+                    y
+                } else {
+                    chart.token1_y(y)
+                })
+            };
+            // Without the deposits to go by, the price of the snapshot the mint was in
+            let entry = overlay
+                .entry_price
+                .or_else(|| series.token1_at(overlay.opened_at?));
+            let lines: Vec<_> = [
+                ("Upper", overlay.upper),
+                ("Lower", overlay.lower),
+                ("Entry", entry),
+            ]
+            .into_iter()
+            .filter_map(|(name, price)| Some((name, to_axis(price?)?)))
+            .collect();
+            let [from, to] = series.window;
+            let marker = overlay
+                .opened_at
+                .filter(|time| from <= *time && *time <= to)
+                .zip(entry.and_then(to_axis));
+            for (name, y) in lines {
+                chart.add_bound(name, y);
+            }
+            if let Some((time, y)) = marker {
+                chart.add_marker(time, y);
+            }
+        }
+
+        let first = first_price(&series.token0);
+        let [from, to] = series.window;
+        for (name, color, line) in indicators(chart_state, &series.first_prices(), self.theme) {
+            let line = line
+                .into_iter()
+                .filter(|(time, _)| from <= *time && *time <= to)
+                .filter_map(|(time, price)| Some((time, on_scale(scale, price, first)?)))
+                .collect();
+            chart.add_overlay(name, color, line);
+        }
+        if let Some(share) = self.in_range_share() {
+            chart.title = format!("{} | {:.0}% in range", chart.title, share);
+        }
+        chart
+    }
+
+    /// A chart over the series' window, yet to be given its data
+    fn token_chart(&self) -> TokenChart {
+        let mut chart = TokenChart::new();
+        chart.token0_ticker = self.tickers.0.to_string();
+        chart.token1_ticker = self.tickers.1.to_string();
+        chart.is_hourly = self.series.is_hourly;
+        chart.window = self.series.window;
+        chart
+    }
+
+    /// Draws the prices as a line or candles, with the volatility below them if on
+    fn render_prices(&self, frame: &mut Frame, area: Rect, chart_state: &mut ChartState) {
+        let theme = self.theme;
+        let (pool, series) = (self.pool, &self.series);
+        if series.stale {
+            let warning = Paragraph::new(vec![Line::from(Span::raw(
+                "Warning: Data may be outdated or not recent!",
+            ))])
+            .style(Style::default().fg(theme.loss()))
+            .block(Block::default().title("Data Warning").borders(Borders::ALL))
+            .wrap(Wrap { trim: true });
+            frame.render_widget(warning, area);
+            return;
+        }
+
+        let mut price_area = area;
+        if chart_state.volatility {
+            let [prices_area, volatility_area] =
+                Layout::vertical([Constraint::Min(10), Constraint::Length(8)]).areas(area);
+            let hours = chart_state.periods.volatility;
+            render_volatility(frame, volatility_area, pool, series.window, hours, theme);
+            price_area = prices_area;
+        }
+        let candles = match chart_state.view {
+            ChartView::Candles => {
+                pool_candles(pool, series.is_hourly, series.window).map(|candles| {
+                    if series.inverted {
+                        candles.into_iter().map(Candle::inverted).collect()
+                    } else {
+                        candles
+                    }
+                })
+            }
+            _ => None,
+        };
+        if let Some(candles) = candles {
+            let mut title = format!("{} (OHLC)", self.tickers.0);
+            if let Some(share) = self.in_range_share() {
+                title = format!("{} | {:.0}% in range", title, share);
+            }
+            render_candle_chart(frame, price_area, &candles, &title, series.is_hourly, theme);
+            return;
+        }
+
+        let mut chart = self.price_chart(chart_state);
+        chart.downsample(price_area);
+        chart_state.cursor.place(&mut chart, price_area);
+        render_token_chart(frame, price_area, &chart, theme);
+        if let Some(overlay) = self.overlay {
+            let out_of_range =
+                |time| value_at(series.range_prices(), time).is_some_and(|p| !overlay.contains(p));
+            shade_plot(
+                frame,
+                price_area,
+                &chart,
+                out_of_range,
+                theme.highlight_unfocused(),
+            );
+        }
+    }
+
+    /// Draws the view's amounts, returning the bucket and totals of the bars
+    /// when they are summed per bucket
+    fn render_amounts(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        chart_state: &mut ChartState,
+    ) -> Option<(Bucket, Vec<(f64, f64)>)> {
+        let view = chart_state.view;
+        let series = &self.series;
+        let span = series.window[1] - series.window[0];
+        let mut chart = self.token_chart();
+        chart.token0_ticker = view.as_str().to_string();
+        chart.y_title = "USD";
+        let mut bars = None;
+        if view == ChartView::Tvl {
+            // A level, so each bucket keeps its latest, at most two per column
+            let bucket = Bucket::fitting(series.step, span, area.width as usize * 2);
+            let levels = resample(&series.amounts, bucket, Aggregate::Last);
+            chart.update_with_price_data(&levels, &[]);
+            chart.title = format!("{} (USD, {})", view.as_str(), bucket.as_str());
+        } else {
+            // Amounts per period, summed into at most a bar per column
+            let bucket = Bucket::fitting(series.step, span, area.width as usize);
+            let totals = resample(&series.amounts, bucket, Aggregate::Sum);
+            chart.update_with_price_data(&totals, &[]);
+            chart.graph_type = GraphType::Bar;
+            chart.min_price = 0.0;
+            chart.title = format!("{} (USD, {})", view.as_str(), bucket.as_str());
+            bars = Some((bucket, totals));
+        }
+        chart_state.cursor.place(&mut chart, area);
+        render_token_chart(frame, area, &chart, self.theme);
+        bars
+    }
+
+    /// Draws what the chart shows at the cursor, if placed
+    fn render_tooltip(
+        &self,
+        frame: &mut Frame,
+        chart_state: &ChartState,
+        bars: Option<&(Bucket, Vec<(f64, f64)>)>,
+    ) {
+        let cursor = &chart_state.cursor;
+        let (Some(time), Some(column), Some(plot)) =
+            (cursor.time(), cursor.column(), cursor.plot())
+        else {
+            return;
+        };
+        let (theme, series) = (self.theme, &self.series);
+        let mut lines = vec![Line::styled(
+            format_time(time, series.is_hourly),
+            Style::default()
+                .fg(theme.text_primary())
+                .add_modifier(Modifier::BOLD),
+        )];
+        // Each price with its change since the start of the range
+        for (ticker, prices) in [
+            (self.tickers.0, &series.token0),
+            (self.tickers.1, &series.token1),
+        ] {
+            let Some(&(_, price)) = prices.iter().find(|(t, _)| *t == time) else {
+                continue;
+            };
+            let mut spans = vec![Span::raw(format!("{} {}", ticker, format_amount(price)))];
+            if let Some(&(_, first)) = prices.first().filter(|(_, first)| *first != 0.0) {
+                let change = (price / first - 1.0) * 100.0;
+                let color = if change >= 0.0 {
+                    theme.profit()
+                } else {
+                    theme.loss()
+                };
+                spans.push(Span::styled(
+                    format!(" {:+.2}%", change),
+                    Style::default().fg(color),
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+        let bar = bars
+            .and_then(|(bucket, totals)| Some((bucket, totals.iter().find(|(t, _)| *t == time)?)));
+        if let Some((bucket, (_, total))) = bar {
+            lines.push(Line::raw(format!(
+                "{} ({}) {}",
+                chart_state.view.as_str(),
+                bucket.as_str(),
+                format_usd(*total)
+            )));
+        } else if let Some(volume) = volume_at(self.pool, series.is_hourly, time) {
+            lines.push(Line::raw(format!("Volume {}", format_usd(volume))));
+        }
+        if let Some((overlay, price)) = self.overlay.zip(value_at(series.range_prices(), time)) {
+            lines.push(if overlay.contains(price) {
+                Line::styled("In range", Style::default().fg(theme.profit()))
+            } else {
+                Line::styled("Out of range", Style::default().fg(theme.loss()))
+            });
+        }
+        render_cursor_tooltip(frame, plot, column, lines, theme);
+    }
+}
+
+/// The lines of the indicator `chart_state` picks over `prices`
+fn indicators(chart_state: &ChartState, prices: &[(f64, f64)], theme: &Theme) -> Vec<Overlay> {
+    let periods = chart_state.periods;
+    match chart_state.indicator {
+        Indicator::None => vec![],
+        Indicator::MovingAverages => vec![
+            (
+                format!("SMA {}", periods.sma),
+                theme.text_secondary(),
+                sma(prices, periods.sma),
+            ),
+            (
+                format!("EMA {}", periods.ema),
+                theme.highlight_focused(),
+                ema(prices, periods.ema),
+            ),
+        ],
+        Indicator::Bollinger => {
+            let bands = bollinger(prices, periods.bollinger, periods.bollinger_width);
+            vec![
+                ("Upper band".to_string(), theme.gray(), bands.upper),
+                (
+                    format!("SMA {}", periods.bollinger),
+                    theme.text_secondary(),
+                    bands.middle,
+                ),
+                ("Lower band".to_string(), theme.gray(), bands.lower),
+            ]
+        }
+    }
+}
+
+/// Draws the chart of `pool` in the view and over the time range `chart`
+/// picks with the time range tab bar below it, the cursor over it if shown and
+/// `overlay` over its prices
+pub fn render_pool_chart(
+    frame: &mut Frame,
+    area: Rect,
+    pool: &Pool,
+    (token0_ticker, token1_ticker): (&str, &str),
+    overlay: Option<&RangeOverlay>,
+    chart_state: &mut ChartState,
+    theme: &Theme,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(10), Constraint::Length(3)].as_ref())
+        .split(area);
+
+    let now = chrono::Utc::now().timestamp() as f64;
+    let series = PoolSeries::new(pool, chart_state, now);
+    let is_hourly = series.is_hourly;
+    if series.is_empty() {
+        let warning = Paragraph::new(vec![
+            Line::from(Span::raw("No chart data available for the selected range. Try another range or check your data source."))
+        ])
+        .style(Style::default().fg(theme.loss()))
+        .block(Block::default().title("Warning").borders(Borders::ALL))
+        .wrap(Wrap { trim: true });
+        frame.render_widget(warning, chunks[0]);
+    } else {
+        let pool_chart = PoolChart {
+            pool,
+            tickers: if series.inverted {
+                (token1_ticker, token0_ticker)
+            } else {
+                (token0_ticker, token1_ticker)
+            },
+            series,
+            overlay,
+            theme,
+        };
+        let bars = match chart_state.view {
+            ChartView::Price | ChartView::Candles => {
+                pool_chart.render_prices(frame, chunks[0], chart_state);
+                None
+            }
+            ChartView::Tvl | ChartView::Volume | ChartView::Fees => {
+                pool_chart.render_amounts(frame, chunks[0], chart_state)
+            }
+        };
+        pool_chart.render_tooltip(frame, chart_state, bars.as_ref());
+    }
+    render_range_tabs(frame, chunks[1], chart_state, is_hourly, now, theme);
+}
+
+/// Draws the time range tab bar, with the chart's toggles and the dates shown
+/// once zoomed, panned or typed in
+fn render_range_tabs(
+    frame: &mut Frame,
+    area: Rect,
+    chart_state: &ChartState,
+    is_hourly: bool,
+    now: f64,
+    theme: &Theme,
+) {
+    // No preset is picked once the chart is zoomed, panned or given dates
+    let picked_range = Some(chart_state.time_range).filter(|_| chart_state.viewport.is_none());
+    let tab_titles: Vec<Span> = ChartTimeRange::ALL
+        .iter()
+        .map(|range| {
+            if Some(*range) == picked_range {
+                Span::styled(
+                    range.as_str(),
+                    Style::default()
+                        .fg(theme.text_primary())
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                )
+            } else {
+                Span::styled(range.as_str(), Style::default().fg(theme.text_secondary()))
+            }
+        })
+        .collect();
+    let toggle_hint = format!("[v] {}", chart_state.view.next().as_str());
+    let mut title = vec![Span::raw(format!(
+        "Range | Toggle: {} | [l] {} | [a] {}{}",
+        toggle_hint,
+        chart_state.scale.as_str(),
+        chart_state.indicator.as_str(),
+        if chart_state.inverted {
+            " | inverted"
+        } else {
+            ""
+        }
+    ))];
+    if let Some(input) = &chart_state.range_input {
+        title.push(Span::styled(
+            format!(" | dates: {}█", input),
+            Style::default().fg(theme.highlight_focused()),
+        ));
+    } else if let Some(error) = &chart_state.range_error {
+        title.push(Span::styled(
+            format!(" | {}", error),
+            Style::default().fg(theme.loss()),
+        ));
+    } else if chart_state.viewport.is_some() {
+        let (from, to) = chart_state.window(now);
+        let date = |time: f64| format_time(time, is_hourly);
+        title.push(Span::styled(
+            format!(" | {} → {}", date(from), date(to)),
+            Style::default().fg(theme.text_primary()),
+        ));
+    }
+    let tabs = ratatui::widgets::Tabs::new(tab_titles)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border_primary()))
+                .title(Line::from(title)),
+        )
+        .highlight_style(
+            Style::default()
+                .fg(theme.text_primary())
+                .add_modifier(Modifier::BOLD),
+        )
+        .select(
+            picked_range.and_then(|picked| ChartTimeRange::ALL.iter().position(|r| *r == picked)),
+        );
+    frame.render_widget(tabs, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn y_bounds_fit_the_window_and_lines_added() {
+        let mut chart = TokenChart::new();
+        chart.window = [1.0, 2.0];
+        chart.update_with_price_data(&[(0.0, 100.0), (1.0, 3.0), (2.0, 5.0)], &[(2.0, 4.0)]);
+        assert_eq!([chart.min_price, chart.max_price], [3.0, 5.0]);
+        chart.add_bound("Upper", 8.0);
+        assert_eq!([chart.min_price, chart.max_price], [3.0, 8.0]);
+    }
+
+    #[test]
+    fn flat_prices_get_some_height() {
+        let mut chart = TokenChart::new();
+        chart.update_with_price_data(&[(0.0, 2.0), (1.0, 2.0)], &[]);
+        assert_eq!(chart.window, [0.0, 1.0]);
+        assert_eq!([chart.min_price, chart.max_price], [1.9, 2.1 + 0.01]);
+    }
+
+    #[test]
+    fn dual_axes_rescale_token1_onto_token0() {
+        let mut chart = TokenChart::new();
+        chart.update_with_dual_axes(&[(0.0, 10.0), (1.0, 20.0)], &[(0.0, 1000.0), (1.0, 3000.0)]);
+        assert_eq!(chart.token1_prices, [(0.0, 10.0), (1.0, 20.0)]);
+        assert_eq!(chart.token1_y(2000.0), 15.0);
+        assert_eq!(chart.right_labels(), ["1000", "2000", "3000"]);
+    }
+}
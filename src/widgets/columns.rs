@@ -0,0 +1,334 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use ratatui::layout::{Alignment, Constraint};
+use serde::Deserialize;
+
+use crate::models::position::Position;
+
+/// A column of the positions table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Id,
+    Pair,
+    FeeTier,
+    Value,
+    Fees,
+    InRange,
+    ImpermanentLoss,
+    Volume,
+    Age,
+}
+
+impl Column {
+    pub const ALL: [Column; 9] = [
+        Column::Id,
+        Column::Pair,
+        Column::FeeTier,
+        Column::Value,
+        Column::Fees,
+        Column::InRange,
+        Column::ImpermanentLoss,
+        Column::Volume,
+        Column::Age,
+    ];
+
+    /// Shown when no columns are configured
+    pub const DEFAULT: [Column; 7] = [
+        Column::Pair,
+        Column::FeeTier,
+        Column::Value,
+        Column::Fees,
+        Column::InRange,
+        Column::Volume,
+        Column::Age,
+    ];
+
+    /// The name used in the config and in filters
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Pair => "pair",
+            Column::FeeTier => "fee_tier",
+            Column::Value => "value",
+            Column::Fees => "fees",
+            Column::InRange => "in_range",
+            Column::ImpermanentLoss => "impermanent_loss",
+            Column::Volume => "volume",
+            Column::Age => "age",
+        }
+    }
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::Pair => "Pair",
+            Column::FeeTier => "Fee",
+            Column::Value => "Value",
+            Column::Fees => "Unclaimed Fees",
+            Column::InRange => "In Range",
+            Column::ImpermanentLoss => "IL",
+            Column::Volume => "24h Volume",
+            Column::Age => "Age",
+        }
+    }
+
+    pub fn alignment(&self) -> Alignment {
+        match self {
+            Column::Pair | Column::Id => Alignment::Left,
+            Column::InRange => Alignment::Center,
+            _ => Alignment::Right,
+        }
+    }
+
+    pub fn width(&self) -> Constraint {
+        match self {
+            Column::Id => Constraint::Length(8),
+            Column::Pair => Constraint::Length(16),
+            Column::FeeTier => Constraint::Length(7),
+            Column::InRange => Constraint::Length(9),
+            Column::ImpermanentLoss => Constraint::Length(9),
+            Column::Age => Constraint::Length(10),
+            Column::Value | Column::Fees | Column::Volume => Constraint::Length(16),
+        }
+    }
+
+    /// Whether filters on this column compare numbers rather than text
+    fn is_numeric(&self) -> bool {
+        !matches!(self, Column::Id | Column::Pair | Column::InRange)
+    }
+
+    /// The displayed text and sort key of this column for `position`
+    pub fn extract(&self, position: &Position, eth_price_usd: f64) -> CellValue {
+        let usd = |value: Option<f64>| match value {
+            Some(value) => CellValue::number(format!("${:.2}", value), value),
+            None => CellValue::missing(),
+        };
+
+        match self {
+            Column::Id => match position.id.parse::<f64>() {
+                Ok(id) => CellValue::number(position.id.clone(), id),
+                Err(_) => CellValue::text(position.id.clone()),
+            },
+            Column::Pair => CellValue::text(position.pair()),
+            Column::FeeTier => match position
+                .pool
+                .fee_tier
+                .as_ref()
+                .and_then(|fee| fee.parse::<f64>().ok())
+            {
+                Some(fee) => CellValue::number(format!("{:.2}%", fee / 10_000.0), fee),
+                None => CellValue::missing(),
+            },
+            Column::Value => usd(position.value_usd(eth_price_usd)),
+            Column::Fees => usd(position.uncollected_fees_usd(eth_price_usd)),
+            Column::InRange => match position.is_in_range() {
+                Some(true) => CellValue::number("✓".to_string(), 1.0),
+                Some(false) => CellValue::number("X".to_string(), 0.0),
+                None => CellValue::missing(),
+            },
+            Column::ImpermanentLoss => match position.impermanent_loss(eth_price_usd) {
+                Some(il) => CellValue::number(format!("{:.2}%", il), il),
+                None => CellValue::missing(),
+            },
+            Column::Volume => {
+                let day_ago = Utc::now().timestamp() as f64 - 60.0 * 60.0 * 24.0;
+                usd(Some(position.pool.volume_since(day_ago)))
+            }
+            Column::Age => match position.opened_at() {
+                Some(opened) => CellValue::number(format_age(opened), opened.timestamp() as f64),
+                None => CellValue::missing(),
+            },
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.to_lowercase().replace('-', "_");
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name() == name)
+            .ok_or_else(|| anyhow!("unknown column '{}'", s))
+    }
+}
+
+fn format_age(opened: DateTime<Utc>) -> String {
+    let duration = Utc::now() - opened;
+    if duration.num_days() > 0 {
+        format!("{}d ago", duration.num_days())
+    } else if duration.num_hours() > 0 {
+        format!("{}h ago", duration.num_hours())
+    } else if duration.num_minutes() > 0 {
+        format!("{}m ago", duration.num_minutes())
+    } else {
+        "just now".to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKey {
+    Number(f64),
+    Text(String),
+    /// The value could not be computed, always sorted last
+    Missing,
+}
+
+/// A table cell: what is shown and what it sorts and filters by
+#[derive(Debug, Clone)]
+pub struct CellValue {
+    pub text: String,
+    pub key: SortKey,
+}
+
+impl CellValue {
+    fn number(text: String, value: f64) -> Self {
+        Self {
+            text,
+            key: SortKey::Number(value),
+        }
+    }
+
+    fn text(text: String) -> Self {
+        Self {
+            key: SortKey::Text(text.to_lowercase()),
+            text,
+        }
+    }
+
+    fn missing() -> Self {
+        Self {
+            text: "N/A".to_string(),
+            key: SortKey::Missing,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    pub fn reversed(self) -> Self {
+        match self {
+            SortOrder::Ascending => SortOrder::Descending,
+            SortOrder::Descending => SortOrder::Ascending,
+        }
+    }
+
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            SortOrder::Ascending => "▲",
+            SortOrder::Descending => "▼",
+        }
+    }
+}
+
+/// Compares two cells in `order`, keeping missing values at the end either way
+pub fn compare(a: &SortKey, b: &SortKey, order: SortOrder) -> Ordering {
+    let ordering = match (a, b) {
+        (SortKey::Missing, SortKey::Missing) => return Ordering::Equal,
+        (SortKey::Missing, _) => return Ordering::Greater,
+        (_, SortKey::Missing) => return Ordering::Less,
+        (SortKey::Number(a), SortKey::Number(b)) => a.total_cmp(b),
+        (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+        (SortKey::Number(_), SortKey::Text(_)) => Ordering::Less,
+        (SortKey::Text(_), SortKey::Number(_)) => Ordering::Greater,
+    };
+    match order {
+        SortOrder::Ascending => ordering,
+        SortOrder::Descending => ordering.reverse(),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    Contains(String),
+    Is(bool),
+    Above(f64),
+    AtLeast(f64),
+    Below(f64),
+    AtMost(f64),
+}
+
+/// An inline filter such as `pair:ETH in-range:true value:>1000`.
+/// Terms without a column match the pair, and every term has to match.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    terms: Vec<(Column, Condition)>,
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut terms = Vec::new();
+        for term in s.split_whitespace() {
+            let (column, value) = match term.split_once(':') {
+                Some((column, value)) => (column.parse::<Column>()?, value),
+                None => (Column::Pair, term),
+            };
+
+            let number = |v: &str| {
+                v.parse::<f64>()
+                    .map_err(|_| anyhow!("'{}' is not a number in '{}'", v, term))
+            };
+            let condition = if column == Column::InRange {
+                match value.to_lowercase().as_str() {
+                    "true" | "yes" => Condition::Is(true),
+                    "false" | "no" => Condition::Is(false),
+                    _ => return Err(anyhow!("expected true or false in '{}'", term)),
+                }
+            } else if let (true, Some(v)) = (column.is_numeric(), value.strip_prefix(">=")) {
+                Condition::AtLeast(number(v)?)
+            } else if let (true, Some(v)) = (column.is_numeric(), value.strip_prefix("<=")) {
+                Condition::AtMost(number(v)?)
+            } else if let (true, Some(v)) = (column.is_numeric(), value.strip_prefix('>')) {
+                Condition::Above(number(v)?)
+            } else if let (true, Some(v)) = (column.is_numeric(), value.strip_prefix('<')) {
+                Condition::Below(number(v)?)
+            } else {
+                Condition::Contains(value.to_lowercase())
+            };
+            terms.push((column, condition));
+        }
+        Ok(Filter { terms })
+    }
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Whether a row matches, given its cells in `Column::ALL` order
+    pub fn matches(&self, cells: &[CellValue]) -> bool {
+        self.terms.iter().all(|(column, condition)| {
+            let Some(cell) = Column::ALL
+                .iter()
+                .position(|c| c == column)
+                .and_then(|i| cells.get(i))
+            else {
+                return false;
+            };
+            let number = match cell.key {
+                SortKey::Number(n) => Some(n),
+                _ => None,
+            };
+            match condition {
+                Condition::Contains(text) => cell.text.to_lowercase().contains(text),
+                Condition::Is(expected) => number.map(|n| n > 0.0) == Some(*expected),
+                Condition::Above(v) => number.is_some_and(|n| n > *v),
+                Condition::AtLeast(v) => number.is_some_and(|n| n >= *v),
+                Condition::Below(v) => number.is_some_and(|n| n < *v),
+                Condition::AtMost(v) => number.is_some_and(|n| n <= *v),
+            }
+        })
+    }
+}
@@ -0,0 +1,270 @@
+use anyhow::Result;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::{
+    models::position::{Pool, PoolTick, Token},
+    theme::Theme,
+    util::{
+        format::format_amount,
+        uniswap::{active_liquidity, adjusted_price, tick_spacing, tick_to_price},
+    },
+    widgets::spinner::Spinner,
+};
+
+/// Tick spacings shown on either side of the current tick when there is no range to fit
+const DEPTH_SPACINGS: i32 = 60;
+
+/// Most tick spacings shown on either side of the current tick, so a wide range
+/// such as a full range one doesn't mean reading thousands of ticks
+const MAX_DEPTH_SPACINGS: i32 = 500;
+
+/// The initialized ticks of one pool over a span, and the span being fetched
+pub struct TickDepth {
+    pool: Option<String>,
+    /// The span `ticks` covers, once loaded
+    span: Option<(i32, i32)>,
+    /// The span being fetched, results for older spans are dropped
+    pending: Option<(i32, i32)>,
+    /// Initialized ticks as `(tick, liquidity_net)`. They can come from the
+    /// subgraph's `ticks` or from the pool contract's `ticks()`, only the net
+    /// liquidity is needed.
+    ticks: Vec<(i32, f64)>,
+    /// Why the last fetch failed
+    pub error: Option<String>,
+}
+
+impl TickDepth {
+    pub fn new() -> Self {
+        Self {
+            pool: None,
+            span: None,
+            pending: None,
+            ticks: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Marks the ticks of `pool` over `span` as wanted, returning whether they
+    /// have to be fetched
+    pub fn request(&mut self, pool: &str, span: (i32, i32)) -> bool {
+        if self.pool.as_deref() != Some(pool) {
+            self.pool = Some(pool.to_string());
+            self.span = None;
+            self.ticks.clear();
+        } else if self.covers(span) || self.pending == Some(span) {
+            return false;
+        }
+        self.pending = Some(span);
+        self.error = None;
+        true
+    }
+
+    /// Stores the ticks of `pool` over `span` unless another span has been asked for since
+    pub fn loaded(&mut self, pool: &str, span: (i32, i32), result: Result<Vec<PoolTick>>) {
+        if self.pool.as_deref() != Some(pool) || self.pending != Some(span) {
+            return;
+        }
+        self.pending = None;
+        match result {
+            Ok(ticks) => {
+                self.span = Some(span);
+                self.ticks = ticks.iter().filter_map(PoolTick::parsed).collect();
+            }
+            Err(e) => self.error = Some(format!("{:#}", e)),
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Whether the loaded ticks span all of `span`
+    pub fn covers(&self, (from, to): (i32, i32)) -> bool {
+        self.span
+            .is_some_and(|(loaded_from, loaded_to)| loaded_from <= from && to <= loaded_to)
+    }
+
+    /// Active liquidity as `(from_tick, liquidity)` segments, once the ticks around
+    /// the current one are loaded, see `active_liquidity`
+    pub fn segments(&self, pool: &Pool) -> Option<Vec<(i32, f64)>> {
+        let current_tick = pool.current_tick()?;
+        if self.pool.as_deref() != Some(pool.id.as_str())
+            || !self.covers((current_tick, current_tick))
+        {
+            return None;
+        }
+        let current_liquidity = pool.liquidity.as_deref()?.parse().ok()?;
+        Some(active_liquidity(
+            &self.ticks,
+            current_tick,
+            current_liquidity,
+        ))
+    }
+}
+
+/// The ticks to show the depth of `pool` over: around the current tick, widened
+/// to fit `range` with some room on either side, up to `MAX_DEPTH_SPACINGS` away
+pub fn depth_span(pool: &Pool, range: Option<(i32, i32)>) -> Option<(i32, i32)> {
+    let current = pool.current_tick()?;
+    let spacing = pool.fee_tier().and_then(tick_spacing)?;
+    Some(span_around(current, spacing, range))
+}
+
+fn span_around(current: i32, spacing: i32, range: Option<(i32, i32)>) -> (i32, i32) {
+    let (from, to) = match range {
+        Some((lower, upper)) => {
+            let (from, to) = (lower.min(current), upper.max(current));
+            let margin = ((to - from) / 5).max(spacing * 10);
+            (from.saturating_sub(margin), to.saturating_add(margin))
+        }
+        None => (
+            current - spacing * DEPTH_SPACINGS,
+            current + spacing * DEPTH_SPACINGS,
+        ),
+    };
+    let reach = spacing * MAX_DEPTH_SPACINGS;
+    (from.max(current - reach), to.min(current + reach))
+}
+
+/// Draws the liquidity active at each price over `span` as bars, marking the
+/// current price and the ticks inside any of `ranges`
+pub fn render_depth_chart(
+    f: &mut Frame,
+    area: Rect,
+    pool: &Pool,
+    depth: &TickDepth,
+    span: (i32, i32),
+    ranges: &[(i32, i32)],
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .title("Liquidity")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_primary()));
+
+    let segments = match depth.segments(pool) {
+        Some(segments) if depth.covers(span) => segments,
+        _ => {
+            let message = if depth.is_loading() {
+                Line::styled(
+                    format!("{} Loading ticks...", Spinner::default().to_string()),
+                    Style::default().fg(theme.text_secondary()),
+                )
+            } else if let Some(error) = &depth.error {
+                Line::styled(
+                    format!("Failed to load ticks: {}", error),
+                    Style::default().fg(theme.loss()),
+                )
+            } else {
+                Line::styled("No tick data", Style::default().fg(theme.text_secondary()))
+            };
+            f.render_widget(
+                Paragraph::new(message)
+                    .alignment(Alignment::Center)
+                    .block(block),
+                area,
+            );
+            return;
+        }
+    };
+
+    // One bar per column, each averaging the liquidity over its ticks
+    let (from, to) = span;
+    let columns = area.width.saturating_sub(2).max(1) as i32;
+    let bucket = ((to - from) as f64 / columns as f64).max(1.0);
+    let buckets: Vec<(i32, i32, f64)> = (0..columns)
+        .map(|i| {
+            let lower = from + (i as f64 * bucket) as i32;
+            let upper = (from + ((i + 1) as f64 * bucket) as i32).max(lower + 1);
+            (lower, upper, average_liquidity(&segments, lower, upper))
+        })
+        .take_while(|(lower, _, _)| *lower < to)
+        .collect();
+    let max = buckets
+        .iter()
+        .map(|(_, _, liquidity)| *liquidity)
+        .fold(0.0, f64::max);
+
+    let current = pool.current_tick().unwrap_or(from);
+    let bars: Vec<Bar> = buckets
+        .iter()
+        .map(|&(lower, upper, liquidity)| {
+            let style = if lower <= current && current < upper {
+                Style::default()
+                    .fg(theme.text_primary())
+                    .add_modifier(Modifier::BOLD)
+            } else if ranges.iter().any(|(l, u)| lower < *u && *l < upper) {
+                Style::default().fg(theme.series_secondary())
+            } else {
+                Style::default().fg(theme.series_primary())
+            };
+            let height = if max > 0.0 {
+                (liquidity / max * 1000.0).round() as u64
+            } else {
+                0
+            };
+            Bar::default()
+                .value(height)
+                .text_value(String::new())
+                .style(style)
+        })
+        .collect();
+
+    let price = |tick: i32| match (&pool.token0, &pool.token1) {
+        (Some(token0), Some(token1)) => format_amount(tick_price(tick, token0, token1)),
+        _ => format!("tick {}", tick),
+    };
+    let chart = BarChart::default()
+        .block(
+            block
+                .title_bottom(Line::from(price(from)).left_aligned())
+                .title_bottom(Line::from(format!("now {}", price(current))).centered())
+                .title_bottom(Line::from(price(to)).right_aligned()),
+        )
+        .bar_width(1)
+        .bar_gap(0)
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, area);
+}
+
+/// Price of token0 in token1 at `tick`
+fn tick_price(tick: i32, token0: &Token, token1: &Token) -> f64 {
+    adjusted_price(tick_to_price(tick), token0.decimals(), token1.decimals())
+}
+
+/// The liquidity over `[lower, upper)` averaged over its ticks
+fn average_liquidity(segments: &[(i32, f64)], lower: i32, upper: i32) -> f64 {
+    let mut weighted = 0.0;
+    for (i, &(from, liquidity)) in segments.iter().enumerate() {
+        let to = segments.get(i + 1).map_or(upper, |(next, _)| *next);
+        let width = to.min(upper) - from.max(lower);
+        if width > 0 {
+            weighted += width as f64 * liquidity;
+        }
+    }
+    weighted / (upper - lower) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::uniswap::{MAX_TICK, MIN_TICK};
+
+    #[test]
+    fn span_fits_a_narrow_range_with_room() {
+        assert_eq!(span_around(0, 60, Some((-600, 1200))), (-1200, 1800));
+        assert_eq!(span_around(100, 60, None), (-3500, 3700));
+    }
+
+    #[test]
+    fn span_of_a_full_range_stays_around_the_current_tick() {
+        let span = span_around(200_000, 60, Some((MIN_TICK, MAX_TICK)));
+        assert_eq!(span, (170_000, 230_000));
+    }
+}
@@ -1,11 +1,13 @@
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, StatefulWidget, Widget, Wrap},
 };
 
+use crate::theme::Theme;
+
 pub struct EnterEnsState {
     search_ens_string: String,
     has_user_input: bool,
@@ -54,26 +56,29 @@ impl EnterEnsState {
     }
 }
 
-pub struct EnterENS {}
+pub struct EnterENS {
+    pub theme: Theme,
+}
 
 impl StatefulWidget for EnterENS {
     type State = EnterEnsState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let theme = self.theme;
         let loading_spinner = if state.is_searching { "⏳" } else { "" };
 
         let input_style = if state.is_searching {
-            Style::default().fg(Color::DarkGray)
+            Style::default().fg(theme.gray())
         } else if state.error_msg.is_some() {
-            Style::default().fg(Color::Red)
+            Style::default().fg(theme.loss())
         } else {
-            Style::default().fg(Color::Cyan)
+            Style::default().fg(theme.text_secondary())
         };
 
         let spans = if !state.has_user_input && state.error_msg.is_some() {
             Line::from(vec![Span::styled(
                 state.error_msg.as_ref().unwrap(),
-                Style::default().fg(Color::Red),
+                Style::default().fg(theme.loss()),
             )])
         } else {
             Line::from(vec![Span::styled(
@@ -86,17 +91,17 @@ impl StatefulWidget for EnterENS {
             Block::new()
                 .title(" Searching... ")
                 .borders(Borders::ALL)
-                .style(Style::default().fg(Color::DarkGray))
+                .style(Style::default().fg(theme.gray()))
         } else if state.error_msg.is_some() {
             Block::new()
                 .title(" Enter ENS ")
                 .borders(Borders::ALL)
-                .style(Style::default().fg(Color::Red))
+                .style(Style::default().fg(theme.loss()))
         } else {
             Block::new()
                 .title(" Enter ENS ")
                 .borders(Borders::ALL)
-                .style(Style::default().fg(Color::Green))
+                .style(Style::default().fg(theme.highlight_focused()))
         };
 
         Paragraph::new(spans)
@@ -1,32 +1,89 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Stylize},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::{Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
+    Frame,
 };
 
-pub fn render_help_popup<'a>(size: Rect) -> (Paragraph<'a>, Block<'a>, Rect) {
+use crate::{
+    keymap::{KeyContext, Keymap},
+    theme::Theme,
+};
+
+/// Keys handled by the context itself rather than through the keymap
+fn context_hints(context: KeyContext) -> &'static [(&'static str, &'static str)] {
+    match context {
+        KeyContext::TextInput => &[
+            ("enter", "Submit what was typed"),
+            ("backspace", "Delete the last character"),
+            ("tab, up, down", "Move between the fields of a form"),
+        ],
+        KeyContext::Positions | KeyContext::PositionInfo | KeyContext::PoolInfo => {
+            &[("1-7", "Pick the chart time range")]
+        }
+        KeyContext::LimitOrders
+        | KeyContext::TopPools
+        | KeyContext::RangeEstimator
+        | KeyContext::Navigation => &[],
+    }
+}
+
+/// Draws the bindings available in `context` over the whole of `size`.
+/// `scroll` is clamped to the last page.
+pub fn render_help_popup(
+    frame: &mut Frame,
+    size: Rect,
+    theme: &Theme,
+    keymap: &Keymap,
+    context: KeyContext,
+    scroll: &mut u16,
+) {
+    let entries = keymap.help_entries(context);
+    let hints = context_hints(context)
+        .iter()
+        .map(|(keys, description)| (keys.to_string(), *description));
+    let lines: Vec<Line> = entries
+        .iter()
+        .map(|(keys, action)| (keys.clone(), action.description()))
+        .chain(hints)
+        .map(|(keys, description)| {
+            Line::from(Span::raw(format!(" {:<20}: {}", keys, description)).fg(theme.text_normal()))
+        })
+        .collect();
+
+    let area = centered_rect(60, 80, size);
+    let visible = area.height.saturating_sub(2);
+    let max_scroll = (lines.len() as u16).saturating_sub(visible);
+    *scroll = (*scroll).min(max_scroll);
+
     let block = Block::default()
         .title("Keybindings - Press Esc to close the popup")
-        .borders(Borders::ALL);
-
-    let input = Paragraph::new(vec![
-        Line::from(
-            Span::raw(format!(" {:<4}: {}", "s", "Move to the Search Bar")).fg(Color::White),
-        ),
-        Line::from(
-            Span::raw(format!(" {:<4}: {}", "1", "Move to Positions Info area")).fg(Color::White),
-        ),
-        Line::from(
-            Span::raw(format!(" {:<4}: {}", "2", "Move to the My Positions")).fg(Color::White),
-        ),
-    ])
-    .style(Style::default().fg(Color::Green))
-    .block(block.to_owned());
-
-    let area = centered_rect(40, 10, size);
-
-    (input, block, area)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_primary()))
+        .style(Style::default().bg(theme.background()));
+
+    let paragraph = Paragraph::new(lines)
+        .style(Style::default().fg(theme.text_primary()))
+        .block(block)
+        .scroll((*scroll, 0));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+
+    if max_scroll > 0 {
+        let mut scrollbar_state =
+            ScrollbarState::new(max_scroll as usize).position(*scroll as usize);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .style(Style::default().fg(theme.border_secondary())),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
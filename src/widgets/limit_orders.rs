@@ -1,28 +1,137 @@
-use crate::network::limit_orders::LimitOrder;
+use crate::{network::limit_orders::LimitOrder, theme::Theme, widgets::spinner::Spinner};
+use chrono::{DateTime, Duration, Local};
 use ratatui::{
-    backend::Backend,
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Row, Table},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
     Frame,
 };
 
+/// Selection and refresh state of the limit orders screen
 pub struct LimitOrdersWidget {
-    orders: Vec<LimitOrder>,
+    pub state: TableState,
+    /// A fetch is in flight
+    pub loading: bool,
+    /// Why the last fetch failed, cleared by the next successful one
+    pub error: Option<String>,
+    pub last_refreshed: Option<DateTime<Local>>,
+    /// When the next poll is due, `None` until polling has started
+    pub next_refresh: Option<DateTime<Local>>,
 }
 
 impl LimitOrdersWidget {
     pub fn new() -> Self {
-        Self { orders: Vec::new() }
+        let mut state = TableState::default();
+        state.select(Some(0));
+        Self {
+            state,
+            loading: false,
+            error: None,
+            last_refreshed: None,
+            next_refresh: None,
+        }
     }
 
-    pub fn update(&mut self, orders: Vec<LimitOrder>) {
-        self.orders = orders;
+    /// Records the outcome of a fetch and when the next one is due
+    pub fn refreshed(&mut self, error: Option<String>, next_refresh_secs: u64) {
+        let now = Local::now();
+        self.loading = false;
+        if error.is_none() {
+            self.last_refreshed = Some(now);
+        }
+        self.error = error;
+        self.next_refresh = Some(now + Duration::seconds(next_refresh_secs as i64));
     }
 
-    pub fn render(&self, f: &mut Frame, area: Rect) {
-        let block = Block::default().title("Limit Orders").borders(Borders::ALL);
+    pub fn next(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let i = self.state.selected().map_or(0, |i| (i + 1) % len);
+        self.state.select(Some(i));
+    }
+
+    pub fn previous(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let i = self
+            .state
+            .selected()
+            .map_or(0, |i| if i == 0 { len - 1 } else { i - 1 });
+        self.state.select(Some(i));
+    }
+
+    pub fn first(&mut self) {
+        self.state.select(Some(0));
+    }
+
+    pub fn last(&mut self, len: usize) {
+        self.state.select(Some(len.saturating_sub(1)));
+    }
+
+    /// Moves the selection by `rows` without wrapping around
+    pub fn scroll_by(&mut self, rows: isize, len: usize) {
+        let i = self
+            .state
+            .selected()
+            .unwrap_or(0)
+            .saturating_add_signed(rows);
+        self.state.select(Some(i.min(len.saturating_sub(1))));
+    }
+
+    /// Keeps the selection on an existing row after the orders changed
+    pub fn clamp_selection(&mut self, len: usize) {
+        let i = self.state.selected().unwrap_or(0);
+        self.state.select(Some(i.min(len.saturating_sub(1))));
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect, orders: &[LimitOrder], theme: &Theme) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(area);
+        let body = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(layout[0]);
+
+        self.render_table(f, body[0], orders, theme);
+        self.render_details(f, body[1], orders, theme);
+        self.render_status(f, layout[1], orders, theme);
+    }
+
+    fn render_table(&mut self, f: &mut Frame, area: Rect, orders: &[LimitOrder], theme: &Theme) {
+        let block = Block::default()
+            .title(format!("Limit Orders ({})", orders.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_primary()));
+
+        if orders.is_empty() {
+            let message = if self.loading {
+                Line::styled(
+                    format!("{} Loading limit orders...", Spinner::default().to_string()),
+                    Style::default().fg(theme.text_secondary()),
+                )
+            } else if let Some(error) = &self.error {
+                Line::styled(
+                    format!("Failed to load limit orders: {}", error),
+                    Style::default().fg(theme.loss()),
+                )
+            } else {
+                Line::styled(
+                    "No open limit orders",
+                    Style::default().fg(theme.text_secondary()),
+                )
+            };
+            let paragraph = Paragraph::new(message)
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .block(block);
+            f.render_widget(paragraph, area);
+            return;
+        }
 
         let header = Row::new(vec![
             Cell::from("Token"),
@@ -34,10 +143,13 @@ impl LimitOrdersWidget {
             Cell::from("Market Cap"),
             Cell::from("24h Volume"),
         ])
-        .style(Style::default().add_modifier(Modifier::BOLD));
+        .style(
+            Style::default()
+                .fg(theme.text_primary())
+                .add_modifier(Modifier::BOLD),
+        );
 
-        let rows: Vec<Row> = self
-            .orders
+        let rows: Vec<Row> = orders
             .iter()
             .map(|order| {
                 Row::new(vec![
@@ -64,8 +176,116 @@ impl LimitOrdersWidget {
             Constraint::Length(15),
         ];
 
-        let table = Table::new(rows, widths).header(header).block(block);
+        let table = Table::new(rows, widths)
+            .header(header)
+            .style(Style::default().fg(theme.text_normal()))
+            .row_highlight_style(
+                Style::default()
+                    .fg(theme.highlight_focused())
+                    .add_modifier(Modifier::REVERSED),
+            )
+            .highlight_symbol("> ")
+            .block(block);
+
+        f.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    fn render_details(&self, f: &mut Frame, area: Rect, orders: &[LimitOrder], theme: &Theme) {
+        let block = Block::default()
+            .title("Order Details")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_primary()));
+
+        let Some(order) = self.state.selected().and_then(|i| orders.get(i)) else {
+            f.render_widget(block, area);
+            return;
+        };
+
+        let field = |name: &str, value: String| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<12}", name),
+                    Style::default().fg(theme.text_secondary()),
+                ),
+                Span::styled(value, Style::default().fg(theme.text_normal())),
+            ])
+        };
+
+        let start = order.start_amount.parse::<f64>().unwrap_or(0.0);
+        let end = order.end_amount.parse::<f64>().unwrap_or(0.0);
+        let decay = if start > 0.0 {
+            format!("{:.2}%", (start - end) / start * 100.0)
+        } else {
+            "N/A".to_string()
+        };
+
+        let lines = vec![
+            field("Token", order.token.clone()),
+            field("Address", order.token_address.clone()),
+            field("Deadline", order.deadline.clone()),
+            field("Start", order.start_amount.clone()),
+            field("End", order.end_amount.clone()),
+            field("Decay", decay),
+            field(
+                "Price",
+                format!("${}", order.price_usd.as_deref().unwrap_or("N/A")),
+            ),
+            field("Value", format!("${}", order.value_usd)),
+            field("Market Cap", format!("${}", order.market_cap_usd)),
+            field("24h Volume", format!("${}", order.volume_24h)),
+            field(
+                "Maker",
+                order.maker.clone().unwrap_or_else(|| "N/A".to_string()),
+            ),
+            field(
+                "Order Hash",
+                order
+                    .order_hash
+                    .clone()
+                    .unwrap_or_else(|| "N/A".to_string()),
+            ),
+        ];
+
+        let paragraph = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(block);
+        f.render_widget(paragraph, area);
+    }
+
+    fn render_status(&self, f: &mut Frame, area: Rect, orders: &[LimitOrder], theme: &Theme) {
+        let mut spans = Vec::new();
+
+        if self.loading && !orders.is_empty() {
+            spans.push(Span::raw(format!(
+                "{} Refreshing",
+                Spinner::default().to_string()
+            )));
+        } else {
+            if let Some(last_refreshed) = self.last_refreshed {
+                spans.push(Span::raw(format!(
+                    "Last refreshed {}",
+                    last_refreshed.format("%H:%M:%S")
+                )));
+            }
+            if let Some(next_refresh) = self.next_refresh {
+                let secs = (next_refresh - Local::now()).num_seconds().max(0);
+                if !spans.is_empty() {
+                    spans.push(Span::raw(" · "));
+                }
+                spans.push(Span::raw(format!("next refresh in {}s", secs)));
+            }
+        }
+
+        if let (Some(error), false) = (&self.error, orders.is_empty()) {
+            spans.push(Span::styled(
+                format!(" · last refresh failed: {}", error),
+                Style::default().fg(theme.loss()),
+            ));
+        }
 
-        f.render_widget(table, area);
+        let status = Paragraph::new(Line::from(spans))
+            .style(Style::default().fg(theme.text_secondary()))
+            .alignment(Alignment::Right);
+        f.render_widget(status, area);
     }
 }
@@ -1,10 +1,19 @@
+pub mod breadcrumbs;
+pub mod candlestick;
 pub mod chart;
+pub mod columns;
+pub mod depth;
 pub mod enter_ens;
 pub mod help;
 pub mod limit_orders;
+pub mod notifications;
+pub mod pool_info;
+pub mod position_info;
+pub mod range_estimator;
 pub mod search;
 pub mod spinner;
 pub mod table;
 pub mod tabs;
+pub mod top_pools;
 pub mod utils;
 pub mod welcome;
@@ -0,0 +1,78 @@
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+const NOTIFICATION_TTL: Duration = Duration::from_secs(8);
+const NOTIFICATION_WIDTH: u16 = 50;
+const MAX_VISIBLE: usize = 3;
+
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+    created_at: Instant,
+}
+
+impl Notification {
+    pub fn new(title: String, message: String) -> Notification {
+        Notification {
+            title,
+            message,
+            created_at: Instant::now(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.created_at.elapsed() > NOTIFICATION_TTL
+    }
+}
+
+/// Draws the most recent notifications stacked in the top right corner of `area`
+pub fn render_notifications(
+    frame: &mut Frame,
+    notifications: &[Notification],
+    area: Rect,
+    theme: &Theme,
+) {
+    let width = NOTIFICATION_WIDTH.min(area.width);
+    let mut y = area.y + 1;
+
+    for notification in notifications
+        .iter()
+        .rev()
+        .filter(|n| !n.is_expired())
+        .take(MAX_VISIBLE)
+    {
+        let height = 4;
+        if y + height > area.y + area.height {
+            break;
+        }
+        let rect = Rect::new(area.x + area.width - width, y, width, height);
+
+        let paragraph = Paragraph::new(Line::from(Span::raw(notification.message.clone())))
+            .style(Style::default().fg(theme.text_normal()))
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.text_primary()))
+                    .title(Span::styled(
+                        format!(" {} ", notification.title),
+                        Style::default()
+                            .fg(theme.text_primary())
+                            .add_modifier(Modifier::BOLD),
+                    )),
+            );
+
+        frame.render_widget(Clear, rect);
+        frame.render_widget(paragraph, rect);
+        y += height;
+    }
+}
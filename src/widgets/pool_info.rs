@@ -0,0 +1,465 @@
+use anyhow::Result;
+use chrono::Utc;
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use crate::{
+    app::ChartState,
+    models::position::{Position, Token},
+    network::pools::{PoolPage, PoolQuery},
+    theme::Theme,
+    util::{
+        format::{format_amount, format_usd},
+        uniswap::tick_spacing,
+    },
+    widgets::{
+        chart::render_pool_chart,
+        depth::{depth_span, render_depth_chart, TickDepth},
+        spinner::Spinner,
+    },
+};
+
+/// Search box, results and selection of the pool explorer
+pub struct PoolExplorer {
+    /// What was typed into the search box
+    pub input: String,
+    /// The search box has focus
+    pub editing: bool,
+    /// The chain searched, `None` for the configured one
+    pub chain: Option<String>,
+    /// The lookup in flight, results of older ones are dropped
+    pub pending: Option<PoolQuery>,
+    /// Why the input could not be parsed or the last lookup failed
+    pub error: Option<String>,
+    pub page: Option<PoolPage>,
+    /// Liquidity around the current price of the pool shown
+    pub depth: TickDepth,
+    /// Selected row of the top positions
+    pub state: TableState,
+}
+
+impl PoolExplorer {
+    pub fn new() -> Self {
+        Self {
+            input: String::new(),
+            editing: false,
+            chain: None,
+            pending: None,
+            error: None,
+            page: None,
+            depth: TickDepth::new(),
+            state: TableState::default(),
+        }
+    }
+
+    pub fn start_search(&mut self) {
+        self.editing = true;
+        self.error = None;
+    }
+
+    /// Parses the input into a lookup to send to the network thread
+    pub fn submit(&mut self) -> Option<PoolQuery> {
+        match self.input.parse::<PoolQuery>() {
+            Ok(query) => {
+                self.editing = false;
+                self.error = None;
+                self.pending = Some(query.clone());
+                Some(query)
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Stores the outcome of `query` unless a newer search has been made since,
+    /// returning the pool and tick span whose liquidity has to be fetched
+    pub fn loaded(
+        &mut self,
+        query: &PoolQuery,
+        result: Result<PoolPage>,
+    ) -> Option<(String, (i32, i32))> {
+        if self.pending.as_ref() != Some(query) {
+            return None;
+        }
+        self.pending = None;
+        match result {
+            Ok(page) => {
+                self.state.select((!page.positions.is_empty()).then_some(0));
+                let span = depth_span(&page.pool, None);
+                let pool = page.pool.id.clone();
+                self.page = Some(page);
+                let span = span?;
+                self.depth.request(&pool, span).then_some((pool, span))
+            }
+            Err(e) => {
+                self.error = Some(format!("{:#}", e));
+                None
+            }
+        }
+    }
+
+    fn positions(&self) -> &[Position] {
+        self.page.as_ref().map_or(&[], |page| &page.positions)
+    }
+
+    /// The selected top position, carrying the pool's history for its chart
+    pub fn selected_position(&self) -> Option<Position> {
+        let page = self.page.as_ref()?;
+        let mut position = page.positions.get(self.state.selected()?)?.clone();
+        position.pool = page.pool.clone();
+        Some(position)
+    }
+
+    pub fn next(&mut self) {
+        let len = self.positions().len();
+        if len > 0 {
+            let i = self.state.selected().map_or(0, |i| (i + 1) % len);
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let len = self.positions().len();
+        if len > 0 {
+            let i = self
+                .state
+                .selected()
+                .map_or(0, |i| if i == 0 { len - 1 } else { i - 1 });
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn first(&mut self) {
+        if !self.positions().is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    pub fn last(&mut self) {
+        let len = self.positions().len();
+        if len > 0 {
+            self.state.select(Some(len - 1));
+        }
+    }
+
+    /// Moves the selection by `rows` without wrapping around
+    pub fn scroll_by(&mut self, rows: isize) {
+        let len = self.positions().len();
+        if len > 0 {
+            let i = self
+                .state
+                .selected()
+                .unwrap_or(0)
+                .saturating_add_signed(rows);
+            self.state.select(Some(i.min(len - 1)));
+        }
+    }
+
+    /// Draws the explorer, marking `positions`, the tick ranges of our own
+    /// positions in the pool, in the liquidity chart
+    pub fn render(
+        &mut self,
+        f: &mut Frame,
+        area: Rect,
+        chart: &mut ChartState,
+        positions: &[(i32, i32)],
+        theme: &Theme,
+    ) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+
+        self.render_search(f, layout[0], theme);
+
+        if self.page.is_none() {
+            let message = if self.pending.is_some() {
+                Line::styled(
+                    format!("{} Loading pool...", Spinner::default().to_string()),
+                    Style::default().fg(theme.text_secondary()),
+                )
+            } else if let Some(error) = &self.error {
+                Line::styled(
+                    format!("Failed to load pool: {}", error),
+                    Style::default().fg(theme.loss()),
+                )
+            } else {
+                Line::styled(
+                    "Search for a pool by its address, or by a pair and fee tier",
+                    Style::default().fg(theme.text_secondary()),
+                )
+            };
+            let paragraph = Paragraph::new(message)
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(theme.border_primary())),
+                );
+            f.render_widget(paragraph, layout[1]);
+            return;
+        }
+
+        let body = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(9), Constraint::Min(13)])
+            .split(layout[1]);
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(body[1]);
+
+        self.render_stats(f, body[0], theme);
+        if let Some(page) = &self.page {
+            let pool = &page.pool;
+            let symbol =
+                |token: &Option<Token>| token.as_ref().map_or(String::new(), |t| t.symbol.clone());
+            let (token0, token1) = (symbol(&pool.token0), symbol(&pool.token1));
+            render_pool_chart(f, bottom[0], pool, (&token0, &token1), None, chart, theme);
+        }
+        let side = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(10), Constraint::Min(0)])
+            .split(bottom[1]);
+        if let Some(page) = &self.page {
+            if let Some(span) = depth_span(&page.pool, None) {
+                render_depth_chart(f, side[0], &page.pool, &self.depth, span, positions, theme);
+            }
+        }
+        self.render_positions(f, side[1], theme);
+    }
+
+    fn render_search(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let title = if self.pending.is_some() && self.page.is_some() {
+            format!("{} Searching", Spinner::default().to_string())
+        } else if self.editing {
+            "Pool address, or pair and fee tier e.g. WETH/USDC 0.3% (enter to search)".to_string()
+        } else {
+            "Pool (e to search)".to_string()
+        };
+        let title = match &self.chain {
+            Some(chain) => format!("{} on {}", title, chain),
+            None => title,
+        };
+        let border = if self.editing {
+            theme.highlight_focused()
+        } else {
+            theme.highlight_unfocused()
+        };
+
+        let mut spans = vec![Span::styled(
+            self.input.clone(),
+            Style::default().fg(theme.text_normal()),
+        )];
+        if self.editing {
+            spans.push(Span::styled(
+                "█",
+                Style::default().fg(theme.highlight_focused()),
+            ));
+        }
+        // Without a pool to show the error is shown in its place instead
+        if let (Some(error), true) = (&self.error, self.page.is_some() || self.editing) {
+            spans.push(Span::styled(
+                format!("  {}", error),
+                Style::default().fg(theme.loss()),
+            ));
+        }
+
+        let paragraph = Paragraph::new(Line::from(spans)).block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(border)),
+        );
+        f.render_widget(paragraph, area);
+    }
+
+    fn render_stats(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let Some(page) = &self.page else {
+            return;
+        };
+        let pool = &page.pool;
+        let panels = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
+
+        let field = |name: &str, value: String| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<14}", name),
+                    Style::default().fg(theme.text_secondary()),
+                ),
+                Span::styled(value, Style::default().fg(theme.text_normal())),
+            ])
+        };
+        let na = || "N/A".to_string();
+
+        let (symbol0, symbol1) = match (&pool.token0, &pool.token1) {
+            (Some(token0), Some(token1)) => (token0.symbol.as_str(), token1.symbol.as_str()),
+            _ => ("token0", "token1"),
+        };
+        // The subgraph's `token1Price` is the price of token0 in token1 and vice versa
+        let price = |price: &str, base: &str, quote: &str| {
+            price.parse::<f64>().map_or_else(
+                |_| na(),
+                |p| format!("1 {} = {} {}", base, format_amount(p), quote),
+            )
+        };
+        let fee_tier = pool.fee_tier();
+
+        let overview = vec![
+            field("Pair", pool.pair().unwrap_or_else(na)),
+            field("Address", pool.id.clone()),
+            field(
+                "Fee tier",
+                fee_tier.map_or_else(na, |fee| format!("{:.2}%", fee as f64 / 10_000.0)),
+            ),
+            field(
+                "Tick spacing",
+                fee_tier
+                    .and_then(tick_spacing)
+                    .map_or_else(na, |spacing| spacing.to_string()),
+            ),
+            field(
+                "Current tick",
+                pool.current_tick().map_or_else(na, |tick| tick.to_string()),
+            ),
+            field("Price", price(&pool.token1_price, symbol0, symbol1)),
+            field("", price(&pool.token0_price, symbol1, symbol0)),
+        ];
+
+        let now = Utc::now().timestamp() as f64;
+        let volume_24h = pool.volume_since(now - 60.0 * 60.0 * 24.0);
+        let volume_7d = pool.volume_since(now - 60.0 * 60.0 * 24.0 * 7.0);
+        let fees = |volume: f64| pool.fees_on(volume).map_or_else(na, format_usd);
+
+        let activity = vec![
+            field(
+                "TVL",
+                pool.total_value_locked_usd
+                    .as_ref()
+                    .and_then(|tvl| tvl.parse::<f64>().ok())
+                    .map_or_else(na, format_usd),
+            ),
+            field("Volume 24h", format_usd(volume_24h)),
+            field("Volume 7d", format_usd(volume_7d)),
+            field("Fees 24h", fees(volume_24h)),
+            field("Fees 7d", fees(volume_7d)),
+            field("Liquidity", pool.liquidity.clone().unwrap_or_else(na)),
+        ];
+
+        let block = |title: &'static str| {
+            Block::default()
+                .title(Span::styled(
+                    title,
+                    Style::default()
+                        .fg(theme.text_primary())
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border_primary()))
+        };
+        f.render_widget(
+            Paragraph::new(overview)
+                .wrap(Wrap { trim: false })
+                .block(block("Pool")),
+            panels[0],
+        );
+        f.render_widget(
+            Paragraph::new(activity)
+                .wrap(Wrap { trim: false })
+                .block(block("Activity")),
+            panels[1],
+        );
+    }
+
+    fn render_positions(&mut self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let Some(page) = &self.page else {
+            return;
+        };
+        let block = Block::default()
+            .title("Top Positions")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_primary()));
+
+        if page.positions.is_empty() {
+            let paragraph = Paragraph::new(Line::styled(
+                "No open positions",
+                Style::default().fg(theme.text_secondary()),
+            ))
+            .alignment(Alignment::Center)
+            .block(block);
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let header = Row::new(vec![
+            Cell::from("Owner"),
+            Cell::from("ID"),
+            Cell::from("Range"),
+            Cell::from("Value"),
+        ])
+        .style(
+            Style::default()
+                .fg(theme.text_primary())
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows: Vec<Row> = page
+            .positions
+            .iter()
+            .map(|position| {
+                let owner = position.owner.as_deref().map_or("N/A".to_string(), |o| {
+                    if o.len() > 10 {
+                        format!("{}…{}", &o[..6], &o[o.len() - 4..])
+                    } else {
+                        o.to_string()
+                    }
+                });
+                let range = match position.is_in_range() {
+                    Some(true) => Span::styled("In", Style::default().fg(theme.profit())),
+                    Some(false) => Span::styled("Out", Style::default().fg(theme.loss())),
+                    None => Span::raw("N/A"),
+                };
+                Row::new(vec![
+                    Cell::from(owner),
+                    Cell::from(position.id.clone()),
+                    Cell::from(range),
+                    Cell::from(
+                        position
+                            .value_usd(page.eth_price_usd)
+                            .map_or("N/A".to_string(), format_usd),
+                    ),
+                ])
+            })
+            .collect();
+
+        let widths = [
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(5),
+            Constraint::Min(8),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .style(Style::default().fg(theme.text_normal()))
+            .row_highlight_style(
+                Style::default()
+                    .fg(theme.highlight_focused())
+                    .add_modifier(Modifier::REVERSED),
+            )
+            .highlight_symbol("> ")
+            .block(block);
+
+        f.render_stateful_widget(table, area, &mut self.state);
+    }
+}
@@ -0,0 +1,196 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    app::ChartState, models::position::Position, theme::Theme, util::format::format_amount,
+    widgets::table::render_position_chart,
+};
+
+fn format_pair_amounts(position: &Position, (amount0, amount1): (f64, f64)) -> String {
+    format!(
+        "{} {} + {} {}",
+        format_amount(amount0),
+        position.token0.symbol,
+        format_amount(amount1),
+        position.token1.symbol
+    )
+}
+
+fn format_percent(value: Option<f64>) -> String {
+    value.map_or("N/A".to_string(), |v| format!("{:.2}%", v))
+}
+
+/// Draws everything known about `position` above a chart of its pool
+pub fn render_position_info(
+    frame: &mut Frame,
+    area: Rect,
+    position: &Position,
+    eth_price_usd: f64,
+    chart: &mut ChartState,
+    theme: &Theme,
+) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(13), Constraint::Min(13)])
+        .split(area);
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(layout[0]);
+
+    let field = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(
+                format!("{:<18}", name),
+                Style::default().fg(theme.text_secondary()),
+            ),
+            Span::styled(value, Style::default().fg(theme.text_normal())),
+        ])
+    };
+    let pair_price = |price: f64| {
+        format!(
+            "{} {} per {}",
+            format_amount(price),
+            position.token1.symbol,
+            position.token0.symbol
+        )
+    };
+
+    let status = match position.is_in_range() {
+        Some(true) => Span::styled("In range", Style::default().fg(theme.profit())),
+        Some(false) => Span::styled("Out of range", Style::default().fg(theme.loss())),
+        None => Span::raw("Unknown"),
+    };
+    let fee_tier = position
+        .pool
+        .fee_tier
+        .as_ref()
+        .and_then(|fee| fee.parse::<f64>().ok())
+        .map_or("N/A".to_string(), |fee| format!("{:.2}%", fee / 10_000.0));
+    let tick_range = position
+        .tick_range()
+        .map_or("N/A".to_string(), |(lower, upper)| {
+            format!("{} to {}", lower, upper)
+        });
+    let (min_price, max_price) = match position.price_range() {
+        Some((lower, upper)) => (pair_price(lower), pair_price(upper)),
+        None => ("N/A".to_string(), "N/A".to_string()),
+    };
+    let current_price = position
+        .pool
+        .token1_price
+        .parse::<f64>()
+        .map_or("N/A".to_string(), pair_price);
+    let opened = position.opened_at().map_or("N/A".to_string(), |t| {
+        t.format("%Y-%m-%d %H:%M UTC").to_string()
+    });
+    let transaction = position
+        .transaction
+        .as_ref()
+        .and_then(|t| t.id.clone())
+        .map_or("N/A".to_string(), |id| {
+            format!("https://etherscan.io/tx/{}", id)
+        });
+
+    let overview = vec![
+        Line::from(vec![
+            Span::styled(
+                format!("{:<18}", "Status"),
+                Style::default().fg(theme.text_secondary()),
+            ),
+            status,
+        ]),
+        field("NFT token ID", position.id.clone()),
+        field("Fee tier", fee_tier),
+        field("Tick range", tick_range),
+        field("Min price", min_price),
+        field("Max price", max_price),
+        field("Current price", current_price),
+        field("Opened", opened),
+        field("Transaction", transaction),
+    ];
+
+    let parse = |s: &str| s.parse::<f64>().unwrap_or(0.0);
+    let usd = |value: Option<f64>| value.map_or("N/A".to_string(), |v| format!("${:.2}", v));
+    let deposited = (
+        parse(&position.deposited_token0),
+        parse(&position.deposited_token1),
+    );
+    let withdrawn = (
+        parse(&position.withdrawn_token0),
+        parse(&position.withdrawn_token1),
+    );
+    let current = position
+        .token_amounts()
+        .map_or("N/A".to_string(), |amounts| {
+            format_pair_amounts(position, amounts)
+        });
+    let uncollected = position
+        .uncollected_fees()
+        .map_or("N/A".to_string(), |fees| {
+            format_pair_amounts(position, fees)
+        });
+
+    let il = position.impermanent_loss(eth_price_usd);
+    let il_style = match il {
+        Some(il) if il < 0.0 => Style::default().fg(theme.loss()),
+        Some(_) => Style::default().fg(theme.profit()),
+        None => Style::default().fg(theme.text_normal()),
+    };
+
+    let amounts = vec![
+        field("Deposited", format_pair_amounts(position, deposited)),
+        field("Withdrawn", format_pair_amounts(position, withdrawn)),
+        field("Current", current),
+        field("Value", usd(position.value_usd(eth_price_usd))),
+        field(
+            "Collected fees",
+            format_pair_amounts(position, position.collected_fees()),
+        ),
+        field("Uncollected fees", uncollected),
+        field(
+            "Uncollected (USD)",
+            usd(position.uncollected_fees_usd(eth_price_usd)),
+        ),
+        Line::from(vec![
+            Span::styled(
+                format!("{:<18}", "Impermanent loss"),
+                Style::default().fg(theme.text_secondary()),
+            ),
+            Span::styled(format_percent(il), il_style),
+        ]),
+        field("Fee APR", format_percent(position.fee_apr(eth_price_usd))),
+    ];
+
+    let block = |title: String| {
+        Block::default()
+            .title(Span::styled(
+                title,
+                Style::default()
+                    .fg(theme.text_primary())
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_primary()))
+    };
+
+    frame.render_widget(
+        Paragraph::new(overview)
+            .wrap(Wrap { trim: false })
+            .block(block(format!("{} #{}", position.pair(), position.id))),
+        panels[0],
+    );
+    frame.render_widget(
+        Paragraph::new(amounts)
+            .wrap(Wrap { trim: false })
+            .block(block("Amounts & Fees".to_string())),
+        panels[1],
+    );
+
+    render_position_chart(frame, layout[1], position, chart, theme);
+}
@@ -0,0 +1,592 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    models::position::{Pool, Token},
+    network::pools::PoolPage,
+    theme::Theme,
+    util::{
+        format::{format_amount, format_usd},
+        uniswap::{
+            adjusted_price, amounts_for_liquidity, nearest_usable_tick, price_to_tick,
+            sqrt_price_x96_to_sqrt_price, tick_spacing, tick_to_price, tick_to_sqrt_price,
+        },
+    },
+    widgets::{
+        chart::{render_volume_chart, TokenChart},
+        depth::{depth_span, render_depth_chart, TickDepth},
+        spinner::Spinner,
+    },
+};
+
+/// Days of hourly volume averaged into the expected daily volume
+const VOLUME_DAYS: f64 = 7.0;
+
+/// An input of the estimator form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EstimatorField {
+    Lower,
+    Upper,
+    Deposit,
+}
+
+impl EstimatorField {
+    pub const ALL: [EstimatorField; 3] = [
+        EstimatorField::Lower,
+        EstimatorField::Upper,
+        EstimatorField::Deposit,
+    ];
+
+    fn index(self) -> usize {
+        EstimatorField::ALL
+            .iter()
+            .position(|field| *field == self)
+            .unwrap_or(0)
+    }
+
+    pub fn next(self) -> EstimatorField {
+        EstimatorField::ALL[(self.index() + 1) % EstimatorField::ALL.len()]
+    }
+
+    pub fn previous(self) -> EstimatorField {
+        let len = EstimatorField::ALL.len();
+        EstimatorField::ALL[(self.index() + len - 1) % len]
+    }
+}
+
+/// The range and deposit the estimate is made for
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RangeInput {
+    tick_lower: i32,
+    tick_upper: i32,
+    deposit_usd: f64,
+}
+
+/// What a position over the entered range would hold and earn
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    /// Prices of token0 in token1 at the bounds, after snapping to usable ticks
+    pub price_lower: f64,
+    pub price_upper: f64,
+    pub liquidity: f64,
+    pub amount0: f64,
+    pub amount1: f64,
+    pub value0_usd: f64,
+    pub value1_usd: f64,
+    pub in_range: bool,
+    /// Fraction of the liquidity at the current price that would be the position's
+    pub share: f64,
+    /// Fraction of the liquidity over the whole range, weighted by tick,
+    /// once the pool's ticks are loaded
+    pub range_share: Option<f64>,
+    pub daily_volume_usd: f64,
+    pub daily_fees_usd: f64,
+    /// Annualised daily fees in percent of the deposit
+    pub fee_apr: f64,
+}
+
+/// Form, tick liquidity and results of the range fee estimator
+pub struct RangeEstimator {
+    /// The pool estimated for, taken from the pool explorer
+    pub page: Option<PoolPage>,
+    /// The chain of the pool, `None` for the configured one
+    pub chain: Option<String>,
+    /// Typed prices or percentages away from the current price
+    pub lower: String,
+    pub upper: String,
+    /// Typed USD amount
+    pub deposit: String,
+    pub field: EstimatorField,
+    /// The form has focus
+    pub editing: bool,
+    /// Why the form could not be read
+    pub error: Option<String>,
+    range: Option<RangeInput>,
+    /// Liquidity around the range and the current price
+    pub depth: TickDepth,
+}
+
+impl RangeEstimator {
+    pub fn new() -> Self {
+        Self {
+            page: None,
+            chain: None,
+            lower: "-10%".to_string(),
+            upper: "+10%".to_string(),
+            deposit: "1000".to_string(),
+            field: EstimatorField::Lower,
+            editing: false,
+            error: None,
+            range: None,
+            depth: TickDepth::new(),
+        }
+    }
+
+    /// Starts estimating for the pool of `page`, keeping the typed range
+    pub fn open(&mut self, page: PoolPage, chain: Option<String>) {
+        self.page = Some(page);
+        self.chain = chain;
+        self.range = None;
+        self.error = None;
+    }
+
+    pub fn start_editing(&mut self) {
+        self.editing = true;
+        self.error = None;
+    }
+
+    /// The input of the focused field
+    pub fn input(&mut self) -> &mut String {
+        match self.field {
+            EstimatorField::Lower => &mut self.lower,
+            EstimatorField::Upper => &mut self.upper,
+            EstimatorField::Deposit => &mut self.deposit,
+        }
+    }
+
+    /// Reads the form, returning the pool and tick span whose liquidity has to
+    /// be fetched if it is not loaded yet
+    pub fn submit(&mut self) -> Option<(String, (i32, i32))> {
+        let page = self.page.as_ref()?;
+        let range = match self.read_form(&page.pool) {
+            Ok(range) => range,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return None;
+            }
+        };
+        self.editing = false;
+        self.error = None;
+        self.range = Some(range);
+
+        let span = depth_span(&page.pool, Some((range.tick_lower, range.tick_upper)))?;
+        self.depth
+            .request(&page.pool.id, span)
+            .then(|| (page.pool.id.clone(), span))
+    }
+
+    fn read_form(&self, pool: &Pool) -> Result<RangeInput> {
+        let (token0, token1) = tokens(pool)?;
+        let current: f64 = pool
+            .token1_price
+            .parse()
+            .map_err(|_| anyhow!("the pool has no price yet"))?;
+        let spacing = pool
+            .fee_tier()
+            .and_then(tick_spacing)
+            .ok_or_else(|| anyhow!("the pool's fee tier is unknown"))?;
+
+        let lower = parse_bound(&self.lower, current, EstimatorField::Lower)?;
+        let upper = parse_bound(&self.upper, current, EstimatorField::Upper)?;
+        if lower >= upper {
+            return Err(anyhow!("the lower price must be below the upper price"));
+        }
+        let deposit_usd = self
+            .deposit
+            .trim()
+            .trim_start_matches('$')
+            .replace(',', "")
+            .parse::<f64>()
+            .ok()
+            .filter(|deposit| *deposit > 0.0)
+            .ok_or_else(|| anyhow!("'{}' is not a USD amount", self.deposit))?;
+
+        // Undo the decimals adjustment to get back to the raw price ticks are based on
+        let scale = adjusted_price(1.0, token0.decimals(), token1.decimals());
+        let tick = |price: f64| nearest_usable_tick(price_to_tick(price / scale), spacing);
+        let tick_lower = tick(lower);
+        let mut tick_upper = tick(upper);
+        if tick_upper <= tick_lower {
+            tick_upper = tick_lower + spacing;
+        }
+
+        Ok(RangeInput {
+            tick_lower,
+            tick_upper,
+            deposit_usd,
+        })
+    }
+
+    /// Works out the position for the submitted form
+    pub fn estimate(&self) -> Result<Estimate> {
+        let page = self
+            .page
+            .as_ref()
+            .ok_or_else(|| anyhow!("there is no pool to estimate for"))?;
+        let range = self
+            .range
+            .ok_or_else(|| anyhow!("enter a range and a deposit"))?;
+        let pool = &page.pool;
+        let (token0, token1) = tokens(pool)?;
+        let sqrt_price = pool
+            .sqrt_price
+            .as_deref()
+            .and_then(sqrt_price_x96_to_sqrt_price)
+            .ok_or_else(|| anyhow!("the pool has no price yet"))?;
+        let current_tick = pool
+            .current_tick()
+            .ok_or_else(|| anyhow!("the pool has no price yet"))?;
+        let usd0 = token0.price_usd(page.eth_price_usd);
+        let usd1 = token1.price_usd(page.eth_price_usd);
+        if usd0 <= 0.0 || usd1 <= 0.0 {
+            return Err(anyhow!("the USD prices of the pool's tokens are unknown"));
+        }
+
+        // Amounts are linear in liquidity, so price one unit and scale it to the deposit
+        let (decimals0, decimals1) = (token0.decimals() as i32, token1.decimals() as i32);
+        let (raw0, raw1) = amounts_for_liquidity(
+            1.0,
+            sqrt_price,
+            tick_to_sqrt_price(range.tick_lower),
+            tick_to_sqrt_price(range.tick_upper),
+        );
+        let unit0 = raw0 / 10f64.powi(decimals0);
+        let unit1 = raw1 / 10f64.powi(decimals1);
+        let liquidity = range.deposit_usd / (unit0 * usd0 + unit1 * usd1);
+        let (amount0, amount1) = (unit0 * liquidity, unit1 * liquidity);
+
+        let pool_liquidity: f64 = pool
+            .liquidity
+            .as_deref()
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(0.0);
+        let in_range = range.tick_lower <= current_tick && current_tick < range.tick_upper;
+        let share = if in_range {
+            liquidity / (liquidity + pool_liquidity)
+        } else {
+            0.0
+        };
+        let range_share = self
+            .depth
+            .segments(pool)
+            .filter(|_| self.depth.covers((range.tick_lower, range.tick_upper)))
+            .map(|segments| {
+                average_share(&segments, (range.tick_lower, range.tick_upper), liquidity)
+            });
+
+        let now = Utc::now().timestamp() as f64;
+        let daily_volume_usd =
+            pool.volume_since(now - 60.0 * 60.0 * 24.0 * VOLUME_DAYS) / VOLUME_DAYS;
+        let daily_fees_usd = pool.fees_on(daily_volume_usd).unwrap_or(0.0) * share;
+        let price =
+            |tick: i32| adjusted_price(tick_to_price(tick), token0.decimals(), token1.decimals());
+
+        Ok(Estimate {
+            tick_lower: range.tick_lower,
+            tick_upper: range.tick_upper,
+            price_lower: price(range.tick_lower),
+            price_upper: price(range.tick_upper),
+            liquidity,
+            amount0,
+            amount1,
+            value0_usd: amount0 * usd0,
+            value1_usd: amount1 * usd1,
+            in_range,
+            share,
+            range_share,
+            daily_volume_usd,
+            daily_fees_usd,
+            fee_apr: daily_fees_usd * 365.0 / range.deposit_usd * 100.0,
+        })
+    }
+
+    /// Draws the estimator, marking `positions`, the tick ranges of our own
+    /// positions in the pool, in the liquidity chart
+    pub fn render(&self, f: &mut Frame, area: Rect, positions: &[(i32, i32)], theme: &Theme) {
+        let Some(page) = &self.page else {
+            let paragraph = Paragraph::new(Line::styled(
+                "Open a pool in the pool explorer to estimate its fees",
+                Style::default().fg(theme.text_secondary()),
+            ))
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.border_primary())),
+            );
+            f.render_widget(paragraph, area);
+            return;
+        };
+        let pool = &page.pool;
+        let (symbol0, symbol1) = match tokens(pool) {
+            Ok((token0, token1)) => (token0.symbol.as_str(), token1.symbol.as_str()),
+            Err(_) => ("token0", "token1"),
+        };
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+        let body = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(layout[1]);
+
+        self.render_form(f, layout[0], symbol0, symbol1, theme);
+        let estimate = self.estimate();
+        self.render_results(f, body[0], &estimate, symbol0, symbol1, theme);
+
+        let mut chart = TokenChart::new();
+        chart.token0_ticker = format!("{} in {}", symbol0, symbol1);
+        chart.is_hourly = true;
+        // The week the fees are estimated from, however much history is loaded
+        let week_ago = Utc::now().timestamp() as f64 - 60.0 * 60.0 * 24.0 * VOLUME_DAYS;
+        let prices: Vec<(f64, f64)> = pool
+            .hours_since(week_ago)
+            .filter_map(|d| Some((d.period_start_unix, d.token1_price.as_ref()?.parse().ok()?)))
+            .collect();
+        chart.update_with_price_data(&prices, &[]);
+        if let Ok(estimate) = &estimate {
+            chart.add_bound("Upper", estimate.price_upper);
+            chart.add_bound("Lower", estimate.price_lower);
+        }
+        let charts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(body[1]);
+        f.render_widget(render_volume_chart(&chart, theme), charts[0]);
+
+        let range = estimate
+            .as_ref()
+            .ok()
+            .map(|estimate| (estimate.tick_lower, estimate.tick_upper));
+        if let Some(span) = depth_span(pool, range) {
+            let ranges: Vec<(i32, i32)> =
+                range.into_iter().chain(positions.iter().copied()).collect();
+            render_depth_chart(f, charts[1], pool, &self.depth, span, &ranges, theme);
+        }
+    }
+
+    fn render_form(&self, f: &mut Frame, area: Rect, symbol0: &str, symbol1: &str, theme: &Theme) {
+        let boxes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(35),
+                Constraint::Percentage(35),
+                Constraint::Percentage(30),
+            ])
+            .split(area);
+        let unit = format!("{} per {}", symbol1, symbol0);
+
+        for (field, area) in EstimatorField::ALL.into_iter().zip(boxes.iter()) {
+            let (title, input) = match field {
+                EstimatorField::Lower => (format!("Lower price, {} or -%", unit), &self.lower),
+                EstimatorField::Upper => (format!("Upper price, {} or +%", unit), &self.upper),
+                EstimatorField::Deposit => ("Deposit (USD)".to_string(), &self.deposit),
+            };
+            let focused = self.editing && self.field == field;
+            let border = if focused {
+                theme.highlight_focused()
+            } else {
+                theme.highlight_unfocused()
+            };
+            let mut spans = vec![Span::styled(
+                input.clone(),
+                Style::default().fg(theme.text_normal()),
+            )];
+            if focused {
+                spans.push(Span::styled(
+                    "█",
+                    Style::default().fg(theme.highlight_focused()),
+                ));
+            }
+            let paragraph = Paragraph::new(Line::from(spans)).block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(border)),
+            );
+            f.render_widget(paragraph, *area);
+        }
+    }
+
+    fn render_results(
+        &self,
+        f: &mut Frame,
+        area: Rect,
+        estimate: &Result<Estimate>,
+        symbol0: &str,
+        symbol1: &str,
+        theme: &Theme,
+    ) {
+        let title = match self.page.as_ref().and_then(|page| page.pool.fee_tier()) {
+            Some(fee) => format!(
+                "Estimate for {}/{} {}%",
+                symbol0,
+                symbol1,
+                fee as f64 / 10_000.0
+            ),
+            None => format!("Estimate for {}/{}", symbol0, symbol1),
+        };
+        let block = Block::default()
+            .title(Span::styled(
+                title,
+                Style::default()
+                    .fg(theme.text_primary())
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_primary()));
+
+        let field = |name: &str, value: String| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<16}", name),
+                    Style::default().fg(theme.text_secondary()),
+                ),
+                Span::styled(value, Style::default().fg(theme.text_normal())),
+            ])
+        };
+        let percent = |share: f64| format!("{:.4}%", share * 100.0);
+
+        let mut lines = Vec::new();
+        if let Some(error) = &self.error {
+            lines.push(Line::styled(
+                error.clone(),
+                Style::default().fg(theme.loss()),
+            ));
+            lines.push(Line::raw(""));
+        }
+        match estimate {
+            Ok(estimate) => {
+                let status = if estimate.in_range {
+                    Span::styled("In range", Style::default().fg(theme.profit()))
+                } else {
+                    Span::styled("Out of range", Style::default().fg(theme.loss()))
+                };
+                let range_share = match (estimate.range_share, self.depth.is_loading()) {
+                    (Some(share), _) => percent(share),
+                    (None, true) => format!("{} Loading ticks", Spinner::default().to_string()),
+                    (None, false) => "N/A".to_string(),
+                };
+                lines.extend([
+                    field(
+                        "Range",
+                        format!(
+                            "{} - {}",
+                            format_amount(estimate.price_lower),
+                            format_amount(estimate.price_upper)
+                        ),
+                    ),
+                    field(
+                        "Ticks",
+                        format!("{} to {}", estimate.tick_lower, estimate.tick_upper),
+                    ),
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{:<16}", "Status"),
+                            Style::default().fg(theme.text_secondary()),
+                        ),
+                        status,
+                    ]),
+                    field("Liquidity", format!("{:.0}", estimate.liquidity)),
+                    field(
+                        symbol0,
+                        format!(
+                            "{} ({})",
+                            format_amount(estimate.amount0),
+                            format_usd(estimate.value0_usd)
+                        ),
+                    ),
+                    field(
+                        symbol1,
+                        format!(
+                            "{} ({})",
+                            format_amount(estimate.amount1),
+                            format_usd(estimate.value1_usd)
+                        ),
+                    ),
+                    field("Share now", percent(estimate.share)),
+                    field("Share of range", range_share),
+                    field("Daily volume", format_usd(estimate.daily_volume_usd)),
+                    field("Daily fees", format_usd(estimate.daily_fees_usd)),
+                    field("Fee APR", format!("{:.2}%", estimate.fee_apr)),
+                    Line::raw(""),
+                    Line::styled(
+                        format!(
+                            "Fees assume the last {} days of volume and that the price stays put",
+                            VOLUME_DAYS
+                        ),
+                        Style::default().fg(theme.text_secondary()),
+                    ),
+                ]);
+            }
+            Err(e) if self.error.is_none() => {
+                lines.push(Line::styled(
+                    e.to_string(),
+                    Style::default().fg(theme.text_secondary()),
+                ));
+            }
+            Err(_) => {}
+        }
+
+        f.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(block),
+            area,
+        );
+    }
+}
+
+/// Both tokens of `pool`, which are only known when it was looked up on its own
+fn tokens(pool: &Pool) -> Result<(&Token, &Token)> {
+    match (&pool.token0, &pool.token1) {
+        (Some(token0), Some(token1)) => Ok((token0, token1)),
+        _ => Err(anyhow!("the pool's tokens are unknown")),
+    }
+}
+
+/// Reads a bound as a price, or as a percentage away from `current` such as
+/// `-5%`. Unsigned percentages go down for the lower bound and up for the upper.
+fn parse_bound(input: &str, current: f64, field: EstimatorField) -> Result<f64> {
+    let input = input.trim();
+    let invalid = || anyhow!("'{}' is not a price or a percentage", input);
+    let price = match input.strip_suffix('%') {
+        Some(percent) => {
+            let (sign, percent) = match percent.chars().next() {
+                Some('+') => (1.0, &percent[1..]),
+                Some('-') => (-1.0, &percent[1..]),
+                Some('±') => (0.0, &percent['±'.len_utf8()..]),
+                _ => (0.0, percent),
+            };
+            let sign = if sign != 0.0 {
+                sign
+            } else if field == EstimatorField::Lower {
+                -1.0
+            } else {
+                1.0
+            };
+            let percent = percent.trim().parse::<f64>().map_err(|_| invalid())?;
+            current * (1.0 + sign * percent / 100.0)
+        }
+        None => input.parse::<f64>().map_err(|_| invalid())?,
+    };
+    if price <= 0.0 || !price.is_finite() {
+        return Err(anyhow!("'{}' leaves the price at or below zero", input));
+    }
+    Ok(price)
+}
+
+/// The position's share of the liquidity over `[lower, upper)`, averaged over
+/// every tick of the range
+fn average_share(segments: &[(i32, f64)], (lower, upper): (i32, i32), liquidity: f64) -> f64 {
+    let mut weighted = 0.0;
+    for (i, &(from, pool_liquidity)) in segments.iter().enumerate() {
+        let to = segments.get(i + 1).map_or(upper, |(next, _)| *next);
+        let width = to.min(upper) - from.max(lower);
+        if width > 0 {
+            weighted += width as f64 * liquidity / (liquidity + pool_liquidity);
+        }
+    }
+    weighted / (upper - lower) as f64
+}
@@ -16,6 +16,7 @@ pub fn render_search_block<'a>(
         return None;
     };
 
+    let theme = app.theme;
     let searchbar_block;
 
     if app.search_state.is_searching {
@@ -25,16 +26,16 @@ pub fn render_search_block<'a>(
                 "{} Searching for {search_query}",
                 Spinner::default().to_string()
             ))
-            .border_style(Style::default().fg(Color::Green))
+            .border_style(Style::default().fg(theme.highlight_focused()))
             .borders(Borders::ALL)
             .border_type(BorderType::Plain);
     } else {
         searchbar_block = Block::default()
             .border_style(Style::default().fg(
                 if let ActiveBlock::SearchBar = app.get_current_route().get_active_block() {
-                    Color::Green
+                    theme.highlight_focused()
                 } else {
-                    Color::White
+                    theme.highlight_unfocused()
                 },
             ))
             .title(format!(
@@ -49,7 +50,7 @@ pub fn render_search_block<'a>(
     }
 
     let input = Paragraph::new(app.search_state.current_search_query.as_str())
-        .style(Style::default().fg(Color::White))
+        .style(Style::default().fg(theme.text_normal()))
         .block(searchbar_block);
 
     Some((input, searchbar))
@@ -1,25 +1,37 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, BorderType, Borders, Cell, Row, Table, TableState},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
     Frame,
 };
 
 use crate::{
-    app::{App, ChartView},
-    routes::ActiveBlock,
-    widgets::chart::{render_volume_chart, TokenChart},
+    app::ChartState,
+    models::position::Position,
+    theme::Theme,
+    widgets::{
+        chart::{render_pool_chart, RangeOverlay, TokenChart},
+        columns::{compare, CellValue, Column, Filter, SortOrder},
+    },
 };
 
-use crate::app::ChartTimeRange;
-use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
-
 pub struct StatefulTable {
     pub state: TableState,
-    pub items: Vec<Vec<String>>,
+    /// Cells of every position, in `Column::ALL` order
+    pub items: Vec<Vec<CellValue>>,
+    /// Indices into `items` of the rows shown, in display order
+    pub rows: Vec<usize>,
+    /// The columns shown, from the `[table]` config section
+    pub columns: Vec<Column>,
+    pub sort: Option<(Column, SortOrder)>,
+    pub filter: Filter,
+    /// The query `filter` was parsed from
+    pub filter_query: String,
+    /// The query being typed, while the filter is edited
+    pub filter_input: Option<String>,
+    pub filter_error: Option<String>,
     pub charts: Vec<TokenChart>,
-    pub token_day_datas: Vec<Vec<(f64, f64)>>,
 }
 
 impl StatefulTable {
@@ -29,89 +41,41 @@ impl StatefulTable {
         StatefulTable {
             state,
             items: Vec::new(),
+            rows: Vec::new(),
+            columns: Column::DEFAULT.to_vec(),
+            sort: None,
+            filter: Filter::default(),
+            filter_query: String::new(),
+            filter_input: None,
+            filter_error: None,
             charts: Vec::new(),
-            token_day_datas: Vec::new(),
         }
     }
 
     pub fn update_positions(
         &mut self,
         positions: &[crate::models::position::Position],
-        token_day_datas: &[Vec<(f64, f64)>],
+        eth_price_usd: f64,
     ) {
         self.items = positions
             .iter()
             .map(|pos| {
-                let age_str = pos.transaction.as_ref().map_or("N/A".to_string(), |t| {
-                    if let Ok(ts) = t.timestamp.parse::<i64>() {
-                        let dt = NaiveDateTime::from_timestamp_opt(ts, 0)
-                            .unwrap_or_else(|| NaiveDateTime::from_timestamp_opt(0, 0).unwrap());
-                        let now = Utc::now().naive_utc();
-                        let duration = now - dt;
-                        if duration.num_days() > 0 {
-                            format!("{}d ago", duration.num_days())
-                        } else if duration.num_hours() > 0 {
-                            format!("{}h ago", duration.num_hours())
-                        } else if duration.num_minutes() > 0 {
-                            format!("{}m ago", duration.num_minutes())
-                        } else {
-                            "just now".to_string()
-                        }
-                    } else {
-                        "N/A".to_string()
-                    }
-                });
-                vec![
-                    format!("{}/{}", pos.token0.symbol, pos.token1.symbol),
-                    format!(
-                        "${:.2}",
-                        pos.pool
-                            .pool_hour_data
-                            .iter()
-                            .last()
-                            .and_then(|d| d.volume_usd.parse::<f64>().ok())
-                            .unwrap_or(0.0)
-                    ),
-                    if pos.liquidity.parse::<f64>().unwrap_or(0.0) > 0.0 {
-                        "✓".to_string()
-                    } else {
-                        "X".to_string()
-                    },
-                    age_str,
-                    {
-                        let deposited0 = pos.deposited_token0.parse::<f64>().unwrap_or(0.0);
-                        let deposited1 = pos.deposited_token1.parse::<f64>().unwrap_or(0.0);
-                        let withdrawn0 = pos.withdrawn_token0.parse::<f64>().unwrap_or(0.0);
-                        let withdrawn1 = pos.withdrawn_token1.parse::<f64>().unwrap_or(0.0);
-
-                        let last = pos.pool.pool_hour_data.last();
-                        let token0_price = last
-                            .and_then(|d| d.token0_price.as_ref())
-                            .or_else(|| Some(&pos.pool.token0_price))
-                            .and_then(|p| p.parse::<f64>().ok())
-                            .unwrap_or(0.0);
-                        let token1_price = last
-                            .and_then(|d| d.token1_price.as_ref())
-                            .or_else(|| Some(&pos.pool.token1_price))
-                            .and_then(|p| p.parse::<f64>().ok())
-                            .unwrap_or(0.0);
-
-                        let fees0 = (withdrawn0 - deposited0).max(0.0) * token0_price;
-                        let fees1 = (withdrawn1 - deposited1).max(0.0) * token1_price;
-                        format!("${:.2}", fees0 + fees1)
-                    },
-                ]
+                Column::ALL
+                    .iter()
+                    .map(|column| column.extract(pos, eth_price_usd))
+                    .collect()
             })
             .collect();
+        self.apply_view();
 
-        // Create charts for each position
+        // Create charts for each position over the last day of its pool's history
+        let day_ago = chrono::Utc::now().timestamp() as f64 - 60.0 * 60.0 * 24.0;
         self.charts = positions
             .iter()
             .map(|pos| {
                 let mut token0_data: Vec<(f64, f64)> = pos
                     .pool
-                    .pool_hour_data
-                    .iter()
+                    .hours_since(day_ago)
                     .map(|d| {
                         (
                             d.period_start_unix,
@@ -127,8 +91,7 @@ impl StatefulTable {
 
                 let mut token1_data: Vec<(f64, f64)> = pos
                     .pool
-                    .pool_hour_data
-                    .iter()
+                    .hours_since(day_ago)
                     .map(|d| {
                         (
                             d.period_start_unix,
@@ -160,15 +123,87 @@ impl StatefulTable {
                 chart
             })
             .collect();
+    }
+
+    /// Recomputes which rows are shown and in what order, keeping the
+    /// selected position selected where possible
+    pub fn apply_view(&mut self) {
+        let selected = self.selected_position();
+
+        let mut rows: Vec<usize> = (0..self.items.len())
+            .filter(|i| self.filter.matches(&self.items[*i]))
+            .collect();
+        if let Some((column, order)) = self.sort {
+            let idx = Column::ALL.iter().position(|c| *c == column).unwrap_or(0);
+            rows.sort_by(|a, b| compare(&self.items[*a][idx].key, &self.items[*b][idx].key, order));
+        }
+        self.rows = rows;
+
+        let i = selected
+            .and_then(|selected| self.rows.iter().position(|row| *row == selected))
+            .unwrap_or(0);
+        self.state.select(Some(i));
+    }
 
-        // Store tokenDayDatas for each position
-        self.token_day_datas = token_day_datas.to_vec();
+    /// The index of the selected position, if any row is shown
+    pub fn selected_position(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|i| self.rows.get(i).copied())
+    }
+
+    /// Sorts by the next visible column, descending first
+    pub fn next_sort_column(&mut self) {
+        let next = match self.sort {
+            Some((column, _)) => self
+                .columns
+                .iter()
+                .position(|c| *c == column)
+                .and_then(|i| self.columns.get(i + 1)),
+            None => self.columns.first(),
+        };
+        self.sort = next.map(|column| (*column, SortOrder::Descending));
+        self.apply_view();
+    }
+
+    pub fn reverse_sort(&mut self) {
+        if let Some((column, order)) = self.sort {
+            self.sort = Some((column, order.reversed()));
+            self.apply_view();
+        }
+    }
+
+    pub fn start_filter(&mut self) {
+        self.filter_input = Some(self.filter_query.clone());
+    }
+
+    pub fn cancel_filter(&mut self) {
+        self.filter_input = None;
+    }
+
+    /// Applies the typed query, keeping the previous filter if it is invalid
+    pub fn submit_filter(&mut self) {
+        let Some(query) = self.filter_input.take() else {
+            return;
+        };
+        match query.parse::<Filter>() {
+            Ok(filter) => {
+                self.filter = filter;
+                self.filter_query = query;
+                self.filter_error = None;
+                self.apply_view();
+            }
+            Err(e) => self.filter_error = Some(e.to_string()),
+        }
     }
 
     pub fn next(&mut self) {
+        if self.rows.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
-                if i >= self.items.len() - 1 {
+                if i >= self.rows.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -180,10 +215,13 @@ impl StatefulTable {
     }
 
     pub fn previous(&mut self) {
+        if self.rows.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.items.len() - 1
+                    self.rows.len() - 1
                 } else {
                     i - 1
                 }
@@ -192,297 +230,135 @@ impl StatefulTable {
         };
         self.state.select(Some(i));
     }
+
+    pub fn first(&mut self) {
+        self.state.select(Some(0));
+    }
+
+    pub fn last(&mut self) {
+        self.state.select(Some(self.rows.len().saturating_sub(1)));
+    }
+
+    /// Moves the selection by `rows` without wrapping around
+    pub fn scroll_by(&mut self, rows: isize) {
+        let last = self.rows.len().saturating_sub(1);
+        let i = self
+            .state
+            .selected()
+            .unwrap_or(0)
+            .saturating_add_signed(rows);
+        self.state.select(Some(i.min(last)));
+    }
 }
 
-pub fn render_table<'a>(
+pub fn render_table(
     frame: &mut Frame,
     table: &mut StatefulTable,
     area: Rect,
-    positions: &'a Vec<crate::models::position::Position>,
-    chart_time_range: ChartTimeRange,
-    chart_view: ChartView,
+    positions: &[Position],
+    chart: &mut ChartState,
+    theme: &Theme,
 ) {
     // Split the area into table, chart, and tab bar sections
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Min(10),   // Table
-                Constraint::Min(10),   // Chart
-                Constraint::Length(3), // Tab bar
+                Constraint::Min(10), // Table
+                Constraint::Min(13), // Chart and tab bar
             ]
             .as_ref(),
         )
         .split(area);
 
-    let table_widget = Table::new(
-        table.items.iter().map(|item| {
-            let height = item
-                .iter()
-                .map(|content| content.lines().count())
-                .max()
-                .unwrap_or(1);
-            Row::new(item.clone()).height(height as u16)
-        }),
-        &[
-            Constraint::Length(20),
-            Constraint::Length(20),
-            Constraint::Length(20),
-            Constraint::Length(20),
-            Constraint::Length(20),
-        ],
-    )
-    .header(
-        Row::new(vec!["Pool", "Token0", "Token1", "Value", "Fees"])
-            .style(Style::default().fg(Color::Yellow))
-            .height(1)
-            .bottom_margin(5),
-    )
-    .block(Block::default().title("My Positions").borders(Borders::ALL))
-    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
-
-    frame.render_stateful_widget(table_widget, chunks[0], &mut table.state);
+    let header = Row::new(table.columns.iter().map(|column| {
+        let title = match table.sort {
+            Some((sorted, order)) if sorted == *column => {
+                format!("{} {}", column.header(), order.arrow())
+            }
+            _ => column.header().to_string(),
+        };
+        Cell::from(Line::from(title).alignment(column.alignment()))
+    }))
+    .style(Style::default().fg(theme.text_primary()))
+    .height(1)
+    .bottom_margin(1);
 
-    // Render the chart for the selected position, filtered by time range and chart view
-    if let Some(selected) = table.state.selected() {
-        if let Some(pos) = positions.get(selected) {
-            let now = chrono::Utc::now().timestamp() as f64;
-            let (token0_data, token1_data, volume_data, is_hourly, data_warning) =
-                match chart_time_range {
-                    ChartTimeRange::OneDay | ChartTimeRange::OneWeek => {
-                        let (cutoff, n_points, warn_age) =
-                            if chart_time_range == ChartTimeRange::OneDay {
-                                (now - 60.0 * 60.0 * 24.0, 24, 60.0 * 60.0 * 24.0)
-                            } else {
-                                (
-                                    now - 60.0 * 60.0 * 24.0 * 7.0,
-                                    168,
-                                    60.0 * 60.0 * 24.0 * 7.0,
-                                )
-                            };
-                        let mut t0: Vec<_> = pos
-                            .pool
-                            .pool_hour_data
-                            .iter()
-                            .filter(|d| d.period_start_unix as f64 >= cutoff)
-                            .map(|d| {
-                                (
-                                    d.period_start_unix as f64,
-                                    d.token0_price
-                                        .as_ref()
-                                        .unwrap_or(&"0.0".to_string())
-                                        .parse::<f64>()
-                                        .unwrap_or(0.0),
-                                )
-                            })
-                            .collect();
-                        let mut t1: Vec<_> = pos
-                            .pool
-                            .pool_hour_data
-                            .iter()
-                            .filter(|d| d.period_start_unix as f64 >= cutoff)
-                            .map(|d| {
-                                (
-                                    d.period_start_unix as f64,
-                                    d.token1_price
-                                        .as_ref()
-                                        .unwrap_or(&"0.0".to_string())
-                                        .parse::<f64>()
-                                        .unwrap_or(0.0),
-                                )
-                            })
-                            .collect();
-                        let mut v: Vec<_> = pos
-                            .pool
-                            .pool_hour_data
-                            .iter()
-                            .filter(|d| d.period_start_unix as f64 >= cutoff)
-                            .map(|d| {
-                                (
-                                    d.period_start_unix as f64,
-                                    d.volume_usd.parse::<f64>().unwrap_or(0.0),
-                                )
-                            })
-                            .collect();
-                        // Fallback: if no data in cutoff, use most recent N points
-                        let mut fallback = false;
-                        if t0.is_empty() || t1.is_empty() {
-                            fallback = true;
-                            let all_t0: Vec<_> = pos
-                                .pool
-                                .pool_hour_data
-                                .iter()
-                                .rev()
-                                .take(n_points)
-                                .map(|d| {
-                                    (
-                                        d.period_start_unix as f64,
-                                        d.token0_price
-                                            .as_ref()
-                                            .unwrap_or(&"0.0".to_string())
-                                            .parse::<f64>()
-                                            .unwrap_or(0.0),
-                                    )
-                                })
-                                .collect();
-                            let all_t1: Vec<_> = pos
-                                .pool
-                                .pool_hour_data
-                                .iter()
-                                .rev()
-                                .take(n_points)
-                                .map(|d| {
-                                    (
-                                        d.period_start_unix as f64,
-                                        d.token1_price
-                                            .as_ref()
-                                            .unwrap_or(&"0.0".to_string())
-                                            .parse::<f64>()
-                                            .unwrap_or(0.0),
-                                    )
-                                })
-                                .collect();
-                            let all_v: Vec<_> = pos
-                                .pool
-                                .pool_hour_data
-                                .iter()
-                                .rev()
-                                .take(n_points)
-                                .map(|d| {
-                                    (
-                                        d.period_start_unix as f64,
-                                        d.volume_usd.parse::<f64>().unwrap_or(0.0),
-                                    )
-                                })
-                                .collect();
-                            t0 = all_t0.into_iter().collect::<Vec<_>>();
-                            t1 = all_t1.into_iter().collect::<Vec<_>>();
-                            v = all_v.into_iter().collect::<Vec<_>>();
-                            t0.reverse();
-                            t1.reverse();
-                            v.reverse();
-                        }
-                        // Show warning if most recent data is too old
-                        let data_warning =
-                            t0.last().map_or(true, |(ts, _)| now - *ts > warn_age) || fallback;
-                        (t0, t1, v, true, data_warning)
-                    }
-                    _ => {
-                        let cutoff = match chart_time_range {
-                            ChartTimeRange::OneMonth => now - 60.0 * 60.0 * 24.0 * 30.0,
-                            ChartTimeRange::ThreeMonths => now - 60.0 * 60.0 * 24.0 * 90.0,
-                            ChartTimeRange::SixMonths => now - 60.0 * 60.0 * 24.0 * 180.0,
-                            ChartTimeRange::OneYear => now - 60.0 * 60.0 * 24.0 * 365.0,
-                            ChartTimeRange::FiveYears => now - 60.0 * 60.0 * 24.0 * 365.0 * 5.0,
-                            _ => now,
-                        };
-                        let mut t0: Vec<_> = pos
-                            .pool
-                            .pool_day_datas
-                            .iter()
-                            .filter(|d| d.date as f64 >= cutoff)
-                            .map(|d| (d.date as f64, d.token0Price.parse::<f64>().unwrap_or(0.0)))
-                            .collect();
-                        t0.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-                        let mut t1: Vec<_> = pos
-                            .pool
-                            .pool_day_datas
-                            .iter()
-                            .filter(|d| d.date as f64 >= cutoff)
-                            .map(|d| (d.date as f64, d.token1Price.parse::<f64>().unwrap_or(0.0)))
-                            .collect();
-                        t1.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-                        let v = table
-                            .token_day_datas
-                            .get(selected)
-                            .cloned()
-                            .unwrap_or_default();
-                        // Show warning if most recent data is too old (e.g., last point older than 7 days)
-                        let data_warning = t0
-                            .last()
-                            .map_or(true, |(ts, _)| now - *ts > 60.0 * 60.0 * 24.0 * 7.0);
-                        (t0, t1, v, false, data_warning)
-                    }
-                };
+    let rows = table.rows.iter().map(|i| {
+        let cells = &table.items[*i];
+        Row::new(table.columns.iter().map(|column| {
+            let text = Column::ALL
+                .iter()
+                .position(|c| c == column)
+                .map_or("", |idx| cells[idx].text.as_str());
+            Cell::from(Line::from(text.to_string()).alignment(column.alignment()))
+        }))
+    });
 
-            let no_data = token0_data.is_empty() && token1_data.is_empty();
-            if no_data {
-                use ratatui::widgets::{Paragraph, Wrap};
-                let warning = Paragraph::new(vec![
-                    ratatui::text::Line::from(Span::raw("No chart data available for the selected range. Try another range or check your data source."))
-                ])
-                .block(Block::default().title("Warning").borders(Borders::ALL))
-                .wrap(Wrap { trim: true });
-                frame.render_widget(warning, chunks[1]);
-            } else {
-                let mut chart = TokenChart::new();
-                chart.token0_ticker = pos.token0.symbol.clone();
-                chart.token1_ticker = pos.token1.symbol.clone();
-                chart.is_hourly = is_hourly;
-                match chart_view {
-                    crate::app::ChartView::Price => {
-                        chart.update_with_price_data(&token0_data, &token1_data);
-                        let mut price_chart = render_volume_chart(&chart);
-                        if data_warning {
-                            use ratatui::widgets::{Paragraph, Wrap};
-                            let warning = Paragraph::new(vec![ratatui::text::Line::from(
-                                Span::raw("Warning: Data may be outdated or not recent!"),
-                            )])
-                            .block(Block::default().title("Data Warning").borders(Borders::ALL))
-                            .wrap(Wrap { trim: true });
-                            frame.render_widget(warning, chunks[1]);
-                        } else {
-                            frame.render_widget(price_chart, chunks[1]);
-                        }
-                    }
-                    crate::app::ChartView::Volume => {
-                        chart.update_with_price_data(&volume_data, &[]);
-                        let mut volume_chart = render_volume_chart(&chart);
-                        volume_chart = volume_chart
-                            .block(Block::default().title("Volume (USD)").borders(Borders::ALL));
-                        frame.render_widget(volume_chart, chunks[1]);
-                    }
-                }
-            }
-        }
+    let mut title = vec![Span::raw(format!(
+        "My Positions ({}/{})",
+        table.rows.len(),
+        table.items.len()
+    ))];
+    if let Some(input) = &table.filter_input {
+        title.push(Span::styled(
+            format!(" filter: {}█", input),
+            Style::default().fg(theme.highlight_focused()),
+        ));
+    } else if !table.filter.is_empty() {
+        title.push(Span::styled(
+            format!(" filter: {}", table.filter_query),
+            Style::default().fg(theme.text_secondary()),
+        ));
+    }
+    if let Some(error) = &table.filter_error {
+        title.push(Span::styled(
+            format!(" {}", error),
+            Style::default().fg(theme.loss()),
+        ));
     }
 
-    // Render the time range tab bar
-    let tab_titles: Vec<Span> = ChartTimeRange::ALL
-        .iter()
-        .map(|range| {
-            if *range == chart_time_range {
-                Span::styled(
-                    range.as_str(),
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-                )
-            } else {
-                Span::styled(range.as_str(), Style::default().fg(Color::Cyan))
-            }
-        })
-        .collect();
-    let toggle_hint = match chart_view {
-        crate::app::ChartView::Price => "[v] Volume",
-        crate::app::ChartView::Volume => "[v] Price",
-    };
-    let tabs = ratatui::widgets::Tabs::new(tab_titles)
+    let table_widget = Table::new(rows, table.columns.iter().map(|column| column.width()))
+        .header(header)
+        .style(Style::default().fg(theme.text_normal()))
         .block(
             Block::default()
+                .title(Line::from(title))
                 .borders(Borders::ALL)
-                .title(format!("Range | Toggle: {}", toggle_hint)),
+                .border_style(Style::default().fg(theme.border_primary())),
         )
-        .highlight_style(
+        .row_highlight_style(
             Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )
-        .select(
-            ChartTimeRange::ALL
-                .iter()
-                .position(|r| *r == chart_time_range)
-                .unwrap_or(0),
+                .fg(theme.highlight_focused())
+                .add_modifier(Modifier::REVERSED),
         );
-    frame.render_widget(tabs, chunks[2]);
+
+    frame.render_stateful_widget(table_widget, chunks[0], &mut table.state);
+
+    // Render the chart for the selected position, filtered by time range and chart view
+    if let Some(selected) = table.selected_position() {
+        if let Some(pos) = positions.get(selected) {
+            render_position_chart(frame, chunks[1], pos, chart, theme);
+        }
+    }
+}
+
+/// Draws the chart of `pos`'s pool with the time range tab bar below it, and
+/// the position's range and entry over the price chart
+pub fn render_position_chart(
+    frame: &mut Frame,
+    area: Rect,
+    pos: &Position,
+    chart: &mut ChartState,
+    theme: &Theme,
+) {
+    render_pool_chart(
+        frame,
+        area,
+        &pos.pool,
+        (&pos.token0.symbol, &pos.token1.symbol),
+        RangeOverlay::new(pos).as_ref(),
+        chart,
+        theme,
+    );
 }
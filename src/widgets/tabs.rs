@@ -1,9 +1,11 @@
 use ratatui::{
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::Span,
     widgets::{Block, Borders, Tabs},
 };
 
+use crate::theme::Theme;
+
 pub struct TabsState<'a> {
     pub titles: Vec<&'a str>,
     pub index: usize,
@@ -25,7 +27,7 @@ impl<'a> TabsState<'a> {
         }
     }
 }
-pub fn render_tab_blocks<'a>(tabs: &TabsState<'a>) -> Tabs<'a> {
+pub fn render_tab_blocks<'a>(tabs: &TabsState<'a>, theme: &Theme) -> Tabs<'a> {
     // Tabs
     let titles: Vec<Vec<Span<'a>>> = tabs
         .titles
@@ -33,20 +35,25 @@ pub fn render_tab_blocks<'a>(tabs: &TabsState<'a>) -> Tabs<'a> {
         .map(|t| {
             let (first, rest) = t.split_at(1);
             vec![
-                Span::styled(first, Style::default().fg(Color::Yellow)),
-                Span::styled(rest, Style::default().fg(Color::Green)),
+                Span::styled(first, Style::default().fg(theme.text_primary())),
+                Span::styled(rest, Style::default().fg(theme.profit())),
             ]
         })
         .collect();
 
     let tab_blocks = Tabs::new(titles)
-        .block(Block::default().borders(Borders::ALL).title("Timeline"))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border_primary()))
+                .title("Timeline"),
+        )
         .select(tabs.index)
-        .style(Style::default().fg(Color::Cyan))
+        .style(Style::default().fg(theme.text_secondary()))
         .highlight_style(
             Style::default()
                 .add_modifier(Modifier::BOLD)
-                .bg(Color::Black),
+                .bg(theme.highlight_unfocused()),
         );
     tab_blocks
 }
@@ -0,0 +1,517 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, Utc};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Tabs, Wrap},
+    Frame,
+};
+
+use crate::{
+    models::position::Pool,
+    network::pools::TOP_POOLS,
+    theme::Theme,
+    util::format::format_usd,
+    widgets::{
+        columns::{compare, SortKey, SortOrder},
+        spinner::Spinner,
+    },
+};
+
+/// Fee tiers the leaderboard can be narrowed to
+pub const FEE_TIERS: [u32; 4] = [100, 500, 3000, 10000];
+
+/// A column of the top pools leaderboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolColumn {
+    Pair,
+    FeeTier,
+    Tvl,
+    Volume,
+    Fees,
+    FeeTvl,
+}
+
+impl PoolColumn {
+    pub const ALL: [PoolColumn; 6] = [
+        PoolColumn::Pair,
+        PoolColumn::FeeTier,
+        PoolColumn::Tvl,
+        PoolColumn::Volume,
+        PoolColumn::Fees,
+        PoolColumn::FeeTvl,
+    ];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            PoolColumn::Pair => "Pair",
+            PoolColumn::FeeTier => "Fee",
+            PoolColumn::Tvl => "TVL",
+            PoolColumn::Volume => "24h Volume",
+            PoolColumn::Fees => "24h Fees",
+            PoolColumn::FeeTvl => "Fees/TVL",
+        }
+    }
+
+    pub fn width(&self) -> Constraint {
+        match self {
+            PoolColumn::Pair => Constraint::Length(18),
+            PoolColumn::FeeTier => Constraint::Length(7),
+            PoolColumn::FeeTvl => Constraint::Length(10),
+            PoolColumn::Tvl | PoolColumn::Volume | PoolColumn::Fees => Constraint::Length(12),
+        }
+    }
+}
+
+/// A pool of the leaderboard with the figures it is ranked by
+#[derive(Debug, Clone)]
+pub struct PoolRow {
+    pub id: String,
+    pub pair: String,
+    pub fee_tier: Option<u32>,
+    pub tvl: Option<f64>,
+    pub volume: f64,
+    pub fees: Option<f64>,
+    /// 24h fees as a percentage of the value locked
+    pub fee_tvl: Option<f64>,
+}
+
+impl PoolRow {
+    fn new(pool: &Pool, now: f64) -> Self {
+        let tvl = pool
+            .total_value_locked_usd
+            .as_ref()
+            .and_then(|tvl| tvl.parse::<f64>().ok());
+        let volume = pool.volume_since(now - 60.0 * 60.0 * 24.0);
+        let fees = pool.fees_on(volume);
+        let fee_tvl = match (fees, tvl) {
+            (Some(fees), Some(tvl)) if tvl > 0.0 => Some(fees / tvl * 100.0),
+            _ => None,
+        };
+        Self {
+            id: pool.id.clone(),
+            pair: pool.pair().unwrap_or_else(|| pool.id.clone()),
+            fee_tier: pool.fee_tier(),
+            tvl,
+            volume,
+            fees,
+            fee_tvl,
+        }
+    }
+
+    fn key(&self, column: PoolColumn) -> SortKey {
+        let number = |value: Option<f64>| value.map_or(SortKey::Missing, SortKey::Number);
+        match column {
+            PoolColumn::Pair => SortKey::Text(self.pair.to_lowercase()),
+            PoolColumn::FeeTier => number(self.fee_tier.map(f64::from)),
+            PoolColumn::Tvl => number(self.tvl),
+            PoolColumn::Volume => SortKey::Number(self.volume),
+            PoolColumn::Fees => number(self.fees),
+            PoolColumn::FeeTvl => number(self.fee_tvl),
+        }
+    }
+
+    fn cell(&self, column: PoolColumn) -> String {
+        let usd = |value: Option<f64>| value.map_or("N/A".to_string(), format_usd);
+        match column {
+            PoolColumn::Pair => self.pair.clone(),
+            PoolColumn::FeeTier => format_fee_tier(self.fee_tier),
+            PoolColumn::Tvl => usd(self.tvl),
+            PoolColumn::Volume => format_usd(self.volume),
+            PoolColumn::Fees => usd(self.fees),
+            PoolColumn::FeeTvl => self
+                .fee_tvl
+                .map_or("N/A".to_string(), |ratio| format!("{:.3}%", ratio)),
+        }
+    }
+}
+
+fn format_fee_tier(fee_tier: Option<u32>) -> String {
+    fee_tier.map_or("All".to_string(), |fee| {
+        format!("{}%", fee as f64 / 10_000.0)
+    })
+}
+
+/// The leaderboard shown for a chain and fee tier
+pub type LeaderboardKey = (String, Option<u32>);
+
+struct Leaderboard {
+    fetched_at: DateTime<Local>,
+    rows: Vec<PoolRow>,
+}
+
+/// Filters, sorting and cached results of the top pools screen
+pub struct TopPools {
+    /// Chains with a subgraph, one tab each
+    pub chains: Vec<String>,
+    pub chain: String,
+    /// `None` ranks pools of every fee tier
+    pub fee_tier: Option<u32>,
+    pub sort: (PoolColumn, SortOrder),
+    pub state: TableState,
+    /// Why the last fetch of the shown leaderboard failed
+    pub error: Option<String>,
+    ttl: Duration,
+    cache: HashMap<LeaderboardKey, Leaderboard>,
+    pending: HashSet<LeaderboardKey>,
+}
+
+impl TopPools {
+    pub fn new(chains: Vec<String>, chain: &str, ttl_secs: u64) -> Self {
+        let mut state = TableState::default();
+        state.select(Some(0));
+        Self {
+            chains,
+            chain: chain.to_owned(),
+            fee_tier: None,
+            sort: (PoolColumn::Tvl, SortOrder::Descending),
+            state,
+            error: None,
+            ttl: Duration::seconds(ttl_secs as i64),
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+        }
+    }
+
+    fn key(&self) -> LeaderboardKey {
+        (self.chain.clone(), self.fee_tier)
+    }
+
+    /// The leaderboard to fetch, if the shown one is missing or older than the TTL
+    /// and not already being fetched
+    pub fn request(&mut self) -> Option<LeaderboardKey> {
+        let key = self.key();
+        let fresh = self
+            .cache
+            .get(&key)
+            .is_some_and(|board| Local::now() - board.fetched_at < self.ttl);
+        if fresh || self.pending.contains(&key) {
+            return None;
+        }
+        self.pending.insert(key.clone());
+        Some(key)
+    }
+
+    pub fn loaded(&mut self, key: LeaderboardKey, result: Result<Vec<Pool>>) {
+        self.pending.remove(&key);
+        let is_shown = key == self.key();
+        match result {
+            Ok(pools) => {
+                let now = Utc::now().timestamp() as f64;
+                let rows = pools.iter().map(|pool| PoolRow::new(pool, now)).collect();
+                self.cache.insert(
+                    key,
+                    Leaderboard {
+                        fetched_at: Local::now(),
+                        rows,
+                    },
+                );
+                if is_shown {
+                    self.error = None;
+                    self.clamp_selection();
+                }
+            }
+            Err(e) if is_shown => self.error = Some(format!("{:#}", e)),
+            Err(_) => {}
+        }
+    }
+
+    pub fn cycle_chain(&mut self) {
+        let i = self
+            .chains
+            .iter()
+            .position(|chain| *chain == self.chain)
+            .map_or(0, |i| (i + 1) % self.chains.len().max(1));
+        if let Some(chain) = self.chains.get(i) {
+            self.chain = chain.clone();
+        }
+        self.filter_changed();
+    }
+
+    /// All fee tiers, then each one in turn
+    pub fn cycle_fee_tier(&mut self) {
+        self.fee_tier = match self.fee_tier {
+            None => FEE_TIERS.first().copied(),
+            Some(fee) => FEE_TIERS
+                .iter()
+                .position(|f| *f == fee)
+                .and_then(|i| FEE_TIERS.get(i + 1))
+                .copied(),
+        };
+        self.filter_changed();
+    }
+
+    fn filter_changed(&mut self) {
+        self.error = None;
+        self.state.select(Some(0));
+    }
+
+    pub fn next_sort_column(&mut self) {
+        let (column, _) = self.sort;
+        let i = PoolColumn::ALL
+            .iter()
+            .position(|c| *c == column)
+            .map_or(0, |i| (i + 1) % PoolColumn::ALL.len());
+        self.sort = (PoolColumn::ALL[i], SortOrder::Descending);
+    }
+
+    pub fn reverse_sort(&mut self) {
+        self.sort.1 = self.sort.1.reversed();
+    }
+
+    /// Says the pools are the largest by TVL whatever they are sorted by
+    fn title(&self, len: usize) -> String {
+        let (column, order) = self.sort;
+        format!(
+            "Top {} Pools by TVL ({}), sorted by {} {}",
+            TOP_POOLS,
+            len,
+            column.header(),
+            order.arrow()
+        )
+    }
+
+    /// The shown leaderboard in sort order
+    fn rows(&self) -> Vec<&PoolRow> {
+        let Some(board) = self.cache.get(&self.key()) else {
+            return Vec::new();
+        };
+        let (column, order) = self.sort;
+        let mut rows: Vec<&PoolRow> = board.rows.iter().collect();
+        rows.sort_by(|a, b| compare(&a.key(column), &b.key(column), order));
+        rows
+    }
+
+    /// The chain and address of the selected pool
+    pub fn selected_pool(&self) -> Option<(String, String)> {
+        let rows = self.rows();
+        let row = rows.get(self.state.selected()?)?;
+        Some((self.chain.clone(), row.id.clone()))
+    }
+
+    pub fn next(&mut self) {
+        let len = self.rows().len();
+        if len > 0 {
+            let i = self.state.selected().map_or(0, |i| (i + 1) % len);
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let len = self.rows().len();
+        if len > 0 {
+            let i = self
+                .state
+                .selected()
+                .map_or(0, |i| if i == 0 { len - 1 } else { i - 1 });
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn first(&mut self) {
+        self.state.select(Some(0));
+    }
+
+    pub fn last(&mut self) {
+        let len = self.rows().len();
+        self.state.select(Some(len.saturating_sub(1)));
+    }
+
+    /// Moves the selection by `rows` without wrapping around
+    pub fn scroll_by(&mut self, rows: isize) {
+        let len = self.rows().len();
+        let i = self
+            .state
+            .selected()
+            .unwrap_or(0)
+            .saturating_add_signed(rows);
+        self.state.select(Some(i.min(len.saturating_sub(1))));
+    }
+
+    fn clamp_selection(&mut self) {
+        let len = self.rows().len();
+        let i = self.state.selected().unwrap_or(0);
+        self.state.select(Some(i.min(len.saturating_sub(1))));
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(area);
+        let filters = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(layout[0]);
+
+        let tabs = |titles: Vec<String>, selected: usize, title: &'static str| {
+            Tabs::new(titles)
+                .block(
+                    Block::default()
+                        .title(title)
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(theme.border_primary())),
+                )
+                .style(Style::default().fg(theme.text_secondary()))
+                .highlight_style(
+                    Style::default()
+                        .fg(theme.text_primary())
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                )
+                .select(selected)
+        };
+        let chain = self
+            .chains
+            .iter()
+            .position(|chain| *chain == self.chain)
+            .unwrap_or(0);
+        f.render_widget(tabs(self.chains.clone(), chain, "Chain [c]"), filters[0]);
+        let fee_tiers: Vec<Option<u32>> = std::iter::once(None)
+            .chain(FEE_TIERS.iter().copied().map(Some))
+            .collect();
+        let fee_tier = fee_tiers
+            .iter()
+            .position(|fee| *fee == self.fee_tier)
+            .unwrap_or(0);
+        f.render_widget(
+            tabs(
+                fee_tiers.into_iter().map(format_fee_tier).collect(),
+                fee_tier,
+                "Fee tier [t]",
+            ),
+            filters[1],
+        );
+
+        self.render_table(f, layout[1], theme);
+        self.render_status(f, layout[2], theme);
+    }
+
+    fn render_table(&mut self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let rows = self.rows();
+        let block = Block::default()
+            .title(self.title(rows.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_primary()));
+
+        if rows.is_empty() {
+            let message = if self.pending.contains(&self.key()) {
+                Line::styled(
+                    format!("{} Loading top pools...", Spinner::default().to_string()),
+                    Style::default().fg(theme.text_secondary()),
+                )
+            } else if let Some(error) = &self.error {
+                Line::styled(
+                    format!("Failed to load top pools: {}", error),
+                    Style::default().fg(theme.loss()),
+                )
+            } else {
+                Line::styled(
+                    "No pools found",
+                    Style::default().fg(theme.text_secondary()),
+                )
+            };
+            let paragraph = Paragraph::new(message)
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .block(block);
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let (sort_column, order) = self.sort;
+        let header = Row::new(PoolColumn::ALL.iter().map(|column| {
+            let title = if *column == sort_column {
+                format!("{} {}", column.header(), order.arrow())
+            } else {
+                column.header().to_string()
+            };
+            let alignment = if *column == PoolColumn::Pair {
+                Alignment::Left
+            } else {
+                Alignment::Right
+            };
+            Cell::from(Line::from(title).alignment(alignment))
+        }))
+        .style(
+            Style::default()
+                .fg(theme.text_primary())
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let table_rows: Vec<Row> = rows
+            .iter()
+            .map(|row| {
+                Row::new(PoolColumn::ALL.iter().map(|column| {
+                    let line = Line::from(row.cell(*column));
+                    Cell::from(if *column == PoolColumn::Pair {
+                        line
+                    } else {
+                        line.alignment(Alignment::Right)
+                    })
+                }))
+            })
+            .collect();
+
+        let table = Table::new(table_rows, PoolColumn::ALL.map(|column| column.width()))
+            .header(header)
+            .style(Style::default().fg(theme.text_normal()))
+            .row_highlight_style(
+                Style::default()
+                    .fg(theme.highlight_focused())
+                    .add_modifier(Modifier::REVERSED),
+            )
+            .highlight_symbol("> ")
+            .block(block);
+
+        f.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    fn render_status(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let key = self.key();
+        let mut spans = Vec::new();
+        if self.pending.contains(&key) {
+            spans.push(Span::raw(format!(
+                "{} Refreshing",
+                Spinner::default().to_string()
+            )));
+        } else if let Some(board) = self.cache.get(&key) {
+            spans.push(Span::raw(format!(
+                "Fetched {}",
+                board.fetched_at.format("%H:%M:%S")
+            )));
+        }
+        if let (Some(error), true) = (&self.error, self.cache.contains_key(&key)) {
+            spans.push(Span::styled(
+                format!(" · last refresh failed: {}", error),
+                Style::default().fg(theme.loss()),
+            ));
+        }
+
+        let status = Paragraph::new(Line::from(spans))
+            .style(Style::default().fg(theme.text_secondary()))
+            .alignment(Alignment::Right);
+        f.render_widget(status, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_says_what_the_pools_are_ranked_by() {
+        let mut top_pools = TopPools::new(vec!["mainnet".to_owned()], "mainnet", 300);
+        top_pools.sort = (PoolColumn::Volume, SortOrder::Descending);
+        assert_eq!(
+            top_pools.title(42),
+            "Top 100 Pools by TVL (42), sorted by 24h Volume ▼"
+        );
+    }
+}
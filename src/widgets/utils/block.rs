@@ -1,10 +1,15 @@
-use ratatui::style::{Color, Style};
+use ratatui::style::Style;
 use ratatui::text::Span;
 use ratatui::widgets::{Block, Borders};
 
-pub fn new(title: &str) -> Block {
+use crate::theme::Theme;
+
+pub fn new<'a>(title: &'a str, theme: &Theme) -> Block<'a> {
     Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Green))
-        .title(Span::styled(title, Style::default().fg(Color::White)))
+        .border_style(Style::default().fg(theme.border_primary()))
+        .title(Span::styled(
+            title,
+            Style::default().fg(theme.text_normal()),
+        ))
 }
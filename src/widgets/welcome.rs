@@ -2,10 +2,11 @@ use log::debug;
 use ratatui::{prelude::*, widgets::*};
 
 use super::enter_ens::{EnterENS, EnterEnsState};
-use crate::{app::App, routes::ActiveBlock};
+use crate::{app::App, routes::ActiveBlock, theme::Theme};
 
 pub fn render_welcome<'a>(
     rect: Rect,
+    theme: &Theme,
 ) -> (
     Paragraph<'a>,
     Paragraph<'a>,
@@ -45,18 +46,21 @@ pub fn render_welcome<'a>(
         })
         .text,
     ))
+    .style(Style::default().fg(theme.text_primary()))
     .wrap(Wrap { trim: false })
     .alignment(Alignment::Center);
 
     let details = Paragraph::new(vec![
         Line::from(
             Span::raw(format!("   {:<13}", "A CLI tool for querying Uniswap info",))
-                .fg(Color::White),
+                .fg(theme.text_normal()),
+        ),
+        Line::from(
+            Span::raw(format!("{:<13}: {}", "Author", "Chad Nehemiah")).fg(theme.text_normal()),
         ),
-        Line::from(Span::raw(format!("{:<13}: {}", "Author", "Chad Nehemiah")).fg(Color::White)),
         Line::from(
             Span::raw(format!(" {:<13}: {}", "Version", env!("CARGO_PKG_VERSION")))
-                .fg(Color::White),
+                .fg(theme.text_normal()),
         ),
     ])
     .block(Block::default())
@@ -64,10 +68,11 @@ pub fn render_welcome<'a>(
 
     // Create a message prompting the user to enter their ETH wallet or ENS
     let prompt_message = Paragraph::new("Please enter your ETH wallet or ENS")
+        .style(Style::default().fg(theme.text_secondary()))
         .alignment(Alignment::Center)
         .block(Block::default());
 
-    let ens_widget = EnterENS {};
+    let ens_widget = EnterENS { theme: *theme };
 
     (
         banner,